@@ -0,0 +1,186 @@
+//!
+//! Decoder for logs emitted by DeVIL contract.
+//!
+//! Backend uses these to keep its database in sync with vectors stored on
+//! DeVIL without polling every vector.
+//!
+
+use alloy_primitives::{Address, B256};
+use alloy_sol_types::SolEvent;
+use deli::events::{OperatorSet, ProgramExecuted, ProgramInstalled, VectorDeleted, VectorStored};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DevilEvent {
+    VectorStored {
+        id: u128,
+        hash: B256,
+        length: u128,
+    },
+    VectorDeleted {
+        id: u128,
+    },
+    ProgramExecuted {
+        program_hash: B256,
+        caller: Address,
+        gas_used: u64,
+    },
+    OperatorSet {
+        operator: Address,
+        enabled: bool,
+    },
+    ProgramInstalled {
+        name: B256,
+        id: u128,
+        version: u128,
+        num_inputs: u128,
+        num_outputs: u128,
+        num_registers: u128,
+    },
+}
+
+/// Decode raw log emitted by DeVIL
+///
+/// Returns `None` if log is not one of DeVIL events.
+pub fn decode_devil_log(topics: &[B256], data: &[u8]) -> Option<DevilEvent> {
+    let signature = topics.first()?;
+    let topics = topics.iter().copied();
+    let event = match *signature {
+        VectorStored::SIGNATURE_HASH => {
+            let e = VectorStored::decode_raw_log(topics, data, true).ok()?;
+            DevilEvent::VectorStored {
+                id: e.id,
+                hash: e.hash,
+                length: e.length,
+            }
+        }
+        VectorDeleted::SIGNATURE_HASH => {
+            let e = VectorDeleted::decode_raw_log(topics, data, true).ok()?;
+            DevilEvent::VectorDeleted { id: e.id }
+        }
+        ProgramExecuted::SIGNATURE_HASH => {
+            let e = ProgramExecuted::decode_raw_log(topics, data, true).ok()?;
+            DevilEvent::ProgramExecuted {
+                program_hash: e.program_hash,
+                caller: e.caller,
+                gas_used: e.gas_used,
+            }
+        }
+        OperatorSet::SIGNATURE_HASH => {
+            let e = OperatorSet::decode_raw_log(topics, data, true).ok()?;
+            DevilEvent::OperatorSet {
+                operator: e.operator,
+                enabled: e.enabled,
+            }
+        }
+        ProgramInstalled::SIGNATURE_HASH => {
+            let e = ProgramInstalled::decode_raw_log(topics, data, true).ok()?;
+            DevilEvent::ProgramInstalled {
+                name: e.name,
+                id: e.id,
+                version: e.version,
+                num_inputs: e.num_inputs,
+                num_outputs: e.num_outputs,
+                num_registers: e.num_registers,
+            }
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+impl std::fmt::Display for DevilEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VectorStored { id, hash, length } => {
+                write!(f, "VectorStored id={} hash={} length={}", id, hash, length)
+            }
+            Self::VectorDeleted { id } => write!(f, "VectorDeleted id={}", id),
+            Self::ProgramExecuted {
+                program_hash,
+                caller,
+                gas_used,
+            } => write!(
+                f,
+                "ProgramExecuted program_hash={} caller={} gas_used={}",
+                program_hash, caller, gas_used
+            ),
+            Self::OperatorSet { operator, enabled } => {
+                write!(f, "OperatorSet operator={} enabled={}", operator, enabled)
+            }
+            Self::ProgramInstalled {
+                name,
+                id,
+                version,
+                num_inputs,
+                num_outputs,
+                num_registers,
+            } => write!(
+                f,
+                "ProgramInstalled name={} id={} version={} num_inputs={} num_outputs={} num_registers={}",
+                name, id, version, num_inputs, num_outputs, num_registers
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_devil_log() {
+        let event = VectorStored {
+            id: 1002,
+            hash: B256::repeat_byte(0xab),
+            length: 48,
+        };
+        let log = event.encode_log_data();
+        assert_eq!(
+            decode_devil_log(log.topics(), &log.data),
+            Some(DevilEvent::VectorStored {
+                id: 1002,
+                hash: B256::repeat_byte(0xab),
+                length: 48
+            })
+        );
+
+        let event = ProgramExecuted {
+            program_hash: B256::repeat_byte(0xcd),
+            caller: Address::repeat_byte(0x11),
+            gas_used: 12345,
+        };
+        let log = event.encode_log_data();
+        assert_eq!(
+            decode_devil_log(log.topics(), &log.data),
+            Some(DevilEvent::ProgramExecuted {
+                program_hash: B256::repeat_byte(0xcd),
+                caller: Address::repeat_byte(0x11),
+                gas_used: 12345
+            })
+        );
+
+        let event = ProgramInstalled {
+            name: B256::repeat_byte(0xef),
+            id: 10,
+            version: 2,
+            num_inputs: 3,
+            num_outputs: 1,
+            num_registers: 4,
+        };
+        let log = event.encode_log_data();
+        assert_eq!(
+            decode_devil_log(log.topics(), &log.data),
+            Some(DevilEvent::ProgramInstalled {
+                name: B256::repeat_byte(0xef),
+                id: 10,
+                version: 2,
+                num_inputs: 3,
+                num_outputs: 1,
+                num_registers: 4
+            })
+        );
+
+        assert_eq!(decode_devil_log(&[B256::ZERO], &[]), None);
+        assert_eq!(decode_devil_log(&[], &[]), None);
+    }
+}
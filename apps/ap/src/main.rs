@@ -1,17 +1,20 @@
+use alloy_primitives::B256;
 use chrono::Utc;
 use clap::Parser;
-use deli::{amount::Amount, labels::Labels, log_msg, vector::Vector};
+use deli::log_msg;
 use ethers::{
     middleware::SignerMiddleware,
-    prelude::abigen,
     providers::{Http, Middleware, Provider},
     signers::{LocalWallet, Signer},
-    types::{Address, Filter, U256},
+    types::{Address, Filter},
 };
-use eyre::Context;
 use std::sync::Arc;
 use std::{env, str::FromStr};
 
+use crate::devil_events::decode_devil_log;
+
+mod devil_events;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -26,16 +29,23 @@ struct Cli {
 
     #[arg(long)]
     dimer_address: String,
+
+    /// Print decoded DeVIL logs from this contract address
+    #[arg(long)]
+    devil_address: Option<String>,
+
+    #[arg(long, default_value_t = 0)]
+    from_block: u64,
 }
 
 fn get_private_key() -> String {
     env::var("AP_PRIVATE_KEY").expect("AP_PRIVATE_KEY not found in environment")
 }
 
+#[allow(dead_code)]
 fn gen_unique_id() -> i64 {
     let now = Utc::now();
-    let value = now.timestamp_micros();
-    value
+    now.timestamp_micros()
 }
 
 pub const CT_STRATEGY: u8 = 1;
@@ -75,11 +85,24 @@ async fn main() -> eyre::Result<()> {
     let priv_key = get_private_key();
     let wallet = LocalWallet::from_str(&priv_key)?;
     let chain_id = provider.get_chainid().await?.as_u64();
-    let _client = Arc::new(SignerMiddleware::new(
+    let client = Arc::new(SignerMiddleware::new(
         provider,
         wallet.clone().with_chain_id(chain_id),
     ));
 
+    if let Some(devil_address) = cli.devil_address {
+        let devil_address: Address = devil_address.parse()?;
+        let filter = Filter::new()
+            .address(devil_address)
+            .from_block(cli.from_block);
+        for log in client.get_logs(&filter).await? {
+            let topics: Vec<B256> = log.topics.iter().map(|t| B256::from(t.0)).collect();
+            if let Some(event) = decode_devil_log(&topics, &log.data) {
+                println!("[{}] {}", log.block_number.unwrap_or_default(), event);
+            }
+        }
+    }
+
     log_msg!("Done.");
     Ok(())
 }
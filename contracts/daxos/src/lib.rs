@@ -5,17 +5,50 @@
 #[macro_use]
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
-use alloy_primitives::{Address, U128};
+use alloy_primitives::{Address, B256, U128, U256, U64, U8};
 use alloy_sol_types::{sol, SolCall};
-use deli::labels::Labels;
-use icore::vil::{execute_buy_order::execute_buy_order, update_supply::update_supply};
+use deli::{
+    amount::Amount,
+    labels::Labels,
+    vector::Vector,
+    vis::{namespace, NAMESPACE_MASK},
+};
+use icore::vil::names::{
+    ACCRUE_MANAGEMENT_FEE, BATCH_FILL_ORDER, BATCH_SUM_COLLATERAL, EXECUTE_BUY_BATCH,
+    EXECUTE_BUY_ORDER, EXECUTE_SELL_ORDER, MATCH_MARKET, SOLVE_QUADRATIC, UPDATE_SUPPLY,
+};
 use stylus_sdk::{
     prelude::*,
-    storage::{StorageAddress, StorageMap},
+    storage::{StorageAddress, StorageMap, StorageU128, StorageU256, StorageU64, StorageU8},
 };
 
+/// Result of last order execution on DeVIL, i.e. [Capped Index Quantity, Remaining Index Quantity]
+const EXECUTED_INDEX_QUANTITIES_ID: u128 = 202;
+
+/// Result of last order execution on DeVIL, i.e. [Asset Quantity; num_assets]
+const EXECUTED_ASSET_QUANTITIES_ID: u128 = 203;
+
+/// Result of last batch execution on DeVIL, i.e. [Filled Index Quantity; num_orders]
+const EXECUTED_ORDER_QUANTITIES_ID: u128 = 204;
+
+/// Vault was set up, and it awaits DAO vote to become active
+const VAULT_PROPOSED: u8 = 1;
+
+/// Vault accepts orders, and executes them in batch
+const VAULT_ACTIVE: u8 = 2;
+
+/// Vault was paused, and it does not accept or execute buy orders until
+/// activated again
+const VAULT_PAUSED: u8 = 3;
+
+/// Vault was retired by DAO, and it cannot be activated again
+///
+/// Only cancelling buy orders, selling ITP, and accruing and sweeping fees
+/// remain open, so that users can exit and custody can be settled.
+const VAULT_RETIRED: u8 = 4;
+
 sol! {
     /// Vector IL (VIL) virtual machine
     ///
@@ -30,11 +63,29 @@ sol! {
     interface IDevil  {
         function setup(address owner) external;
 
+        function setOperator(address operator, bool enabled) external;
+
         function submit(uint128 id, uint8[] memory data) external;
 
+        function submitMany(uint128[] memory ids, uint8[][] memory data) external;
+
+        function update(uint128 id, uint8[] memory data, bytes32 expected_hash) external;
+
+        function delete(uint128 id) external;
+
         function get(uint128 id) external view returns (uint8[] memory);
 
+        function installProgram(bytes32 name, uint128 id, uint8[] memory code, uint128 num_inputs, uint128 num_outputs, uint128 num_registers) external returns (uint128);
+
+        function getProgram(bytes32 name) external view returns (uint128, uint128);
+
+        function getProgramVersion(bytes32 name, uint128 version) external view returns (uint128);
+
+        function getProgramArity(uint128 id) external view returns (uint128, uint128, uint128);
+
         function execute(uint8[] memory code, uint128 num_registry) external;
+
+        function executeStored(uint128 program_id, uint128[] memory args) external;
     }
 
     /// Market monitors supply and demand for assets
@@ -53,7 +104,13 @@ sol! {
     interface IMarket  {
         function setup(address owner, address devil) external;
 
-        function submitSupply() external;
+        function submitAssets(uint8[] memory assets) external;
+
+        function setVendor(address vendor, bool enabled) external;
+
+        function submitSupply(address vendor) external;
+
+        function getAssets() external view returns (uint128);
 
         function getSupply() external view returns (uint128, uint128);
 
@@ -68,6 +125,15 @@ sol! {
         function getSlopes() external view returns (uint128);
     }
 
+    /// Collateral token, e.g. USDC
+    interface IERC20  {
+        function decimals() external view returns (uint8);
+
+        function transfer(address to, uint256 value) external returns (bool);
+
+        function transferFrom(address from, address to, uint256 value) external returns (bool);
+    }
+
     /// Vault (a.k.a. Index) tracks its price and orders
     ///
     /// Vault stores:
@@ -80,18 +146,82 @@ sol! {
     /// only organises handles to those vectors and submits VIL programs to
     /// execute.
     interface IVault  {
-        function setup(address owner, address devil) external;
+        function setup(address owner, address devil, string memory name, string memory symbol) external;
+
+        function submitIndex(uint8[] memory assets, uint8[] memory weights, uint8[] memory fees) external;
+
+        function setFees(uint8[] memory fees) external;
+
+        function submitOrder(address user, uint128 collateral_amount) external returns (uint128);
 
-        function submitOrder(address user, uint128 collateral_amount) external;
+        function submitSellOrder(address user, uint128 quantity) external returns (uint128);
+
+        function mint(address to, uint256 value) external;
+
+        function burn(address from, uint256 value) external;
+
+        function totalSupply() external view returns (uint256);
+
+        function enqueueOrder(address user) external;
+
+        function dequeueOrder(address user) external;
+
+        function cancelOrder(address user) external returns (uint128);
+
+        function clearQueue() external;
 
         function getQueue() external view returns (uint128);
 
+        function getOrderUser(uint128 order_id) external view returns (address);
+
         function getAssets() external view returns (uint128);
 
         function getWeights() external view returns (uint128);
 
         function getQuote() external view returns (uint128);
+
+        function getFees() external view returns (uint128);
+
+        function getSupply() external view returns (uint128);
+
+        function getAccruedFees() external view returns (uint128);
     }
+
+    event OrderQueued(uint128 indexed index, address indexed user, uint128 remaining_quantity);
+
+    event OrderCancelled(uint128 indexed index, address indexed user, uint128 collateral_amount);
+
+    event GovernorSet(address indexed governor);
+
+    event VaultStateChanged(uint128 indexed index, uint8 state);
+
+    event FeesSwept(uint128 indexed index, uint128 entry_fees, uint128 exit_fees, uint128 management_fees);
+}
+
+/// Ids of Vault vectors on DeVIL
+#[storage]
+pub struct VaultIds {
+    assets: StorageU128,
+    weights: StorageU128,
+    quote: StorageU128,
+    fees: StorageU128,
+    supply: StorageU128,
+    accrued_fees: StorageU128,
+}
+
+/// Ids of Market vectors on DeVIL
+#[storage]
+pub struct MarketIds {
+    assets: StorageU128,
+    supply_long: StorageU128,
+    supply_short: StorageU128,
+    demand_long: StorageU128,
+    demand_short: StorageU128,
+    delta_long: StorageU128,
+    delta_short: StorageU128,
+    liquidity: StorageU128,
+    prices: StorageU128,
+    slopes: StorageU128,
 }
 
 #[storage]
@@ -101,6 +231,14 @@ pub struct Daxos {
     devil: StorageAddress,
     market: StorageAddress,
     vaults: StorageMap<U128, StorageAddress>,
+    last_accrual: StorageMap<U128, StorageU64>,
+    vault_ids: StorageMap<U128, VaultIds>,
+    market_ids: MarketIds,
+    collateral: StorageAddress,
+    collateral_decimals: StorageU8,
+    custody: StorageMap<U128, StorageU256>, // Collateral held for each Vault
+    governor: StorageAddress,               // DAO governing Vault lifecycle
+    vault_states: StorageMap<U128, StorageU8>,
 }
 
 impl Daxos {
@@ -112,15 +250,234 @@ impl Daxos {
         Ok(())
     }
 
-    fn send_to_devil(&mut self, code: Vec<u128>, num_registry: u128) -> Result<(), Vec<u8>> {
-        let devil_call = IDevil::executeCall {
-            code: Labels { data: code }.to_vec(),
-            num_registry,
+    fn check_governor(&self, address: Address) -> Result<(), Vec<u8>> {
+        let governor = self.governor.get();
+        if governor.is_zero() || address != governor {
+            Err(b"Must be governor")?;
+        }
+        Ok(())
+    }
+
+    /// Move Vault into new state, given it is currently in one of allowed states
+    fn set_vault_state(&mut self, index: U128, allowed: &[u8], state: u8) -> Result<(), Vec<u8>> {
+        let mut state_access = self.vault_states.setter(index);
+        if !allowed.contains(&state_access.get().to::<u8>()) {
+            Err(b"Invalid Vault State")?;
+        }
+        state_access.set(U8::from(state));
+        log(
+            self.vm(),
+            VaultStateChanged {
+                index: index.to(),
+                state,
+            },
+        );
+        Ok(())
+    }
+
+    fn query<C: SolCall>(&self, address: Address, call: C) -> Result<C::Return, Vec<u8>> {
+        let result = self.vm().static_call(&self, address, &call.abi_encode())?;
+        C::abi_decode_returns(&result, true).map_err(|_| b"Invalid response".to_vec())
+    }
+
+    fn get_vault(&self, index: U128) -> Result<Address, Vec<u8>> {
+        let vault_address = self.vaults.getter(index).get();
+        if vault_address.is_zero() {
+            Err(b"Vault Not Found")?;
+        }
+        Ok(vault_address)
+    }
+
+    /// Get ids of Vault vectors, i.e. [Assets, Weights, Quote, Fees, Supply,
+    /// Accrued Fees]
+    ///
+    /// Vault never changes these ids once set up, so they are cached.
+    fn get_vault_ids(&mut self, index: U128, vault_address: Address) -> Result<[u128; 6], Vec<u8>> {
+        let cached = self.vault_ids.getter(index);
+        let ids = [
+            cached.assets.get(),
+            cached.weights.get(),
+            cached.quote.get(),
+            cached.fees.get(),
+            cached.supply.get(),
+            cached.accrued_fees.get(),
+        ]
+        .map(|id| id.to::<u128>());
+        if !ids.contains(&0) {
+            return Ok(ids);
+        }
+        let ids = [
+            self.query(vault_address, IVault::getAssetsCall {})?._0,
+            self.query(vault_address, IVault::getWeightsCall {})?._0,
+            self.query(vault_address, IVault::getQuoteCall {})?._0,
+            self.query(vault_address, IVault::getFeesCall {})?._0,
+            self.query(vault_address, IVault::getSupplyCall {})?._0,
+            self.query(vault_address, IVault::getAccruedFeesCall {})?._0,
+        ];
+        if ids.contains(&0) {
+            Err(b"Vault Not Ready")?;
+        }
+        let mut cached = self.vault_ids.setter(index);
+        cached.assets.set(U128::from(ids[0]));
+        cached.weights.set(U128::from(ids[1]));
+        cached.quote.set(U128::from(ids[2]));
+        cached.fees.set(U128::from(ids[3]));
+        cached.supply.set(U128::from(ids[4]));
+        cached.accrued_fees.set(U128::from(ids[5]));
+        Ok(ids)
+    }
+
+    /// Get ids of Market vectors, i.e. [Assets, Supply Long, Supply Short,
+    /// Demand Long, Demand Short, Delta Long, Delta Short, Liquidity, Prices,
+    /// Slopes]
+    ///
+    /// Market never changes these ids once set up, so they are cached until
+    /// Daxos is set up with another Market.
+    fn get_market_ids(&mut self) -> Result<[u128; 10], Vec<u8>> {
+        let cached = &self.market_ids;
+        let ids = [
+            cached.assets.get(),
+            cached.supply_long.get(),
+            cached.supply_short.get(),
+            cached.demand_long.get(),
+            cached.demand_short.get(),
+            cached.delta_long.get(),
+            cached.delta_short.get(),
+            cached.liquidity.get(),
+            cached.prices.get(),
+            cached.slopes.get(),
+        ]
+        .map(|id| id.to::<u128>());
+        if !ids.contains(&0) {
+            return Ok(ids);
+        }
+        let market_address = self.market.get();
+        let assets = self.query(market_address, IMarket::getAssetsCall {})?;
+        let supply = self.query(market_address, IMarket::getSupplyCall {})?;
+        let demand = self.query(market_address, IMarket::getDemandCall {})?;
+        let delta = self.query(market_address, IMarket::getDeltaCall {})?;
+        let liquidity = self.query(market_address, IMarket::getLiquidityCall {})?;
+        let prices = self.query(market_address, IMarket::getPricesCall {})?;
+        let slopes = self.query(market_address, IMarket::getSlopesCall {})?;
+        let ids = [
+            assets._0,
+            supply._0,
+            supply._1,
+            demand._0,
+            demand._1,
+            delta._0,
+            delta._1,
+            liquidity._0,
+            prices._0,
+            slopes._0,
+        ];
+        if ids.contains(&0) {
+            Err(b"Market Not Ready")?;
+        }
+        let cached = &mut self.market_ids;
+        cached.assets.set(U128::from(ids[0]));
+        cached.supply_long.set(U128::from(ids[1]));
+        cached.supply_short.set(U128::from(ids[2]));
+        cached.demand_long.set(U128::from(ids[3]));
+        cached.demand_short.set(U128::from(ids[4]));
+        cached.delta_long.set(U128::from(ids[5]));
+        cached.delta_short.set(U128::from(ids[6]));
+        cached.liquidity.set(U128::from(ids[7]));
+        cached.prices.set(U128::from(ids[8]));
+        cached.slopes.set(U128::from(ids[9]));
+        Ok(ids)
+    }
+
+    /// Get id of latest version of stored procedure on DeVIL
+    ///
+    /// Not cached, as procedures may be upgraded by installing new version.
+    fn get_program_id(&self, name: [u8; 32]) -> Result<u128, Vec<u8>> {
+        let get_program = IDevil::getProgramCall { name: name.into() };
+        Ok(self.query(self.devil.get(), get_program)?._0)
+    }
+
+    /// Execute latest version of stored procedure on DeVIL
+    ///
+    /// Procedure code lives on DeVIL, and we only send ids of vectors it
+    /// operates on as arguments.
+    fn send_to_devil(&mut self, name: [u8; 32], args: Vec<u128>) -> Result<(), Vec<u8>> {
+        let devil_address = self.devil.get();
+        let devil_call = IDevil::executeStoredCall {
+            program_id: self.get_program_id(name)?,
+            args,
         };
         self.vm()
-            .call(&self, self.devil.get(), &devil_call.abi_encode())?;
+            .call(&self, devil_address, &devil_call.abi_encode())?;
         Ok(())
     }
+
+    /// Read executed index quantities, i.e. (Capped Index Quantity, Remaining Index Quantity)
+    fn get_executed_index_quantities(&self) -> Result<(Amount, Amount), Vec<u8>> {
+        let executed = self.get_vector(EXECUTED_INDEX_QUANTITIES_ID)?;
+        let [capped_quantity, remaining_quantity, ..] = executed.data[..] else {
+            Err(b"Invalid executed quantities")?
+        };
+        Ok((capped_quantity, remaining_quantity))
+    }
+
+    fn get_vector(&self, id: u128) -> Result<Vector, Vec<u8>> {
+        let get = IDevil::getCall { id };
+        Ok(Vector::from_vec(self.query(self.devil.get(), get)?._0))
+    }
+
+    /// Read index supply tracked on DeVIL
+    fn get_supply(&self, supply_id: u128) -> Result<Amount, Vec<u8>> {
+        let supply = self.get_vector(supply_id)?;
+        let [supply] = supply.data[..] else {
+            Err(b"Invalid supply")?
+        };
+        Ok(supply)
+    }
+
+    /// Check that ITP Token supply of the Vault matches index supply
+    ///
+    /// VIL programs update supply vector on DeVIL, while Vault mints and burns
+    /// ITP Token, so that both must agree once order is executed.
+    fn check_supply(&self, vault_address: Address, supply: Amount) -> Result<(), Vec<u8>> {
+        let total_supply = self.query(vault_address, IVault::totalSupplyCall {})?._0;
+        if total_supply != U256::from(supply.to_u128_raw()) {
+            Err(b"Supply mismatch")?;
+        }
+        Ok(())
+    }
+
+    /// Convert amount of collateral into collateral token units
+    fn amount_to_collateral(&self, amount: Amount) -> U256 {
+        let decimals = U256::from(self.collateral_decimals.get());
+        amount.to_u256() * U256::from(10).pow(decimals) / Amount::u256_scale()
+    }
+
+    /// Convert collateral token units into amount of collateral
+    fn collateral_to_amount(&self, value: u128) -> Amount {
+        Amount::from_u128_with_scale(value, self.collateral_decimals.get().to())
+    }
+
+    fn transfer_collateral(&mut self, calldata: Vec<u8>) -> Result<(), Vec<u8>> {
+        let result = self.vm().call(&self, self.collateral.get(), &calldata)?;
+        let success = IERC20::transferCall::abi_decode_returns(&result, true)
+            .map_err(|_| b"Transfer failed".to_vec())?;
+        if !success._0 {
+            Err(b"Transfer failed")?;
+        }
+        Ok(())
+    }
+
+    /// Pay collateral held for the Vault out to the user
+    fn pay_out(&mut self, index: U128, to: Address, value: U256) -> Result<(), Vec<u8>> {
+        let mut custody_access = self.custody.setter(index);
+        let custody = custody_access.get();
+        if custody < value {
+            Err(b"Insufficient custody")?;
+        }
+        custody_access.set(custody - value);
+        let transfer = IERC20::transferCall { to, value };
+        self.transfer_collateral(transfer.abi_encode())
+    }
 }
 
 #[public]
@@ -130,19 +487,37 @@ impl Daxos {
         owner: Address,
         devil: Address,
         market: Address,
+        collateral: Address,
     ) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().tx_origin())?;
         self.owner.set(owner);
         self.devil.set(devil);
         self.market.set(market);
+        let decimals = self.query(collateral, IERC20::decimalsCall {})?._0;
+        self.collateral.set(collateral);
+        self.collateral_decimals.set(U8::from(decimals));
+        // Market ids are fetched again from new Market
+        self.market_ids.assets.set(U128::ZERO);
+        Ok(())
+    }
+
+    /// Set DAO governor, which may activate and retire Vaults
+    pub fn set_governor(&mut self, governor: Address) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        self.governor.set(governor);
+        log(self.vm(), GovernorSet { governor });
         Ok(())
     }
 
     /// Issuer has deployed Vault contract and now we need to set it up
+    ///
+    /// Vault stays proposed until DAO votes to activate it.
     pub fn setup_vault(
         &mut self,
         vault_id: U128,
-        vault_address: Address, /* ... setup params ...*/
+        vault_address: Address,
+        name: String,
+        symbol: String,
     ) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().tx_origin())?;
         let mut vault_access = self.vaults.setter(vault_id);
@@ -155,50 +530,138 @@ impl Daxos {
         let vault_setup = IVault::setupCall {
             owner: me,
             devil: devil_address,
-            /* ...setup params... */
+            name,
+            symbol,
         };
         self.vm()
             .call(&self, vault_address, &vault_setup.abi_encode())?;
+
+        // Vault manages its order queue on DeVIL
+        let set_operator = IDevil::setOperatorCall {
+            operator: vault_address,
+            enabled: true,
+        };
+        self.vm()
+            .call(&self, devil_address, &set_operator.abi_encode())?;
+        self.set_vault_state(vault_id, &[0], VAULT_PROPOSED)
+    }
+
+    /// Submit index of proposed Vault, i.e. asset names, their weights and fees
+    /// (Entry Fee, Exit Fee, Management Fee)
+    ///
+    /// Vault stores them on DeVIL under ids it allocated in its namespace.
+    pub fn submit_vault_index(
+        &mut self,
+        index: U128,
+        assets: Vec<u8>,
+        weights: Vec<u8>,
+        fees: Vec<u8>,
+    ) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let vault_address = self.get_vault(index)?;
+        if self.get_vault_state(index) != VAULT_PROPOSED {
+            Err(b"Invalid Vault State")?;
+        }
+        let submit = IVault::submitIndexCall {
+            assets,
+            weights,
+            fees,
+        };
+        self.vm().call(&self, vault_address, &submit.abi_encode())?;
         Ok(())
     }
 
+    /// Set fees of the Vault (Entry Fee, Exit Fee, Management Fee)
+    ///
+    /// Management fee is accrued at current rate first, and new fees apply
+    /// from now on.
+    pub fn set_vault_fees(&mut self, index: U128, fees: Vec<u8>) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let vault_address = self.get_vault(index)?;
+        self.accrue_fees(index)?;
+        let set_fees = IVault::setFeesCall { fees };
+        self.vm()
+            .call(&self, vault_address, &set_fees.abi_encode())?;
+        Ok(())
+    }
+
+    /// DAO has voted to activate Vault, or to resume paused Vault
+    pub fn activate_vault(&mut self, index: U128) -> Result<(), Vec<u8>> {
+        self.check_governor(self.vm().msg_sender())?;
+        self.set_vault_state(index, &[VAULT_PROPOSED, VAULT_PAUSED], VAULT_ACTIVE)
+    }
+
+    /// Pause active Vault, so that it does not accept new orders
+    pub fn pause_vault(&mut self, index: U128) -> Result<(), Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        if self.check_governor(sender).is_err() {
+            self.check_owner(sender)?;
+        }
+        self.set_vault_state(index, &[VAULT_ACTIVE], VAULT_PAUSED)
+    }
+
+    /// DAO has voted to retire Vault
+    ///
+    /// Users may still cancel their orders and sell ITP of retired Vault, and
+    /// owner may still accrue and sweep its fees, while no buy orders are
+    /// submitted or executed in batch.
+    pub fn retire_vault(&mut self, index: U128) -> Result<(), Vec<u8>> {
+        self.check_governor(self.vm().msg_sender())?;
+        self.set_vault_state(
+            index,
+            &[VAULT_PROPOSED, VAULT_ACTIVE, VAULT_PAUSED],
+            VAULT_RETIRED,
+        )
+    }
+
+    /// Get Vault state, i.e. Proposed (1), Active (2), Paused (3) or Retired (4)
+    pub fn get_vault_state(&self, index: U128) -> u8 {
+        self.vault_states.get(index).to()
+    }
+
+    /// Submit buy order, where collateral amount is in collateral token units
+    ///
+    /// Collateral is taken into custody of Daxos, and it must be approved by
+    /// the user beforehand.
     pub fn submit_order(&mut self, index: U128, collateral_amount: u128) -> Result<(), Vec<u8>> {
         let user = self.vm().msg_sender();
-        let vault_access = self.vaults.getter(index);
-        let vault_address = vault_access.get();
-        if vault_address.is_zero() {
-            Err(b"Vault Not Found")?;
+        let vault_address = self.get_vault(index)?;
+        if self.get_vault_state(index) != VAULT_ACTIVE {
+            Err(b"Vault Not Active")?;
         }
+        let value = U256::from(collateral_amount);
+        let transfer_from = IERC20::transferFromCall {
+            from: user,
+            to: self.vm().contract_address(),
+            value,
+        };
+        self.transfer_collateral(transfer_from.abi_encode())?;
+
         let submit = IVault::submitOrderCall {
             user,
-            collateral_amount,
+            collateral_amount: self.collateral_to_amount(collateral_amount).to_u128_raw(),
         };
-        self.vm().call(&self, vault_address, &submit.abi_encode())?;
+        let result = self.vm().call(&self, vault_address, &submit.abi_encode())?;
+        let index_order_id = IVault::submitOrderCall::abi_decode_returns(&result, true)
+            .map_err(|_| b"Invalid order".to_vec())?
+            ._0;
 
-        // TODO: We need to set these up. They are from Vault and Market.
-        let index_order_id = 10001;
-        let executed_asset_quantities_id = 10002;
-        let executed_index_quantities_id = 10003;
-        let asset_names_id = 1001;
-        let weights_id = 1002;
-        let quote_id = 1003;
-        let market_asset_names_id = 101;
-        let supply_long_id = 102;
-        let supply_short_id = 103;
-        let demand_long_id = 104;
-        let demand_short_id = 105;
-        let delta_long_id = 106;
-        let delta_short_id = 107;
-        let solve_quadratic_id = 10;
-
-        // TODO: get those from Vault and Market
-        let update = execute_buy_order(
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] =
+            self.get_vault_ids(index, vault_address)?;
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, ..] =
+            self.get_market_ids()?;
+        let solve_quadratic_id = self.get_program_id(SOLVE_QUADRATIC)?;
+        let match_market_id = self.get_program_id(MATCH_MARKET)?;
+
+        let args = vec![
             index_order_id,
-            executed_index_quantities_id,
-            executed_asset_quantities_id,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            EXECUTED_ASSET_QUANTITIES_ID,
             asset_names_id,
             weights_id,
             quote_id,
+            fees_id,
+            supply_id,
             market_asset_names_id,
             supply_long_id,
             supply_short_id,
@@ -206,24 +669,289 @@ impl Daxos {
             demand_short_id,
             delta_long_id,
             delta_short_id,
+            accrued_fees_id,
             solve_quadratic_id,
+            match_market_id,
+        ];
+        self.send_to_devil(EXECUTE_BUY_ORDER, args)?;
+
+        // ITP Token is minted for filled part of the order
+        let (capped_quantity, remaining_quantity) = self.get_executed_index_quantities()?;
+        if capped_quantity != Amount::ZERO {
+            let mint = IVault::mintCall {
+                to: user,
+                value: U256::from(capped_quantity.to_u128_raw()),
+            };
+            self.vm().call(&self, vault_address, &mint.abi_encode())?;
+        }
+        self.check_supply(vault_address, self.get_supply(supply_id)?)?;
+
+        // Order was partially filled, and remainder waits in the queue to be
+        // executed in next batch, e.g. once quote is updated. Fully filled
+        // order leaves the queue, as it may have been queued before.
+        if remaining_quantity == Amount::ZERO {
+            let dequeue = IVault::dequeueOrderCall { user };
+            self.vm()
+                .call(&self, vault_address, &dequeue.abi_encode())?;
+        } else {
+            let enqueue = IVault::enqueueOrderCall { user };
+            self.vm()
+                .call(&self, vault_address, &enqueue.abi_encode())?;
+            log(
+                self.vm(),
+                OrderQueued {
+                    index: index.to(),
+                    user,
+                    remaining_quantity: remaining_quantity.to_u128_raw(),
+                },
+            );
+        }
+
+        // Collateral is held until spent, refunded or paid out
+        let mut custody_access = self.custody.setter(index);
+        let custody = custody_access.get();
+        custody_access.set(custody + value);
+        Ok(())
+    }
+
+    /// Cancel remaining (unfilled) part of the order
+    ///
+    /// Returns collateral amount, which was not spent.
+    pub fn cancel_order(&mut self, index: U128) -> Result<u128, Vec<u8>> {
+        let user = self.vm().msg_sender();
+        let vault_address = self.get_vault(index)?;
+        let cancel = IVault::cancelOrderCall { user };
+        let result = self.vm().call(&self, vault_address, &cancel.abi_encode())?;
+        let remaining = IVault::cancelOrderCall::abi_decode_returns(&result, true)
+            .map_err(|_| b"Invalid order".to_vec())?
+            ._0;
+        let refund = self.amount_to_collateral(Amount::from_u128_raw(remaining));
+        self.pay_out(index, user, refund)?;
+        let collateral_amount = refund.to::<u128>();
+        log(
+            self.vm(),
+            OrderCancelled {
+                index: index.to(),
+                user,
+                collateral_amount,
+            },
         );
-        let num_registry = 16;
-        self.send_to_devil(update, num_registry)?;
+        Ok(collateral_amount)
+    }
+
+    /// Submit sell order for ITP quantity
+    ///
+    /// Order is filled up to capacity, and filled quantity is burned, while
+    /// collateral returned is paid out to the user. Sell order is not kept
+    /// open, so that the user keeps unfilled quantity, and may sell it again.
+    ///
+    /// Returns collateral paid out in collateral token units.
+    pub fn submit_sell_order(&mut self, index: U128, quantity: u128) -> Result<u128, Vec<u8>> {
+        let user = self.vm().msg_sender();
+        let vault_address = self.get_vault(index)?;
+        let submit = IVault::submitSellOrderCall { user, quantity };
+        let result = self.vm().call(&self, vault_address, &submit.abi_encode())?;
+        let sell_order_id = IVault::submitSellOrderCall::abi_decode_returns(&result, true)
+            .map_err(|_| b"Invalid order".to_vec())?
+            ._0;
+
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] =
+            self.get_vault_ids(index, vault_address)?;
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, ..] =
+            self.get_market_ids()?;
+        let match_market_id = self.get_program_id(MATCH_MARKET)?;
+
+        let args = vec![
+            sell_order_id,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            EXECUTED_ASSET_QUANTITIES_ID,
+            asset_names_id,
+            weights_id,
+            quote_id,
+            fees_id,
+            supply_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            accrued_fees_id,
+            match_market_id,
+        ];
+        self.send_to_devil(EXECUTE_SELL_ORDER, args)?;
+
+        let executed = self.get_vector(EXECUTED_INDEX_QUANTITIES_ID)?;
+        let [capped_quantity, _, collateral_returned] = executed.data[..] else {
+            Err(b"Invalid executed quantities")?
+        };
+        let burn = IVault::burnCall {
+            from: user,
+            value: U256::from(capped_quantity.to_u128_raw()),
+        };
+        self.vm().call(&self, vault_address, &burn.abi_encode())?;
+        self.check_supply(vault_address, self.get_supply(supply_id)?)?;
+
+        let payout = self.amount_to_collateral(collateral_returned);
+        self.pay_out(index, user, payout)?;
+        Ok(payout.to())
+    }
+
+    /// Execute all pending orders in the Vault queue as one batch
+    ///
+    /// Keeper clears the queue in one transaction, when instant fill is
+    /// throttled. All orders are executed against current quote, and filled
+    /// pro-rata when their total exceeds capacity. Queue is only executed
+    /// while Vault is active, and queued orders may be cancelled otherwise.
+    pub fn execute_batch(&mut self, index: U128) -> Result<(), Vec<u8>> {
+        let vault_address = self.get_vault(index)?;
+        if self.get_vault_state(index) != VAULT_ACTIVE {
+            Err(b"Vault Not Active")?;
+        }
+        // Queue is allocated with first queued order, so it is not cached
+        let queue_id = self.query(vault_address, IVault::getQueueCall {})?._0;
+        if queue_id == 0 {
+            Err(b"Queue Empty")?;
+        }
+
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] =
+            self.get_vault_ids(index, vault_address)?;
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, ..] =
+            self.get_market_ids()?;
+        let solve_quadratic_id = self.get_program_id(SOLVE_QUADRATIC)?;
+        let match_market_id = self.get_program_id(MATCH_MARKET)?;
+        let batch_sum_collateral_id = self.get_program_id(BATCH_SUM_COLLATERAL)?;
+        let batch_fill_order_id = self.get_program_id(BATCH_FILL_ORDER)?;
+
+        let args = vec![
+            queue_id,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            EXECUTED_ASSET_QUANTITIES_ID,
+            EXECUTED_ORDER_QUANTITIES_ID,
+            asset_names_id,
+            weights_id,
+            quote_id,
+            fees_id,
+            supply_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            accrued_fees_id,
+            solve_quadratic_id,
+            match_market_id,
+            batch_sum_collateral_id,
+            batch_fill_order_id,
+        ];
+        self.send_to_devil(EXECUTE_BUY_BATCH, args)?;
+
+        // ITP Token is minted to owner of each order filled in the batch
+        let get_queue = IDevil::getCall { id: queue_id };
+        let queue = Labels::from_vec(self.query(self.devil.get(), get_queue)?._0);
+        let filled = self.get_vector(EXECUTED_ORDER_QUANTITIES_ID)?;
+        if queue.data.len() != filled.data.len() {
+            Err(b"Invalid executed quantities")?;
+        }
+        for (order_id, quantity) in queue.data.into_iter().zip(filled.data) {
+            if quantity == Amount::ZERO {
+                continue;
+            }
+            let user = self
+                .query(vault_address, IVault::getOrderUserCall { order_id })?
+                ._0;
+            let mint = IVault::mintCall {
+                to: user,
+                value: U256::from(quantity.to_u128_raw()),
+            };
+            self.vm().call(&self, vault_address, &mint.abi_encode())?;
+        }
+        self.check_supply(vault_address, self.get_supply(supply_id)?)?;
+
+        // All orders were fully filled, otherwise they stay in the queue
+        let (_, remaining_quantity) = self.get_executed_index_quantities()?;
+        if remaining_quantity == Amount::ZERO {
+            let clear_queue = IVault::clearQueueCall {};
+            self.vm()
+                .call(&self, vault_address, &clear_queue.abi_encode())?;
+        }
         Ok(())
     }
 
-    pub fn submit_supply(&mut self) -> Result<(), Vec<u8>> {
+    /// Set up Market with initial sorted asset names
+    ///
+    /// Market becomes operator of DeVIL, so that it can submit its vectors.
+    pub fn setup_market(&mut self, assets: Vec<u8>) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
         let market_address = self.market.get();
-        let submit = IMarket::submitSupplyCall {};
+        let set_operator = IDevil::setOperatorCall {
+            operator: market_address,
+            enabled: true,
+        };
+        self.vm()
+            .call(&self, self.devil.get(), &set_operator.abi_encode())?;
+        let submit = IMarket::submitAssetsCall { assets };
         self.vm()
             .call(&self, market_address, &submit.abi_encode())?;
+        Ok(())
+    }
 
-        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id] =
-            [0; 7];
+    /// Authorise vendor to submit supply, or revoke it
+    ///
+    /// Vendor is also operator of DeVIL, so that it can submit supply vectors
+    /// in its namespace.
+    pub fn set_vendor(&mut self, vendor: Address, enabled: bool) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let set_operator = IDevil::setOperatorCall {
+            operator: vendor,
+            enabled,
+        };
+        self.vm()
+            .call(&self, self.devil.get(), &set_operator.abi_encode())?;
+        let set_vendor = IMarket::setVendorCall { vendor, enabled };
+        self.vm()
+            .call(&self, self.market.get(), &set_vendor.abi_encode())?;
+        Ok(())
+    }
 
-        // TODO: get those from Market
-        let update = update_supply(
+    /// Submit supply of assets by vendor
+    ///
+    /// Vendor submits sorted asset names, and long and short asset quantities
+    /// to DeVIL, and then passes their ids here so that Market supply and
+    /// delta are updated with them. Only vendors authorised by Market may
+    /// submit supply, and only from vectors in their own namespace.
+    pub fn submit_supply(
+        &mut self,
+        asset_names_id: U128,
+        asset_quantities_long_id: U128,
+        asset_quantities_short_id: U128,
+    ) -> Result<(), Vec<u8>> {
+        let vendor = self.vm().msg_sender();
+        let vendor_namespace = namespace(vendor.into_array());
+        for id in [
+            asset_names_id,
+            asset_quantities_long_id,
+            asset_quantities_short_id,
+        ] {
+            if id.to::<u128>() & NAMESPACE_MASK != vendor_namespace {
+                Err(b"Outside namespace")?;
+            }
+        }
+        let market_address = self.market.get();
+        let submit = IMarket::submitSupplyCall { vendor };
+        self.vm()
+            .call(&self, market_address, &submit.abi_encode())?;
+
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, liquidity_id, prices_id, slopes_id] =
+            self.get_market_ids()?;
+
+        let args = vec![
+            asset_names_id.to(),
+            asset_quantities_long_id.to(),
+            asset_quantities_short_id.to(),
             market_asset_names_id,
             supply_long_id,
             supply_short_id,
@@ -231,12 +959,1411 @@ impl Daxos {
             demand_short_id,
             delta_long_id,
             delta_short_id,
-        );
-        let num_registry = 16;
-        self.send_to_devil(update, num_registry)?;
+            liquidity_id,
+            prices_id,
+            slopes_id,
+        ];
+        self.send_to_devil(UPDATE_SUPPLY, args)?;
         Ok(())
     }
+
+    /// Accrue management fee of the Vault for time elapsed since last accrual
+    ///
+    /// First call only starts the clock.
+    pub fn accrue_fees(&mut self, index: U128) -> Result<(), Vec<u8>> {
+        let vault_address = self.get_vault(index)?;
+        let now = self.vm().block_timestamp();
+        let mut last_accrual_access = self.last_accrual.setter(index);
+        let last_accrual = last_accrual_access.get().to::<u64>();
+        last_accrual_access.set(U64::from(now));
+        if last_accrual == 0 || now <= last_accrual {
+            return Ok(());
+        }
+        let elapsed = Amount::from_u128_with_scale((now - last_accrual) as u128, 0);
+
+        let [_, _, quote_id, fees_id, supply_id, accrued_fees_id] =
+            self.get_vault_ids(index, vault_address)?;
+        let args = vec![
+            elapsed.to_u128_raw(),
+            fees_id,
+            quote_id,
+            supply_id,
+            accrued_fees_id,
+        ];
+        self.send_to_devil(ACCRUE_MANAGEMENT_FEE, args)?;
+        Ok(())
+    }
+
+    /// Get collateral held for the Vault in collateral token units
+    pub fn get_custody(&self, index: U128) -> U256 {
+        self.custody.get(index)
+    }
+
+    /// Sweep fees accrued so far by the Vault, i.e. reset its fee accumulator
+    /// to zeros, and settle them with owner
+    ///
+    /// Entry and exit fees were collected in collateral, so that their total
+    /// is transferred out of the Vault custody. Management fee is only accrued
+    /// on index value, and it is not backed by any collateral, so that it is
+    /// settled by minting ITP Token at current price, which dilutes holders.
+    ///
+    /// Returns swept (Entry Fees, Exit Fees, Management Fees).
+    pub fn sweep_fees(&mut self, index: U128) -> Result<(u128, u128, u128), Vec<u8>> {
+        let owner = self.vm().msg_sender();
+        self.check_owner(owner)?;
+        let vault_address = self.get_vault(index)?;
+        let [_, _, quote_id, _, supply_id, accrued_fees_id] =
+            self.get_vault_ids(index, vault_address)?;
+        let accrued = self.get_vector(accrued_fees_id)?;
+        let [entry_fees, exit_fees, management_fees] = accrued.data[..] else {
+            Err(b"Invalid fees")?
+        };
+        let zeros = Vector {
+            data: vec![Amount::ZERO; 3],
+        };
+        let devil_address = self.devil.get();
+        let update = IDevil::updateCall {
+            id: accrued_fees_id,
+            data: zeros.to_vec(),
+            expected_hash: B256::ZERO,
+        };
+        self.vm().call(&self, devil_address, &update.abi_encode())?;
+
+        let collected = entry_fees
+            .checked_add(exit_fees)
+            .ok_or_else(|| b"Invalid fees".to_vec())?;
+        let value = self.amount_to_collateral(collected);
+        self.pay_out(index, owner, value)?;
+
+        if management_fees != Amount::ZERO {
+            let quote = self.get_vector(quote_id)?;
+            let [_, price, _] = quote.data[..] else {
+                Err(b"Invalid quote")?
+            };
+            if price == Amount::ZERO {
+                Err(b"Invalid quote")?;
+            }
+            let quantity = management_fees
+                .checked_div(price)
+                .ok_or_else(|| b"Invalid quote".to_vec())?;
+            let supply = self
+                .get_supply(supply_id)?
+                .checked_add(quantity)
+                .ok_or_else(|| b"Invalid supply".to_vec())?;
+            let update = IDevil::updateCall {
+                id: supply_id,
+                data: Vector { data: vec![supply] }.to_vec(),
+                expected_hash: B256::ZERO,
+            };
+            self.vm().call(&self, devil_address, &update.abi_encode())?;
+            let mint = IVault::mintCall {
+                to: owner,
+                value: U256::from(quantity.to_u128_raw()),
+            };
+            self.vm().call(&self, vault_address, &mint.abi_encode())?;
+            self.check_supply(vault_address, supply)?;
+        }
+
+        let fees = (
+            entry_fees.to_u128_raw(),
+            exit_fees.to_u128_raw(),
+            management_fees.to_u128_raw(),
+        );
+        log(
+            self.vm(),
+            FeesSwept {
+                index: index.to(),
+                entry_fees: fees.0,
+                exit_fees: fees.1,
+                management_fees: fees.2,
+            },
+        );
+        Ok(fees)
+    }
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use deli::vis::{arg, num_registers};
+    use devil::Devil;
+    use icore::vil::{
+        accrue_management_fee::accrue_management_fee, execute_buy_order::execute_buy_order,
+        match_market::match_market, solve_quadratic::solve_quadratic, submit_order::create_order,
+    };
+    use labels_macros::label_vec;
+    use market::Market;
+    use stylus_sdk::testing::*;
+    use vault::Vault;
+    use vector_macros::amount_vec;
+
+    const OWNER: Address = Address::new([0x01; 20]);
+    const USER: Address = Address::new([0x02; 20]);
+    const DAXOS: Address = Address::new([0xda; 20]);
+    const DEVIL: Address = Address::new([0x0d; 20]);
+    const MARKET: Address = Address::new([0x0a; 20]);
+    const VAULT: Address = Address::new([0x0b; 20]);
+    const USDC: Address = Address::new([0x0c; 20]);
+    const DAO: Address = Address::new([0xd0; 20]);
+    const VENDOR: Address = Address::new([0x0e; 20]);
+
+    /// Vault vectors, i.e. [Assets, Weights, Quote, Fees, Supply, Accrued Fees]
+    const VAULT_IDS: [u128; 6] = [1001, 1002, 1003, 1004, 1005, 1006];
+
+    /// Market vectors, i.e. [Assets, Supply Long, Supply Short, Demand Long,
+    /// Demand Short, Delta Long, Delta Short, Liquidity, Prices, Slopes]
+    const MARKET_IDS: [u128; 10] = [101, 102, 103, 104, 105, 106, 107, 108, 109, 110];
+
+    /// Set up Daxos with one Vault, where Vault, Market and collateral token
+    /// (6 decimals) are mocked
+    ///
+    /// Vault is left proposed, i.e. DAO has not yet voted to activate it.
+    fn setup_proposed(vm: &TestVM) -> (Daxos, U128) {
+        let mut daxos = Daxos::from(vm);
+        let index = U128::from(1);
+
+        vm.set_contract_address(DAXOS);
+        vm.set_tx_origin(OWNER);
+        vm.mock_static_call(
+            USDC,
+            IERC20::decimalsCall {}.abi_encode(),
+            Ok(IERC20::decimalsCall::abi_encode_returns(&(6,))),
+        );
+        daxos.setup(OWNER, DEVIL, MARKET, USDC).unwrap();
+        vm.set_sender(OWNER);
+        daxos.set_governor(DAO).unwrap();
+        daxos
+            .setup_vault(index, VAULT, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+
+        // Vault and Market hand out ids of their vectors
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] = VAULT_IDS;
+        for (call, id) in [
+            (IVault::getAssetsCall {}.abi_encode(), asset_names_id),
+            (IVault::getWeightsCall {}.abi_encode(), weights_id),
+            (IVault::getQuoteCall {}.abi_encode(), quote_id),
+            (IVault::getFeesCall {}.abi_encode(), fees_id),
+            (IVault::getSupplyCall {}.abi_encode(), supply_id),
+            (IVault::getAccruedFeesCall {}.abi_encode(), accrued_fees_id),
+        ] {
+            let id = IVault::getAssetsCall::abi_encode_returns(&(id,));
+            vm.mock_static_call(VAULT, call, Ok(id));
+        }
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, liquidity_id, prices_id, slopes_id] =
+            MARKET_IDS;
+        for (call, ids) in [
+            (
+                IMarket::getSupplyCall {}.abi_encode(),
+                (supply_long_id, supply_short_id),
+            ),
+            (
+                IMarket::getDemandCall {}.abi_encode(),
+                (demand_long_id, demand_short_id),
+            ),
+            (
+                IMarket::getDeltaCall {}.abi_encode(),
+                (delta_long_id, delta_short_id),
+            ),
+        ] {
+            let ids = IMarket::getSupplyCall::abi_encode_returns(&ids);
+            vm.mock_static_call(MARKET, call, Ok(ids));
+        }
+        for (call, id) in [
+            (
+                IMarket::getAssetsCall {}.abi_encode(),
+                market_asset_names_id,
+            ),
+            (IMarket::getLiquidityCall {}.abi_encode(), liquidity_id),
+            (IMarket::getPricesCall {}.abi_encode(), prices_id),
+            (IMarket::getSlopesCall {}.abi_encode(), slopes_id),
+        ] {
+            let id = IMarket::getAssetsCall::abi_encode_returns(&(id,));
+            vm.mock_static_call(MARKET, call, Ok(id));
+        }
+        (daxos, index)
+    }
+
+    /// Set up Daxos with one active Vault
+    fn setup_daxos(vm: &TestVM) -> (Daxos, U128) {
+        let (mut daxos, index) = setup_proposed(vm);
+        vm.set_sender(DAO);
+        daxos.activate_vault(index).unwrap();
+        (daxos, index)
+    }
+
+    fn vault_states(vm: &TestVM) -> Vec<u8> {
+        vm.get_emitted_logs()
+            .iter()
+            .filter_map(|(topics, data)| {
+                VaultStateChanged::decode_raw_log(topics.iter().copied(), data, true).ok()
+            })
+            .map(|event| event.state)
+            .collect()
+    }
+
+    fn mock_program(vm: &TestVM, name: [u8; 32], id: u128) {
+        let call = IDevil::getProgramCall { name: name.into() };
+        vm.mock_static_call(
+            DEVIL,
+            call.abi_encode(),
+            Ok(IDevil::getProgramCall::abi_encode_returns(&(id, 1))),
+        );
+    }
+
+    fn mock_vector(vm: &TestVM, id: u128, data: Vec<u8>) {
+        vm.mock_static_call(
+            DEVIL,
+            IDevil::getCall { id }.abi_encode(),
+            Ok(IDevil::getCall::abi_encode_returns(&(data,))),
+        );
+    }
+
+    fn mock_total_supply(vm: &TestVM, total_supply: U256) {
+        vm.mock_static_call(
+            VAULT,
+            IVault::totalSupplyCall {}.abi_encode(),
+            Ok(IVault::totalSupplyCall::abi_encode_returns(
+                &(total_supply,),
+            )),
+        );
+    }
+
+    fn mock_transfer(vm: &TestVM, calldata: Vec<u8>) {
+        vm.mock_call(
+            USDC,
+            calldata,
+            Ok(IERC20::transferCall::abi_encode_returns(&(true,))),
+        );
+    }
+
+    const SOLVE_QUADRATIC_ID: u128 = 10;
+    const EXECUTE_BUY_ORDER_ID: u128 = 11;
+    const MATCH_MARKET_ID: u128 = 12;
+
+    /// Set up DeVIL holding Vault and Market vectors, where capacity is too
+    /// low to fill order of 1000 USDC in full, and install buy order procedures
+    fn setup_devil(vm: &TestVM, devil: &mut Devil) {
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] = VAULT_IDS;
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, ..] =
+            MARKET_IDS;
+        let inputs = [
+            (asset_names_id, label_vec![51, 53, 54].to_vec()),
+            (weights_id, amount_vec![0.1, 1, 100].to_vec()),
+            (quote_id, amount_vec![0.05, 10_000, 100].to_vec()),
+            (fees_id, amount_vec![0, 0, 0].to_vec()),
+            (supply_id, amount_vec![0].to_vec()),
+            (accrued_fees_id, amount_vec![0, 0, 0].to_vec()),
+            (
+                market_asset_names_id,
+                label_vec![51, 52, 53, 54, 55].to_vec(),
+            ),
+            (
+                supply_long_id,
+                amount_vec![0.05, 0.05, 0.05, 0.05, 0.05].to_vec(),
+            ),
+            (supply_short_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+            (demand_long_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+            (demand_short_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+            (delta_long_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+            (delta_short_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+        ];
+        let (ids, data): (Vec<_>, Vec<_>) = inputs
+            .into_iter()
+            .map(|(id, data)| (U128::from(id), data))
+            .unzip();
+        devil.submit_many(ids, data).unwrap();
+        devil
+            .install_program(
+                SOLVE_QUADRATIC.into(),
+                U128::from(SOLVE_QUADRATIC_ID),
+                Labels {
+                    data: solve_quadratic(),
+                }
+                .to_vec(),
+                U128::from(3),
+                U128::from(1),
+                U128::from(4),
+            )
+            .unwrap();
+        devil
+            .install_program(
+                MATCH_MARKET.into(),
+                U128::from(MATCH_MARKET_ID),
+                Labels {
+                    data: match_market(),
+                }
+                .to_vec(),
+                U128::from(7),
+                U128::from(4),
+                U128::from(7),
+            )
+            .unwrap();
+        let code = execute_buy_order(
+            arg(0),
+            arg(1),
+            arg(2),
+            arg(3),
+            arg(4),
+            arg(5),
+            arg(6),
+            arg(7),
+            arg(8),
+            arg(9),
+            arg(10),
+            arg(11),
+            arg(12),
+            arg(13),
+            arg(14),
+            arg(15),
+            arg(16),
+            arg(17),
+        );
+        devil
+            .install_program(
+                EXECUTE_BUY_ORDER.into(),
+                U128::from(EXECUTE_BUY_ORDER_ID),
+                Labels { data: code }.to_vec(),
+                U128::ZERO,
+                U128::ZERO,
+                U128::from(20),
+            )
+            .unwrap();
+        mock_program(vm, SOLVE_QUADRATIC, SOLVE_QUADRATIC_ID);
+        mock_program(vm, MATCH_MARKET, MATCH_MARKET_ID);
+        mock_program(vm, EXECUTE_BUY_ORDER, EXECUTE_BUY_ORDER_ID);
+    }
+
+    #[test]
+    fn test_submit_order() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_daxos(&vm);
+
+        let index_order_id = 10001;
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] = VAULT_IDS;
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, ..] =
+            MARKET_IDS;
+        let solve_quadratic_id = SOLVE_QUADRATIC_ID;
+        let execute_buy_order_id = EXECUTE_BUY_ORDER_ID;
+        let match_market_id = MATCH_MARKET_ID;
+
+        // DeVIL holds Vault and Market vectors, and capacity is too low to
+        // fill the order in full
+        let devil_vm = TestVM::default();
+        let mut devil = Devil::from(&devil_vm);
+        setup_devil(&vm, &mut devil);
+        devil
+            .submit(U128::from(index_order_id), amount_vec![1000, 0, 0].to_vec())
+            .unwrap();
+
+        // User pays 1000 USDC, which is 1000 in collateral amount
+        let collateral_amount = 1_000_000_000;
+        vm.mock_call(
+            VAULT,
+            IVault::submitOrderCall {
+                user: USER,
+                collateral_amount: amount_vec![1000].data[0].to_u128_raw(),
+            }
+            .abi_encode(),
+            Ok(IVault::submitOrderCall::abi_encode_returns(&(
+                index_order_id,
+            ))),
+        );
+
+        let args = [
+            index_order_id,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            EXECUTED_ASSET_QUANTITIES_ID,
+            asset_names_id,
+            weights_id,
+            quote_id,
+            fees_id,
+            supply_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            accrued_fees_id,
+            solve_quadratic_id,
+            match_market_id,
+        ];
+        let execute_stored = IDevil::executeStoredCall {
+            program_id: execute_buy_order_id,
+            args: args.to_vec(),
+        }
+        .abi_encode();
+
+        // Collateral must be transferred from the user
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.submit_order(index, collateral_amount).unwrap_err(),
+            b"Transfer failed"
+        );
+        mock_transfer(
+            &vm,
+            IERC20::transferFromCall {
+                from: USER,
+                to: DAXOS,
+                value: U256::from(collateral_amount),
+            }
+            .abi_encode(),
+        );
+
+        // Daxos sends exactly this call to DeVIL, which we verify by making it
+        // revert first...
+        vm.mock_call(DEVIL, execute_stored.clone(), Err(b"Stop".to_vec()));
+        assert_eq!(
+            daxos.submit_order(index, collateral_amount).unwrap_err(),
+            b"Stop"
+        );
+
+        // ...and then DeVIL executes it
+        devil
+            .execute_stored(
+                U128::from(execute_buy_order_id),
+                args.map(U128::from).to_vec(),
+            )
+            .unwrap();
+        let executed = devil.get(U128::from(EXECUTED_INDEX_QUANTITIES_ID)).unwrap();
+        vm.mock_call(DEVIL, execute_stored, Ok(Vec::new()));
+        mock_vector(&vm, EXECUTED_INDEX_QUANTITIES_ID, executed.clone());
+
+        // ITP Token supply must match supply updated by DeVIL
+        let supply = devil.get(U128::from(supply_id)).unwrap();
+        mock_vector(&vm, supply_id, supply.clone());
+        mock_total_supply(&vm, U256::ZERO);
+        assert_eq!(
+            daxos.submit_order(index, collateral_amount).unwrap_err(),
+            b"Supply mismatch"
+        );
+        let supply = Vector::from_vec(supply).data[0];
+        mock_total_supply(&vm, U256::from(supply.to_u128_raw()));
+
+        // Ids are cached, so Vault is not asked again
+        vm.mock_static_call(
+            VAULT,
+            IVault::getAssetsCall {}.abi_encode(),
+            Err(b"Not cached".to_vec()),
+        );
+        daxos.submit_order(index, collateral_amount).unwrap();
+        assert_eq!(daxos.get_custody(index), U256::from(collateral_amount));
+
+        // Order was filled up to capacity, and remainder was queued
+        let order = Vector::from_vec(devil.get(U128::from(index_order_id)).unwrap());
+        let [collateral, spent, minted] = order.data[..] else {
+            panic!("Invalid order");
+        };
+        assert!(collateral > Amount::ZERO && spent > Amount::ZERO);
+        assert_eq!(minted, amount_vec![0.05].data[0]);
+
+        let executed = Vector::from_vec(executed);
+        let queued: Vec<_> = vm
+            .get_emitted_logs()
+            .iter()
+            .filter_map(|(topics, data)| {
+                OrderQueued::decode_raw_log(topics.iter().copied(), data, true).ok()
+            })
+            .collect();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].user, USER);
+        assert_eq!(queued[0].remaining_quantity, executed.data[1].to_u128_raw());
+        assert!(executed.data[1] > Amount::ZERO);
+
+        // ITP Token is minted for filled quantity, which we verify by making
+        // mint revert
+        let mint = IVault::mintCall {
+            to: USER,
+            value: U256::from(executed.data[0].to_u128_raw()),
+        };
+        vm.mock_call(VAULT, mint.abi_encode(), Err(b"Minted".to_vec()));
+        assert_eq!(
+            daxos.submit_order(index, collateral_amount).unwrap_err(),
+            b"Minted"
+        );
+        assert_eq!(executed.data[0], minted);
+
+        // Fully filled order leaves the queue, which it may have joined before
+        vm.mock_call(VAULT, mint.abi_encode(), Ok(Vec::new()));
+        mock_vector(
+            &vm,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            amount_vec![0.05, 0].to_vec(),
+        );
+        vm.mock_call(
+            VAULT,
+            IVault::dequeueOrderCall { user: USER }.abi_encode(),
+            Err(b"Dequeued".to_vec()),
+        );
+        assert_eq!(
+            daxos.submit_order(index, collateral_amount).unwrap_err(),
+            b"Dequeued"
+        );
+    }
+
+    #[test]
+    fn test_submit_order_wired() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_daxos(&vm);
+
+        // TestVM runs each contract in isolation and does not dispatch calls
+        // between them, so each call Daxos makes to Vault and DeVIL and each
+        // call Vault makes to DeVIL is relayed to real contract, and its result
+        // is then mocked for the caller. Market and Vault index vectors are
+        // provisioned outside of these contracts, so they are submitted to
+        // DeVIL by the owner.
+        let devil_vm = TestVM::default();
+        let mut devil = Devil::from(&devil_vm);
+        setup_devil(&vm, &mut devil);
+        devil_vm.set_sender(DAXOS);
+        devil.setup(DAXOS).unwrap();
+        devil.set_operator(VAULT, true).unwrap();
+
+        let vault_vm = TestVM::default();
+        let mut vault = Vault::from(&vault_vm);
+        vault_vm.set_contract_address(VAULT);
+        vault_vm.set_sender(DAXOS);
+        vault
+            .setup(DAXOS, DEVIL, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+
+        let relay_get = |devil: &Devil, id: u128| {
+            devil_vm.set_sender(VAULT);
+            let result = devil
+                .get(U128::from(id))
+                .map(|data| IDevil::getCall::abi_encode_returns(&(data,)));
+            vault_vm.mock_static_call(DEVIL, IDevil::getCall { id }.abi_encode(), result);
+        };
+        let relay_execute = |devil: &mut Devil, code: Vec<u128>| {
+            devil_vm.set_sender(VAULT);
+            devil.execute(Labels { data: code }.to_vec(), 0).unwrap();
+        };
+
+        // User pays 1000 USDC, and Vault creates order in its namespace
+        let collateral_amount = 1_000_000_000;
+        let collateral = amount_vec![1000].data[0].to_u128_raw();
+        mock_transfer(
+            &vm,
+            IERC20::transferFromCall {
+                from: USER,
+                to: DAXOS,
+                value: U256::from(collateral_amount),
+            }
+            .abi_encode(),
+        );
+        let order_id = vault.submit_order(USER, U128::from(collateral)).unwrap();
+        relay_execute(&mut devil, create_order(order_id.to(), collateral));
+        vm.mock_call(
+            VAULT,
+            IVault::submitOrderCall {
+                user: USER,
+                collateral_amount: collateral,
+            }
+            .abi_encode(),
+            Ok(IVault::submitOrderCall::abi_encode_returns(&(
+                order_id.to::<u128>(),
+            ))),
+        );
+        let namespace = deli::vis::namespace(VAULT.into_array());
+        assert_eq!(order_id.to::<u128>() & !namespace, 7);
+
+        // Daxos executes buy order on Vault's order
+        let [asset_names_id, weights_id, quote_id, fees_id, supply_id, accrued_fees_id] = VAULT_IDS;
+        let [market_asset_names_id, supply_long_id, supply_short_id, demand_long_id, demand_short_id, delta_long_id, delta_short_id, ..] =
+            MARKET_IDS;
+        let args = [
+            order_id.to(),
+            EXECUTED_INDEX_QUANTITIES_ID,
+            EXECUTED_ASSET_QUANTITIES_ID,
+            asset_names_id,
+            weights_id,
+            quote_id,
+            fees_id,
+            supply_id,
+            market_asset_names_id,
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            accrued_fees_id,
+            SOLVE_QUADRATIC_ID,
+            MATCH_MARKET_ID,
+        ];
+        devil_vm.set_sender(DAXOS);
+        devil
+            .execute_stored(
+                U128::from(EXECUTE_BUY_ORDER_ID),
+                args.map(U128::from).to_vec(),
+            )
+            .unwrap();
+        let executed = devil.get(U128::from(EXECUTED_INDEX_QUANTITIES_ID)).unwrap();
+        mock_vector(&vm, EXECUTED_INDEX_QUANTITIES_ID, executed.clone());
+        let executed = Vector::from_vec(executed);
+
+        // Filled quantity is minted, and remainder is queued in the namespace
+        vault
+            .mint(USER, U256::from(executed.data[0].to_u128_raw()))
+            .unwrap();
+        let queue_id = namespace | 8;
+        relay_get(&devil, queue_id);
+        vault.enqueue_order(USER).unwrap();
+        devil_vm.set_sender(VAULT);
+        devil
+            .submit(
+                U128::from(queue_id),
+                Labels {
+                    data: vec![order_id.to()],
+                }
+                .to_vec(),
+            )
+            .unwrap();
+
+        // ITP Token supply is checked against supply updated by DeVIL
+        mock_vector(&vm, supply_id, devil.get(U128::from(supply_id)).unwrap());
+        mock_total_supply(&vm, vault.total_supply());
+
+        vm.set_sender(USER);
+        daxos.submit_order(index, collateral_amount).unwrap();
+
+        // Vault and DeVIL agree on order, its minted quantity and queue
+        let order = Vector::from_vec(devil.get(order_id).unwrap());
+        assert_eq!(order.data[2], executed.data[0]);
+        assert_eq!(
+            vault.balance_of(USER),
+            U256::from(order.data[2].to_u128_raw())
+        );
+        assert_eq!(vault.get_order(USER).unwrap(), order_id);
+        assert_eq!(vault.get_queue().unwrap(), U128::from(queue_id));
+        assert_eq!(
+            Labels::from_vec(devil.get(U128::from(queue_id)).unwrap()).data,
+            vec![order_id.to::<u128>()]
+        );
+
+        // Vault cannot write outside of its namespace
+        devil_vm.set_sender(VAULT);
+        assert!(devil.delete(U128::from(quote_id)).is_err());
+        assert!(devil
+            .execute(
+                Labels {
+                    data: create_order(quote_id, 1)
+                }
+                .to_vec(),
+                0
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_execute_batch() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_daxos(&vm);
+        let queue_id = 20000;
+        let other = Address::new([0x03; 20]);
+
+        for (name, id) in [
+            (SOLVE_QUADRATIC, SOLVE_QUADRATIC_ID),
+            (MATCH_MARKET, MATCH_MARKET_ID),
+            (BATCH_SUM_COLLATERAL, 15),
+            (BATCH_FILL_ORDER, 16),
+            (EXECUTE_BUY_BATCH, 17),
+        ] {
+            mock_program(&vm, name, id);
+        }
+        vm.mock_static_call(
+            VAULT,
+            IVault::getQueueCall {}.abi_encode(),
+            Ok(IVault::getQueueCall::abi_encode_returns(&(queue_id,))),
+        );
+
+        // Batch filled first and last order, and second had no collateral
+        mock_vector(&vm, queue_id, label_vec![20001, 20002, 20003].to_vec());
+        mock_vector(
+            &vm,
+            EXECUTED_ORDER_QUANTITIES_ID,
+            amount_vec![0.6, 0, 0.4].to_vec(),
+        );
+        mock_vector(
+            &vm,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            amount_vec![1, 0].to_vec(),
+        );
+        for (order_id, user) in [(20001, USER), (20003, other)] {
+            vm.mock_static_call(
+                VAULT,
+                IVault::getOrderUserCall { order_id }.abi_encode(),
+                Ok(IVault::getOrderUserCall::abi_encode_returns(&(user,))),
+            );
+        }
+        vm.mock_static_call(
+            VAULT,
+            IVault::getOrderUserCall { order_id: 20002 }.abi_encode(),
+            Err(b"Not filled".to_vec()),
+        );
+
+        // ITP Token is minted per order, which we verify by making mint revert
+        let mint = |to: Address, quantity: Amount| {
+            IVault::mintCall {
+                to,
+                value: U256::from(quantity.to_u128_raw()),
+            }
+            .abi_encode()
+        };
+        let [first, _, last] = amount_vec![0.6, 0, 0.4].data[..] else {
+            unreachable!()
+        };
+        vm.mock_call(VAULT, mint(USER, first), Err(b"Minted first".to_vec()));
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Minted first");
+        vm.mock_call(VAULT, mint(USER, first), Ok(Vec::new()));
+        vm.mock_call(VAULT, mint(other, last), Err(b"Minted last".to_vec()));
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Minted last");
+        vm.mock_call(VAULT, mint(other, last), Ok(Vec::new()));
+
+        // ITP Token supply must match supply updated by DeVIL
+        let supply_id = VAULT_IDS[4];
+        mock_vector(&vm, supply_id, amount_vec![1].to_vec());
+        mock_total_supply(&vm, U256::from(first.to_u128_raw()));
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Supply mismatch");
+        let total_supply = first.checked_add(last).unwrap();
+        mock_total_supply(&vm, U256::from(total_supply.to_u128_raw()));
+
+        // All orders were filled, so queue is cleared
+        vm.mock_call(
+            VAULT,
+            IVault::clearQueueCall {}.abi_encode(),
+            Err(b"Cleared".to_vec()),
+        );
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Cleared");
+    }
+
+    #[test]
+    fn test_submit_supply() {
+        let vm = TestVM::default();
+        let (mut daxos, _) = setup_daxos(&vm);
+        let update_supply_id = 14;
+        mock_program(&vm, UPDATE_SUPPLY, update_supply_id);
+        let stop = b"Stop".to_vec();
+
+        // Market is real contract, and its results are relayed to Daxos same
+        // as in wired test, including ids it allocated for its vectors
+        let market_vm = TestVM::default();
+        let mut market = Market::from(&market_vm);
+        market_vm.set_contract_address(MARKET);
+        market_vm.set_sender(DAXOS);
+        market.setup(DAXOS, DEVIL).unwrap();
+        let (supply_long_id, supply_short_id) = market.get_supply();
+        let (demand_long_id, demand_short_id) = market.get_demand();
+        let (delta_long_id, delta_short_id) = market.get_delta();
+        let market_ids = [
+            market.get_assets(),
+            supply_long_id,
+            supply_short_id,
+            demand_long_id,
+            demand_short_id,
+            delta_long_id,
+            delta_short_id,
+            market.get_liquidity(),
+            market.get_prices(),
+            market.get_slopes(),
+        ]
+        .map(|id| id.to::<u128>());
+        for (call, ids) in [
+            (IMarket::getSupplyCall {}.abi_encode(), (1, 2)),
+            (IMarket::getDemandCall {}.abi_encode(), (3, 4)),
+            (IMarket::getDeltaCall {}.abi_encode(), (5, 6)),
+        ] {
+            let ids = (market_ids[ids.0], market_ids[ids.1]);
+            let ids = IMarket::getSupplyCall::abi_encode_returns(&ids);
+            vm.mock_static_call(MARKET, call, Ok(ids));
+        }
+        for (call, id) in [
+            (IMarket::getAssetsCall {}.abi_encode(), market_ids[0]),
+            (IMarket::getLiquidityCall {}.abi_encode(), market_ids[7]),
+            (IMarket::getPricesCall {}.abi_encode(), market_ids[8]),
+            (IMarket::getSlopesCall {}.abi_encode(), market_ids[9]),
+        ] {
+            let id = IMarket::getAssetsCall::abi_encode_returns(&(id,));
+            vm.mock_static_call(MARKET, call, Ok(id));
+        }
+        let relay_submit = |market: &mut Market| {
+            let result = market.submit_supply(VENDOR).map(|_| Vec::new());
+            let submit = IMarket::submitSupplyCall { vendor: VENDOR };
+            vm.mock_call(MARKET, submit.abi_encode(), result);
+        };
+
+        // Vendor submits vectors in its namespace
+        let namespace = namespace(VENDOR.into_array());
+        let [asset_names_id, asset_quantities_long_id, asset_quantities_short_id] =
+            [1, 2, 3].map(|id| namespace | id);
+        let submit_supply = |daxos: &mut Daxos| {
+            daxos.submit_supply(
+                U128::from(asset_names_id),
+                U128::from(asset_quantities_long_id),
+                U128::from(asset_quantities_short_id),
+            )
+        };
+
+        // Vendor must be authorised...
+        vm.set_sender(VENDOR);
+        relay_submit(&mut market);
+        assert_eq!(submit_supply(&mut daxos).unwrap_err(), b"Must be vendor");
+
+        // ...by owner, and it becomes operator of DeVIL
+        assert_eq!(daxos.set_vendor(VENDOR, true).unwrap_err(), b"Mut be owner");
+        vm.set_sender(OWNER);
+        let set_operator = IDevil::setOperatorCall {
+            operator: VENDOR,
+            enabled: true,
+        }
+        .abi_encode();
+        vm.mock_call(DEVIL, set_operator.clone(), Err(stop.clone()));
+        assert_eq!(daxos.set_vendor(VENDOR, true).unwrap_err(), stop);
+        vm.mock_call(DEVIL, set_operator, Ok(Vec::new()));
+        let set_vendor = IMarket::setVendorCall {
+            vendor: VENDOR,
+            enabled: true,
+        }
+        .abi_encode();
+        vm.mock_call(MARKET, set_vendor.clone(), Err(stop.clone()));
+        assert_eq!(daxos.set_vendor(VENDOR, true).unwrap_err(), stop);
+        vm.mock_call(MARKET, set_vendor, Ok(Vec::new()));
+        daxos.set_vendor(VENDOR, true).unwrap();
+        market.set_vendor(VENDOR, true).unwrap();
+        relay_submit(&mut market);
+
+        // Vendor cannot pass vectors of others, e.g. of the Market
+        vm.set_sender(VENDOR);
+        assert_eq!(
+            daxos
+                .submit_supply(
+                    U128::from(market_ids[1]),
+                    U128::from(asset_quantities_long_id),
+                    U128::from(asset_quantities_short_id),
+                )
+                .unwrap_err(),
+            b"Outside namespace"
+        );
+
+        // Market is updated with vectors submitted by vendor
+        let mut args = vec![
+            asset_names_id,
+            asset_quantities_long_id,
+            asset_quantities_short_id,
+        ];
+        args.extend(market_ids);
+        let execute_stored = IDevil::executeStoredCall {
+            program_id: update_supply_id,
+            args,
+        }
+        .abi_encode();
+        vm.mock_call(DEVIL, execute_stored.clone(), Err(stop.clone()));
+        assert_eq!(submit_supply(&mut daxos).unwrap_err(), stop);
+        vm.mock_call(DEVIL, execute_stored, Ok(Vec::new()));
+        submit_supply(&mut daxos).unwrap();
+    }
+
+    #[test]
+    fn test_setup_market() {
+        let vm = TestVM::default();
+        let (mut daxos, _) = setup_daxos(&vm);
+        let assets = label_vec![51, 52, 53].to_vec();
+
+        // Market becomes operator of DeVIL, and then submits its vectors
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.setup_market(assets.clone()).unwrap_err(),
+            b"Mut be owner"
+        );
+        vm.set_sender(OWNER);
+        let set_operator = IDevil::setOperatorCall {
+            operator: MARKET,
+            enabled: true,
+        }
+        .abi_encode();
+        vm.mock_call(DEVIL, set_operator.clone(), Err(b"Stop".to_vec()));
+        assert_eq!(daxos.setup_market(assets.clone()).unwrap_err(), b"Stop");
+        vm.mock_call(DEVIL, set_operator, Ok(Vec::new()));
+        let submit = IMarket::submitAssetsCall {
+            assets: assets.clone(),
+        }
+        .abi_encode();
+        vm.mock_call(MARKET, submit.clone(), Err(b"Stop".to_vec()));
+        assert_eq!(daxos.setup_market(assets.clone()).unwrap_err(), b"Stop");
+        vm.mock_call(MARKET, submit, Ok(Vec::new()));
+        daxos.setup_market(assets).unwrap();
+    }
+
+    #[test]
+    fn test_accrue_fees() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_daxos(&vm);
+        let [_, _, quote_id, fees_id, supply_id, accrued_fees_id] = VAULT_IDS;
+        let accrue_management_fee_id = 13;
+
+        // DeVIL holds Vault vectors, and procedure is installed once taking
+        // elapsed time as first argument
+        let devil_vm = TestVM::default();
+        let mut devil = Devil::from(&devil_vm);
+        let inputs = [
+            (quote_id, amount_vec![0.05, 1000, 1].to_vec()),
+            (fees_id, amount_vec![0, 0, 0.0001].to_vec()),
+            (supply_id, amount_vec![0.5].to_vec()),
+            (accrued_fees_id, amount_vec![1, 2, 0].to_vec()),
+        ];
+        let (ids, data): (Vec<_>, Vec<_>) = inputs
+            .into_iter()
+            .map(|(id, data)| (U128::from(id), data))
+            .unzip();
+        devil.submit_many(ids, data).unwrap();
+        let code = accrue_management_fee(arg(1), arg(2), arg(3), arg(4));
+        devil
+            .install_program(
+                ACCRUE_MANAGEMENT_FEE.into(),
+                U128::from(accrue_management_fee_id),
+                Labels { data: code.clone() }.to_vec(),
+                U128::ZERO,
+                U128::ZERO,
+                U128::from(num_registers(&code)),
+            )
+            .unwrap();
+        mock_program(&vm, ACCRUE_MANAGEMENT_FEE, accrue_management_fee_id);
+
+        // First call only starts the clock
+        vm.set_block_timestamp(1000);
+        daxos.accrue_fees(index).unwrap();
+
+        // Daxos sends elapsed time as argument, which we verify by making the
+        // call revert first...
+        let args = [
+            amount_vec![3600].data[0].to_u128_raw(),
+            fees_id,
+            quote_id,
+            supply_id,
+            accrued_fees_id,
+        ];
+        let execute_stored = IDevil::executeStoredCall {
+            program_id: accrue_management_fee_id,
+            args: args.to_vec(),
+        }
+        .abi_encode();
+        vm.mock_call(DEVIL, execute_stored.clone(), Err(b"Stop".to_vec()));
+        vm.set_block_timestamp(4600);
+        assert_eq!(daxos.accrue_fees(index).unwrap_err(), b"Stop");
+
+        // ...and then DeVIL executes it
+        devil
+            .execute_stored(
+                U128::from(accrue_management_fee_id),
+                args.map(U128::from).to_vec(),
+            )
+            .unwrap();
+        vm.mock_call(DEVIL, execute_stored, Ok(Vec::new()));
+        daxos.last_accrual.setter(index).set(U64::from(1000));
+        daxos.accrue_fees(index).unwrap();
+
+        // Fee = 0.0001 * 3600 * 0.5 * 1000
+        let accrued = devil.get(U128::from(accrued_fees_id)).unwrap();
+        assert_eq!(
+            Vector::from_vec(accrued.clone()).data,
+            amount_vec![1, 2, 180].data
+        );
+
+        // Owner sweeps fees accrued by the Vault
+        mock_vector(&vm, accrued_fees_id, accrued);
+        vm.set_sender(USER);
+        assert_eq!(daxos.sweep_fees(index).unwrap_err(), b"Mut be owner");
+        vm.set_sender(OWNER);
+
+        // Only entry and exit fees were collected, so only their total is paid
+        // out of the Vault custody, which must hold it. Custody also holds
+        // remaining collateral of users, which stays there.
+        let transfer = IERC20::transferCall {
+            to: OWNER,
+            value: U256::from(3_000_000),
+        };
+        mock_transfer(&vm, transfer.abi_encode());
+        daxos.custody.setter(index).set(U256::from(2_000_000));
+        assert_eq!(
+            daxos.sweep_fees(index).unwrap_err(),
+            b"Insufficient custody"
+        );
+        daxos.custody.setter(index).set(U256::from(200_000_000));
+
+        // Management fee is settled in ITP at price of 1000, i.e. 0.18 ITP is
+        // minted to owner and added to index supply
+        mock_vector(&vm, quote_id, devil.get(U128::from(quote_id)).unwrap());
+        mock_vector(&vm, supply_id, devil.get(U128::from(supply_id)).unwrap());
+        let update_supply = IDevil::updateCall {
+            id: supply_id,
+            data: amount_vec![0.68].to_vec(),
+            expected_hash: B256::ZERO,
+        }
+        .abi_encode();
+        vm.mock_call(DEVIL, update_supply.clone(), Err(b"Stop".to_vec()));
+        assert_eq!(daxos.sweep_fees(index).unwrap_err(), b"Stop");
+        vm.mock_call(DEVIL, update_supply, Ok(Vec::new()));
+        let mint = IVault::mintCall {
+            to: OWNER,
+            value: U256::from(amount_vec![0.18].data[0].to_u128_raw()),
+        }
+        .abi_encode();
+        vm.mock_call(VAULT, mint.clone(), Err(b"Stop".to_vec()));
+        daxos.custody.setter(index).set(U256::from(200_000_000));
+        assert_eq!(daxos.sweep_fees(index).unwrap_err(), b"Stop");
+        vm.mock_call(VAULT, mint, Ok(Vec::new()));
+
+        // ITP Token supply must match index supply including minted fee
+        mock_total_supply(&vm, U256::from(amount_vec![0.5].data[0].to_u128_raw()));
+        daxos.custody.setter(index).set(U256::from(200_000_000));
+        assert_eq!(daxos.sweep_fees(index).unwrap_err(), b"Supply mismatch");
+        mock_total_supply(&vm, U256::from(amount_vec![0.68].data[0].to_u128_raw()));
+        daxos.custody.setter(index).set(U256::from(200_000_000));
+
+        let fees: Vec<_> = amount_vec![1, 2, 180]
+            .data
+            .iter()
+            .map(|x| x.to_u128_raw())
+            .collect();
+        assert_eq!(
+            daxos.sweep_fees(index).unwrap(),
+            (fees[0], fees[1], fees[2])
+        );
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = FeesSwept::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(event.index, index.to::<u128>());
+        assert_eq!(event.management_fees, fees[2]);
+        assert_eq!(daxos.get_custody(index), U256::from(197_000_000));
+
+        // Management fee cannot be settled without price
+        mock_vector(&vm, quote_id, amount_vec![0.05, 0, 1].to_vec());
+        assert_eq!(daxos.sweep_fees(index).unwrap_err(), b"Invalid quote");
+    }
+
+    #[test]
+    fn test_cancel_order() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_daxos(&vm);
+        daxos.custody.setter(index).set(U256::from(1_000_000_000));
+
+        // Vault returns collateral remaining, i.e. 400.0000005, and user is
+        // refunded 400 USDC, as dust below token precision is kept
+        vm.mock_call(
+            VAULT,
+            IVault::cancelOrderCall { user: USER }.abi_encode(),
+            Ok(IVault::cancelOrderCall::abi_encode_returns(&(amount_vec![
+                400.0000005
+            ]
+            .data[0]
+                .to_u128_raw(),))),
+        );
+        vm.set_sender(USER);
+        assert_eq!(daxos.cancel_order(index).unwrap_err(), b"Transfer failed");
+
+        let refund = U256::from(400_000_000);
+        mock_transfer(
+            &vm,
+            IERC20::transferCall {
+                to: USER,
+                value: refund,
+            }
+            .abi_encode(),
+        );
+        daxos.custody.setter(index).set(U256::from(1_000_000_000));
+        assert_eq!(daxos.cancel_order(index).unwrap(), 400_000_000);
+        assert_eq!(daxos.get_custody(index), U256::from(600_000_000));
+
+        let logs = vm.get_emitted_logs();
+        let (topics, data) = logs.last().unwrap();
+        let event = OrderCancelled::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+        assert_eq!(event.user, USER);
+        assert_eq!(event.collateral_amount, 400_000_000);
+
+        // Refund cannot exceed collateral held for the Vault
+        daxos.custody.setter(index).set(U256::from(100_000_000));
+        assert_eq!(
+            daxos.cancel_order(index).unwrap_err(),
+            b"Insufficient custody"
+        );
+    }
+
+    #[test]
+    fn test_submit_sell_order() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_daxos(&vm);
+        daxos.custody.setter(index).set(U256::from(1_000_000_000));
+
+        let sell_order_id = 10004;
+        let quantity = amount_vec![10].data[0].to_u128_raw();
+        vm.mock_call(
+            VAULT,
+            IVault::submitSellOrderCall {
+                user: USER,
+                quantity,
+            }
+            .abi_encode(),
+            Ok(IVault::submitSellOrderCall::abi_encode_returns(&(
+                sell_order_id,
+            ))),
+        );
+        mock_program(&vm, EXECUTE_SELL_ORDER, 12);
+        mock_program(&vm, MATCH_MARKET, 13);
+
+        // Sell order executes 10 ITP returning 890.1 collateral
+        mock_vector(
+            &vm,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            amount_vec![10, 0, 890.1].to_vec(),
+        );
+
+        // User must hold ITP being burned
+        let burn = IVault::burnCall {
+            from: USER,
+            value: U256::from(quantity),
+        };
+        vm.mock_call(
+            VAULT,
+            burn.abi_encode(),
+            Err(b"Insufficient balance".to_vec()),
+        );
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.submit_sell_order(index, quantity).unwrap_err(),
+            b"Insufficient balance"
+        );
+
+        vm.mock_call(VAULT, burn.abi_encode(), Ok(Vec::new()));
+
+        // ITP Token supply must match supply updated by DeVIL, which is left
+        // with 20 ITP of 30 ITP
+        let supply_id = VAULT_IDS[4];
+        mock_vector(&vm, supply_id, amount_vec![20].to_vec());
+        mock_total_supply(&vm, U256::from(amount_vec![30].data[0].to_u128_raw()));
+        assert_eq!(
+            daxos.submit_sell_order(index, quantity).unwrap_err(),
+            b"Supply mismatch"
+        );
+        mock_total_supply(&vm, U256::from(amount_vec![20].data[0].to_u128_raw()));
+        mock_transfer(
+            &vm,
+            IERC20::transferCall {
+                to: USER,
+                value: U256::from(890_100_000),
+            }
+            .abi_encode(),
+        );
+        assert_eq!(
+            daxos.submit_sell_order(index, quantity).unwrap(),
+            890_100_000
+        );
+        assert_eq!(daxos.get_custody(index), U256::from(109_900_000));
+
+        // Order of 20 ITP is filled up to capacity of 10 ITP, and only filled
+        // quantity is burned
+        let quantity = amount_vec![20].data[0].to_u128_raw();
+        vm.mock_call(
+            VAULT,
+            IVault::submitSellOrderCall {
+                user: USER,
+                quantity,
+            }
+            .abi_encode(),
+            Ok(IVault::submitSellOrderCall::abi_encode_returns(&(
+                sell_order_id,
+            ))),
+        );
+        mock_vector(
+            &vm,
+            EXECUTED_INDEX_QUANTITIES_ID,
+            amount_vec![10, 10, 89.1].to_vec(),
+        );
+        vm.mock_call(VAULT, burn.abi_encode(), Err(b"Stop".to_vec()));
+        assert_eq!(
+            daxos.submit_sell_order(index, quantity).unwrap_err(),
+            b"Stop"
+        );
+        vm.mock_call(VAULT, burn.abi_encode(), Ok(Vec::new()));
+        mock_transfer(
+            &vm,
+            IERC20::transferCall {
+                to: USER,
+                value: U256::from(89_100_000),
+            }
+            .abi_encode(),
+        );
+        assert_eq!(
+            daxos.submit_sell_order(index, quantity).unwrap(),
+            89_100_000
+        );
+        assert_eq!(daxos.get_custody(index), U256::from(20_800_000));
+    }
+
+    #[test]
+    fn test_vault_lifecycle() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_proposed(&vm);
+        assert_eq!(daxos.get_vault_state(index), VAULT_PROPOSED);
+        assert_eq!(vault_states(&vm), vec![VAULT_PROPOSED]);
+
+        // Proposed Vault does not accept orders
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.submit_order(index, 1_000_000).unwrap_err(),
+            b"Vault Not Active"
+        );
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Vault Not Active");
+
+        // Only DAO may activate Vault
+        assert_eq!(
+            daxos.activate_vault(index).unwrap_err(),
+            b"Must be governor"
+        );
+        vm.set_sender(OWNER);
+        assert_eq!(
+            daxos.activate_vault(index).unwrap_err(),
+            b"Must be governor"
+        );
+        assert_eq!(
+            daxos.pause_vault(index).unwrap_err(),
+            b"Invalid Vault State"
+        );
+        vm.set_sender(DAO);
+        daxos.activate_vault(index).unwrap();
+        assert_eq!(daxos.get_vault_state(index), VAULT_ACTIVE);
+        assert_eq!(
+            daxos.activate_vault(index).unwrap_err(),
+            b"Invalid Vault State"
+        );
+
+        // Active Vault accepts orders, and here it asks for collateral
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.submit_order(index, 1_000_000).unwrap_err(),
+            b"Transfer failed"
+        );
+
+        // Owner may pause Vault, and DAO may resume it
+        assert_eq!(daxos.pause_vault(index).unwrap_err(), b"Mut be owner");
+        vm.set_sender(OWNER);
+        daxos.pause_vault(index).unwrap();
+        assert_eq!(daxos.get_vault_state(index), VAULT_PAUSED);
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.submit_order(index, 1_000_000).unwrap_err(),
+            b"Vault Not Active"
+        );
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Vault Not Active");
+        vm.set_sender(DAO);
+        daxos.activate_vault(index).unwrap();
+        daxos.pause_vault(index).unwrap();
+
+        // Retired Vault cannot be activated again
+        vm.set_sender(OWNER);
+        assert_eq!(daxos.retire_vault(index).unwrap_err(), b"Must be governor");
+        vm.set_sender(DAO);
+        daxos.retire_vault(index).unwrap();
+        assert_eq!(daxos.get_vault_state(index), VAULT_RETIRED);
+        assert_eq!(
+            daxos.activate_vault(index).unwrap_err(),
+            b"Invalid Vault State"
+        );
+        assert_eq!(
+            daxos.retire_vault(index).unwrap_err(),
+            b"Invalid Vault State"
+        );
+
+        // Retired Vault neither accepts nor executes buy orders
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.submit_order(index, 1_000_000).unwrap_err(),
+            b"Vault Not Active"
+        );
+        assert_eq!(daxos.execute_batch(index).unwrap_err(), b"Vault Not Active");
+
+        // Each transition is logged
+        assert_eq!(
+            vault_states(&vm),
+            vec![
+                VAULT_PROPOSED,
+                VAULT_ACTIVE,
+                VAULT_PAUSED,
+                VAULT_ACTIVE,
+                VAULT_PAUSED,
+                VAULT_RETIRED
+            ]
+        );
+
+        // Unknown Vault has no state
+        vm.set_sender(DAO);
+        assert_eq!(
+            daxos.activate_vault(U128::from(2)).unwrap_err(),
+            b"Invalid Vault State"
+        );
+    }
+
+    #[test]
+    fn test_vault_index() {
+        let vm = TestVM::default();
+        let (mut daxos, index) = setup_proposed(&vm);
+        let assets = label_vec![51, 53, 54].to_vec();
+        let weights = amount_vec![0.1, 1, 100].to_vec();
+        let fees = amount_vec![0.005, 0.01, 0.0001].to_vec();
+
+        // Only owner submits index, and Vault is asked to store it
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos
+                .submit_vault_index(index, assets.clone(), weights.clone(), fees.clone())
+                .unwrap_err(),
+            b"Mut be owner"
+        );
+        vm.set_sender(OWNER);
+        let submit = IVault::submitIndexCall {
+            assets: assets.clone(),
+            weights: weights.clone(),
+            fees: fees.clone(),
+        }
+        .abi_encode();
+        vm.mock_call(VAULT, submit.clone(), Err(b"Stop".to_vec()));
+        assert_eq!(
+            daxos
+                .submit_vault_index(index, assets.clone(), weights.clone(), fees.clone())
+                .unwrap_err(),
+            b"Stop"
+        );
+        vm.mock_call(VAULT, submit, Ok(Vec::new()));
+        daxos
+            .submit_vault_index(index, assets.clone(), weights.clone(), fees.clone())
+            .unwrap();
+
+        // Index cannot change once Vault is active...
+        vm.set_sender(DAO);
+        daxos.activate_vault(index).unwrap();
+        vm.set_sender(OWNER);
+        assert_eq!(
+            daxos
+                .submit_vault_index(index, assets, weights, fees.clone())
+                .unwrap_err(),
+            b"Invalid Vault State"
+        );
+
+        // ...while its fees can
+        let set_fees = IVault::setFeesCall { fees: fees.clone() }.abi_encode();
+        vm.mock_call(VAULT, set_fees.clone(), Err(b"Stop".to_vec()));
+        assert_eq!(
+            daxos.set_vault_fees(index, fees.clone()).unwrap_err(),
+            b"Stop"
+        );
+        vm.mock_call(VAULT, set_fees, Ok(Vec::new()));
+        vm.set_sender(USER);
+        assert_eq!(
+            daxos.set_vault_fees(index, fees.clone()).unwrap_err(),
+            b"Mut be owner"
+        );
+        vm.set_sender(OWNER);
+        daxos.set_vault_fees(index, fees).unwrap();
+    }
+}
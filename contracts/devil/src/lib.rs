@@ -7,25 +7,45 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 
-use alloy_primitives::{Address, U128};
-use deli::{labels::Labels, vector::Vector};
+use alloy_primitives::{Address, B256, U128};
+use deli::{
+    events::{OperatorSet, ProgramExecuted, ProgramInstalled, VectorDeleted, VectorStored},
+    labels::Labels,
+    matrix::Matrix,
+    vector::Vector,
+    vis::{namespace, NAMESPACE_MASK},
+};
 use stylus_sdk::{
     prelude::*,
-    storage::{StorageAddress, StorageBytes, StorageMap},
+    storage::{
+        StorageAddress, StorageB256, StorageBool, StorageBytes, StorageMap, StorageU128, StorageVec,
+    },
 };
 
-use crate::program::{ErrorCode, Program, VectorIO};
+use crate::program::{Arity, ErrorCode, Program, VectorIO};
 
 pub mod program;
 
 #[cfg(test)]
 pub mod test;
 
+/// Registered stored procedure
+#[storage]
+pub struct Procedure {
+    name: StorageB256,
+    num_inputs: StorageU128,
+    num_outputs: StorageU128,
+    num_registers: StorageU128,
+}
+
 #[storage]
 #[entrypoint]
 pub struct Devil {
     owner: StorageAddress,
     vectors: StorageMap<U128, StorageBytes>,
+    programs: StorageMap<U128, Procedure>, // Mapping = {Program Id => Procedure}
+    versions: StorageMap<B256, StorageVec<StorageU128>>, // Mapping = {Program Name => [Program Id; num_versions]}
+    operators: StorageMap<Address, StorageBool>,
 }
 
 impl Devil {
@@ -36,6 +56,93 @@ impl Devil {
         }
         Ok(())
     }
+
+    fn check_operator(&self, address: Address) -> Result<(), Vec<u8>> {
+        if self.operators.get(address) {
+            return Ok(());
+        }
+        self.check_owner(address)
+    }
+
+    /// Operators may only write ids in their namespace, while owner may
+    /// write any id (see `deli::vis::namespace`)
+    fn is_writable(&self, id: U128) -> bool {
+        let caller = self.vm().msg_sender();
+        let owner = self.owner.get();
+        if owner.is_zero() || caller == owner {
+            return true;
+        }
+        id.to::<u128>() & NAMESPACE_MASK == namespace(caller.into_array())
+    }
+
+    fn check_writable(&self, id: U128) -> Result<(), Vec<u8>> {
+        if !self.is_writable(id) {
+            Err(b"Outside namespace")?;
+        }
+        Ok(())
+    }
+
+    fn is_program(&self, id: U128) -> bool {
+        !self.programs.getter(id).name.get().is_zero()
+    }
+
+    fn check_not_program(&self, id: U128) -> Result<(), Vec<u8>> {
+        // Code of registered programs can only be changed by installing new version
+        if self.is_program(id) {
+            Err(b"Program locked")?;
+        }
+        Ok(())
+    }
+
+    fn run_program(
+        &mut self,
+        caller: Address,
+        code: Vec<u8>,
+        num_registers: usize,
+        args: Vec<u128>,
+    ) -> Result<(), Vec<u8>> {
+        let program_hash = self.vm().native_keccak256(&code);
+        let gas_start = self.vm().evm_gas_left();
+        let mut program = Program::with_args(self, args);
+        program
+            .execute(code, num_registers)
+            .map_err(|_| b"Program error")?;
+        let gas_used = gas_start.saturating_sub(self.vm().evm_gas_left());
+        log(
+            self.vm(),
+            ProgramExecuted {
+                program_hash,
+                caller,
+                gas_used,
+            },
+        );
+        Ok(())
+    }
+
+    fn set_vector(&mut self, id: U128, data: Vec<u8>) {
+        let hash = self.vm().native_keccak256(&data);
+        let length = data.len() as u128;
+        self.vectors.setter(id).set_bytes(data);
+        log(
+            self.vm(),
+            VectorStored {
+                id: id.to(),
+                hash,
+                length,
+            },
+        );
+    }
+
+    /// Store blob produced by VIL program, which must not overwrite code of
+    /// registered programs nor ids outside of caller's namespace
+    fn store(&mut self, id: u128, data: Vec<u8>) -> Result<(), ErrorCode> {
+        let id = U128::from(id);
+        if self.is_program(id) || !self.is_writable(id) {
+            Err(ErrorCode::Locked)?;
+        }
+        self.set_vector(id, data);
+        Ok(())
+    }
 }
 
 impl VectorIO for Devil {
@@ -55,52 +162,243 @@ impl VectorIO for Devil {
         Ok(Vector::from_vec(vector.get_bytes()))
     }
 
+    fn load_matrix(&self, id: u128) -> Result<Matrix, ErrorCode> {
+        let matrix = self.vectors.getter(U128::from(id));
+        if matrix.is_empty() {
+            Err(ErrorCode::NotFound)?;
+        }
+        Matrix::try_from_vec(matrix.get_bytes()).ok_or(ErrorCode::InvalidOperand)
+    }
+
+    fn load_arity(&self, id: u128) -> Result<Option<Arity>, ErrorCode> {
+        let program = self.programs.getter(U128::from(id));
+        if program.name.get().is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(Arity {
+            num_inputs: program.num_inputs.get().to(),
+            num_outputs: program.num_outputs.get().to(),
+            num_registers: program.num_registers.get().to(),
+        }))
+    }
+
     fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode> {
-        let mut vector = self.vectors.setter(U128::from(id));
-        vector.set_bytes(input.to_vec());
-        Ok(())
+        self.store(id, input.to_vec())
     }
 
     fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode> {
-        let mut vector = self.vectors.setter(U128::from(id));
-        vector.set_bytes(input.to_vec());
-        Ok(())
+        self.store(id, input.to_vec())
+    }
+
+    fn store_matrix(&mut self, id: u128, input: Matrix) -> Result<(), ErrorCode> {
+        self.store(id, input.to_vec())
     }
 }
 
 #[public]
 impl Devil {
-    pub fn setup(&mut self, owner: Address)  -> Result<(), Vec<u8>> {
+    pub fn setup(&mut self, owner: Address) -> Result<(), Vec<u8>> {
         // Note it's cheaper in terms of KiB to not use contructor
         self.check_owner(self.vm().msg_sender())?;
         self.owner.set(owner);
         Ok(())
     }
 
-    pub fn submit(&mut self, id: U128, data: Vec<u8>) -> Result<(), Vec<u8>> {
+    /// Grant or revoke operator access (owner only)
+    ///
+    /// Operators may read any id, but write only ids in their namespace, which
+    /// also applies to vectors stored by programs they execute.
+    pub fn set_operator(&mut self, operator: Address, enabled: bool) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().msg_sender())?;
+        self.operators.setter(operator).set(enabled);
+        log(self.vm(), OperatorSet { operator, enabled });
+        Ok(())
+    }
+
+    pub fn submit(&mut self, id: U128, data: Vec<u8>) -> Result<(), Vec<u8>> {
+        self.check_operator(self.vm().msg_sender())?;
+        self.check_writable(id)?;
         // Note it's cheaper in terms of KiB to limit public interface
-        let mut vector = self.vectors.setter(id);
-        if !vector.is_empty() {
+        if !self.vectors.getter(id).is_empty() {
             Err(b"Duplicate data")?;
         }
-        vector.set_bytes(data);
+        self.set_vector(id, data);
+        Ok(())
+    }
+
+    /// Submit multiple vectors at once
+    ///
+    /// Same as `submit()` for each pair of `ids[i]` and `data[i]`, i.e. none of
+    /// the ids may already hold data.
+    pub fn submit_many(&mut self, ids: Vec<U128>, data: Vec<Vec<u8>>) -> Result<(), Vec<u8>> {
+        self.check_operator(self.vm().msg_sender())?;
+        if ids.len() != data.len() {
+            Err(b"Length mismatch")?;
+        }
+        for id in &ids {
+            self.check_writable(*id)?;
+            if !self.vectors.getter(*id).is_empty() {
+                Err(b"Duplicate data")?;
+            }
+        }
+        for (id, data) in ids.into_iter().zip(data) {
+            self.set_vector(id, data);
+        }
+        Ok(())
+    }
+
+    /// Overwrite existing vector
+    ///
+    /// When `expected_hash` is non-zero, update is only performed if keccak256
+    /// of currently stored data matches it (compare-and-swap), which protects
+    /// from overwriting changes made by VIL programs since data was last read.
+    pub fn update(&mut self, id: U128, data: Vec<u8>, expected_hash: B256) -> Result<(), Vec<u8>> {
+        self.check_operator(self.vm().msg_sender())?;
+        self.check_writable(id)?;
+        let current = self.vectors.getter(id).get_bytes();
+        if current.is_empty() {
+            Err(b"No data")?;
+        }
+        self.check_not_program(id)?;
+        if !expected_hash.is_zero() && self.vm().native_keccak256(&current) != expected_hash {
+            Err(b"Hash mismatch")?;
+        }
+        self.set_vector(id, data);
+        Ok(())
+    }
+
+    /// Delete vector clearing its storage (refunds gas)
+    pub fn delete(&mut self, id: U128) -> Result<(), Vec<u8>> {
+        self.check_operator(self.vm().msg_sender())?;
+        self.check_writable(id)?;
+        if self.vectors.getter(id).is_empty() {
+            Err(b"No data")?;
+        }
+        self.check_not_program(id)?;
+        self.vectors.delete(id);
+        log(self.vm(), VectorDeleted { id: id.to() });
         Ok(())
     }
 
     pub fn get(&self, id: U128) -> Result<Vec<u8>, Vec<u8>> {
-        self.check_owner(self.vm().msg_sender())?;
+        self.check_operator(self.vm().msg_sender())?;
         let vector = self.vectors.getter(id);
-        if !vector.is_empty() {
+        if vector.is_empty() {
             Err(b"No data")?;
         }
         Ok(vector.get_bytes())
     }
 
-    pub fn execute(&mut self, code: Vec<u8>, num_registry: u128) -> Result<(), Vec<u8>> {
+    /// Install new version of stored procedure
+    ///
+    /// Procedure `code` is stored under `id`, which must not hold any data.
+    /// The `name` (ASCII procedure name padded with zeros, see
+    /// `icore::vil::names`) then resolves to this latest version, while
+    /// previous versions remain installed so that programs referring to them
+    /// by id continue to work. Declared number of inputs, outputs and
+    /// registers is verified by `B` and `FOLD`.
+    ///
+    /// Returns version number of installed procedure starting from 1.
+    pub fn install_program(
+        &mut self,
+        name: B256,
+        id: U128,
+        code: Vec<u8>,
+        num_inputs: U128,
+        num_outputs: U128,
+        num_registers: U128,
+    ) -> Result<U128, Vec<u8>> {
+        // Installing procedures is governed by the owner
         self.check_owner(self.vm().msg_sender())?;
-        let mut program = Program::new(self);
-        program.execute(code, num_registry as usize).map_err(|_| b"Program error")?;
-        Ok(())
+        if name.is_zero() {
+            Err(b"Invalid name")?;
+        }
+        if !self.vectors.getter(id).is_empty() {
+            Err(b"Duplicate data")?;
+        }
+        self.set_vector(id, code);
+
+        let mut program = self.programs.setter(id);
+        program.name.set(name);
+        program.num_inputs.set(num_inputs);
+        program.num_outputs.set(num_outputs);
+        program.num_registers.set(num_registers);
+
+        let mut versions = self.versions.setter(name);
+        versions.push(id);
+        let version = U128::from(versions.len());
+
+        log(
+            self.vm(),
+            ProgramInstalled {
+                name,
+                id: id.to(),
+                version: version.to(),
+                num_inputs: num_inputs.to(),
+                num_outputs: num_outputs.to(),
+                num_registers: num_registers.to(),
+            },
+        );
+        Ok(version)
+    }
+
+    /// Get id and version number of latest version of procedure
+    pub fn get_program(&self, name: B256) -> Result<(U128, U128), Vec<u8>> {
+        let versions = self.versions.getter(name);
+        let version = versions.len();
+        if version == 0 {
+            Err(b"No program")?;
+        }
+        let id = versions.get(version - 1).unwrap_or_default();
+        Ok((id, U128::from(version)))
+    }
+
+    /// Get id of given version of procedure
+    pub fn get_program_version(&self, name: B256, version: U128) -> Result<U128, Vec<u8>> {
+        let index = version
+            .checked_sub(U128::from(1))
+            .ok_or_else(|| b"No program".to_vec())?;
+        let id = self
+            .versions
+            .getter(name)
+            .get(index)
+            .ok_or_else(|| b"No program".to_vec())?;
+        Ok(id)
+    }
+
+    /// Get declared number of inputs, outputs and registers of procedure
+    pub fn get_program_arity(&self, id: U128) -> Result<(U128, U128, U128), Vec<u8>> {
+        let program = self.programs.getter(id);
+        if program.name.get().is_zero() {
+            Err(b"No program")?;
+        }
+        Ok((
+            program.num_inputs.get(),
+            program.num_outputs.get(),
+            program.num_registers.get(),
+        ))
+    }
+
+    pub fn execute(&mut self, code: Vec<u8>, num_registry: u128) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.check_operator(caller)?;
+        self.run_program(caller, code, num_registry as usize, Vec::new())
+    }
+
+    /// Execute stored procedure
+    ///
+    /// Procedure must be installed via `install_program()`, and it is executed
+    /// with declared number of registers. The `args` are bound to parametric
+    /// storage ids `arg(0)..arg(n)` and to registers R0-Rn as Scalar values.
+    pub fn execute_stored(&mut self, program_id: U128, args: Vec<U128>) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        self.check_operator(caller)?;
+        let num_registers = match self.load_arity(program_id.to()) {
+            Ok(Some(arity)) => arity.num_registers,
+            _ => Err(b"No program")?,
+        };
+        let code = self.vectors.getter(program_id).get_bytes();
+        let args = args.into_iter().map(|x| x.to()).collect();
+        self.run_program(caller, code, num_registers, args)
     }
 }
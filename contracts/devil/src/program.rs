@@ -4,7 +4,16 @@ use core::mem::swap;
 use core::fmt::Debug;
 
 use alloc::vec::Vec;
-use deli::{amount::Amount, labels::Labels, log_msg, vector::Vector, vis::*};
+use deli::{
+    amount::Amount,
+    asset::{get_asset_id, get_side, make_asset},
+    labels::Labels,
+    log_msg,
+    math::checked_dot,
+    matrix::Matrix,
+    vector::Vector,
+    vis::*,
+};
 
 pub enum ErrorCode {
     StackUnderflow,
@@ -16,6 +25,9 @@ pub enum ErrorCode {
     NotAligned,
     MathUnderflow,
     MathOverflow,
+    InvalidArity,
+    NotSorted,
+    Locked,
 }
 
 #[cfg(test)]
@@ -31,16 +43,52 @@ impl Debug for ErrorCode {
             Self::NotAligned => write!(f, "NotAligned"),
             Self::MathUnderflow => write!(f, "MathUnderflow"),
             Self::MathOverflow => write!(f, "MathOverflow"),
+            Self::InvalidArity => write!(f, "InvalidArity"),
+            Self::NotSorted => write!(f, "NotSorted"),
+            Self::Locked => write!(f, "Locked"),
         }
     }
 }
 
+/// Declared number of inputs, outputs and registers of a registered program
+pub struct Arity {
+    pub num_inputs: usize,
+    pub num_outputs: usize,
+    pub num_registers: usize,
+}
+
+impl Arity {
+    /// Verify that program is called with declared number of inputs and
+    /// outputs, and that it is given at least as many registers as it needs.
+    fn check(
+        &self,
+        num_inputs: usize,
+        num_outputs: usize,
+        num_registers: usize,
+    ) -> Result<(), ErrorCode> {
+        if num_inputs != self.num_inputs
+            || num_outputs != self.num_outputs
+            || num_registers < self.num_registers
+        {
+            Err(ErrorCode::InvalidArity)?;
+        }
+        Ok(())
+    }
+}
+
 pub trait VectorIO {
     fn load_labels(&self, id: u128) -> Result<Labels, ErrorCode>;
     fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode>;
 
     fn store_labels(&mut self, id: u128, input: Labels) -> Result<(), ErrorCode>;
     fn store_vector(&mut self, id: u128, input: Vector) -> Result<(), ErrorCode>;
+
+    fn load_matrix(&self, id: u128) -> Result<Matrix, ErrorCode>;
+    fn store_matrix(&mut self, id: u128, input: Matrix) -> Result<(), ErrorCode>;
+
+    /// Load declared arity of program stored under `id`, or `None` if program
+    /// is not registered.
+    fn load_arity(&self, id: u128) -> Result<Option<Arity>, ErrorCode>;
 }
 
 pub struct Program<'vio, VIO>
@@ -48,6 +96,7 @@ where
     VIO: VectorIO,
 {
     vio: &'vio mut VIO,
+    args: Vec<u128>,
 }
 
 enum Operand {
@@ -56,6 +105,7 @@ enum Operand {
     Vector(Vector),
     Scalar(Amount),
     Label(u128),
+    Matrix(Matrix),
 }
 
 impl Clone for Operand {
@@ -68,8 +118,12 @@ impl Clone for Operand {
             Operand::Vector(x) => Operand::Vector(Vector {
                 data: x.data.clone(),
             }),
-            Operand::Scalar(x) => Operand::Scalar(x.clone()),
-            Operand::Label(x) => Operand::Label(x.clone()),
+            Operand::Scalar(x) => Operand::Scalar(*x),
+            Operand::Label(x) => Operand::Label(*x),
+            Operand::Matrix(x) => Operand::Matrix(Matrix {
+                num_cols: x.num_cols,
+                data: x.data.clone(),
+            }),
         }
     }
 }
@@ -141,6 +195,75 @@ macro_rules! impl_devil_binary_op {
     };
 }
 
+macro_rules! impl_devil_compare_op {
+    (
+        $fn_name:ident,
+        $cmp_op:ident
+    ) => {
+        fn $fn_name(&mut self, pos: usize) -> Result<(), ErrorCode> {
+            let mask = |x1: &Amount, x2: &Amount| {
+                if x1.$cmp_op(x2) {
+                    Amount::ONE
+                } else {
+                    Amount::ZERO
+                }
+            };
+            let stack_index = self.get_stack_index(pos)?;
+            let (v1, rest) = self
+                .stack
+                .split_last_mut()
+                .ok_or(ErrorCode::StackUnderflow)?;
+            let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+            match (v1, v2) {
+                (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
+                    if v1.data.len() != v2.data.len() {
+                        Err(ErrorCode::NotAligned)?;
+                    }
+                    for (x1, x2) in v1.data.iter_mut().zip(v2.data.iter()) {
+                        *x1 = mask(x1, x2);
+                    }
+                }
+                (Operand::Vector(ref mut v1), Operand::Scalar(ref x2)) => {
+                    for x1 in v1.data.iter_mut() {
+                        *x1 = mask(x1, x2);
+                    }
+                }
+                (Operand::Scalar(ref mut x1), Operand::Scalar(ref x2)) => {
+                    *x1 = mask(x1, x2);
+                }
+                _ => {
+                    Err(ErrorCode::InvalidOperand)?;
+                }
+            }
+            Ok(())
+        }
+    };
+}
+
+fn slice_of<T: Copy>(data: &[T], from: usize, len: usize) -> Result<Vec<T>, ErrorCode> {
+    let end = from.checked_add(len).ok_or(ErrorCode::OutOfRange)?;
+    let res = data.get(from..end).ok_or(ErrorCode::OutOfRange)?;
+    Ok(res.to_vec())
+}
+
+fn gather_from<T: Copy>(data: &[T], indices: &[u128]) -> Result<Vec<T>, ErrorCode> {
+    let mut res = Vec::with_capacity(indices.len());
+    for &i in indices {
+        res.push(*data.get(i as usize).ok_or(ErrorCode::OutOfRange)?);
+    }
+    Ok(res)
+}
+
+fn scatter_into<T: Copy>(data: &mut [T], indices: &[u128], values: &[T]) -> Result<(), ErrorCode> {
+    if indices.len() != values.len() {
+        Err(ErrorCode::NotAligned)?;
+    }
+    for (&i, &x) in indices.iter().zip(values) {
+        *data.get_mut(i as usize).ok_or(ErrorCode::OutOfRange)? = x;
+    }
+    Ok(())
+}
+
 impl Stack {
     pub(crate) fn new(num_registers: usize) -> Self {
         let mut registry = Vec::new();
@@ -156,7 +279,7 @@ impl Stack {
     }
 
     fn pop(&mut self) -> Result<Operand, ErrorCode> {
-        let res = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let res = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
         Ok(res)
     }
 
@@ -190,19 +313,13 @@ impl Stack {
     }
 
     fn ldr(&mut self, pos: usize) -> Result<(), ErrorCode> {
-        let v = self
-            .registry
-            .get(pos)
-            .ok_or_else(|| ErrorCode::OutOfRange)?;
+        let v = self.registry.get(pos).ok_or(ErrorCode::OutOfRange)?;
         self.push(v.clone());
         Ok(())
     }
 
     fn ldm(&mut self, pos: usize) -> Result<(), ErrorCode> {
-        let v1 = self
-            .registry
-            .get_mut(pos)
-            .ok_or_else(|| ErrorCode::OutOfRange)?;
+        let v1 = self.registry.get_mut(pos).ok_or(ErrorCode::OutOfRange)?;
         let mut v2 = Operand::None;
         swap(v1, &mut v2);
         self.push(v2);
@@ -210,11 +327,8 @@ impl Stack {
     }
 
     fn op_str(&mut self, pos: usize) -> Result<(), ErrorCode> {
-        let x = self
-            .registry
-            .get_mut(pos)
-            .ok_or_else(|| ErrorCode::OutOfRange)?;
-        *x = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let x = self.registry.get_mut(pos).ok_or(ErrorCode::OutOfRange)?;
+        *x = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
         Ok(())
     }
 
@@ -251,7 +365,7 @@ impl Stack {
     }
 
     fn unpk(&mut self) -> Result<(), ErrorCode> {
-        let v = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
         let mut exp = Vec::new();
         match v {
             Operand::Vector(v) => {
@@ -298,9 +412,9 @@ impl Stack {
             }
         }
         let mut transposed = vec![vec![Amount::ZERO; count]; num_rows];
-        for row in 0..num_rows {
-            for col in 0..count {
-                transposed[row][col] = vectors[col][row];
+        for (row, t) in transposed.iter_mut().enumerate() {
+            for (col, v) in vectors.iter().enumerate() {
+                t[col] = v[row];
             }
         }
 
@@ -311,26 +425,155 @@ impl Stack {
         Ok(())
     }
 
+    fn pkm(&mut self, count: usize) -> Result<(), ErrorCode> {
+        let pos = self.get_stack_offset(count)?;
+
+        let mut rows = Vec::with_capacity(count);
+        for v in self.stack.drain(pos..) {
+            match v {
+                Operand::Vector(v) => rows.push(v),
+                _ => Err(ErrorCode::InvalidOperand)?,
+            }
+        }
+        let res = Matrix::from_rows(rows).ok_or(ErrorCode::NotAligned)?;
+        self.push(Operand::Matrix(res));
+        Ok(())
+    }
+
+    fn unpkm(&mut self) -> Result<(), ErrorCode> {
+        match self.stack.pop().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Matrix(m) => {
+                for row in m.into_rows() {
+                    self.stack.push(Operand::Vector(row));
+                }
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn matvec(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+
+        let m2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+
+        match (v1, m2) {
+            (Operand::Vector(v1), Operand::Matrix(m2)) => {
+                if v1.data.len() != m2.num_cols {
+                    Err(ErrorCode::NotAligned)?;
+                }
+                *v1 = m2.checked_mul_vector(v1).ok_or(ErrorCode::MathOverflow)?;
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn dot(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+
+        match (&v1, v2) {
+            (Operand::Vector(x1), Operand::Vector(x2)) => {
+                if x1.data.len() != x2.data.len() {
+                    Err(ErrorCode::NotAligned)?;
+                }
+                let s = checked_dot(&x1.data, &x2.data).ok_or(ErrorCode::MathOverflow)?;
+                *v1 = Operand::Scalar(s);
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn mrow(&mut self, row: usize) -> Result<(), ErrorCode> {
+        let res = match self.stack.last().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Matrix(m) => m.row(row).ok_or(ErrorCode::OutOfRange)?,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        self.push(Operand::Vector(res));
+        Ok(())
+    }
+
+    fn mcol(&mut self, col: usize) -> Result<(), ErrorCode> {
+        let res = match self.stack.last().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Matrix(m) => m.col(col).ok_or(ErrorCode::OutOfRange)?,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        self.push(Operand::Vector(res));
+        Ok(())
+    }
+
     impl_devil_binary_op!(add, checked_add);
     impl_devil_binary_op!(sub, checked_sub);
     impl_devil_binary_op!(ssb, saturating_sub);
     impl_devil_binary_op!(mul, checked_mul);
     impl_devil_binary_op!(div, checked_div);
+    #[cfg(feature = "amount-exp")]
+    impl_devil_binary_op!(pow, checked_pow);
 
     fn sqrt(&mut self) -> Result<(), ErrorCode> {
-        let v1 = self
-            .stack
-            .last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v1 = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
+        match v1 {
+            Operand::Vector(ref mut v1) => {
+                for i in 0..v1.data.len() {
+                    let x = &mut v1.data[i];
+                    *x = x.checked_sqrt().ok_or(ErrorCode::MathOverflow)?;
+                }
+            }
+            Operand::Scalar(ref mut x) => {
+                *x = x.checked_sqrt().ok_or(ErrorCode::MathOverflow)?;
+            }
+            _ => return Err(ErrorCode::InvalidOperand),
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "amount-exp")]
+    fn exp(&mut self) -> Result<(), ErrorCode> {
+        let v1 = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
+        match v1 {
+            Operand::Vector(ref mut v1) => {
+                for i in 0..v1.data.len() {
+                    let x = &mut v1.data[i];
+                    *x = x.checked_exp().ok_or(ErrorCode::MathOverflow)?;
+                }
+            }
+            Operand::Scalar(ref mut x) => {
+                *x = x.checked_exp().ok_or(ErrorCode::MathOverflow)?;
+            }
+            _ => return Err(ErrorCode::InvalidOperand),
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "amount-exp")]
+    fn ln(&mut self) -> Result<(), ErrorCode> {
+        let v1 = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
         match v1 {
             Operand::Vector(ref mut v1) => {
                 for i in 0..v1.data.len() {
                     let x = &mut v1.data[i];
-                    *x = x.checked_sqrt().ok_or_else(|| ErrorCode::MathOverflow)?;
+                    *x = x.checked_ln().ok_or(ErrorCode::MathOverflow)?;
                 }
             }
             Operand::Scalar(ref mut x) => {
-                *x = x.checked_sqrt().ok_or_else(|| ErrorCode::MathOverflow)?;
+                *x = x.checked_ln().ok_or(ErrorCode::MathOverflow)?;
             }
             _ => return Err(ErrorCode::InvalidOperand),
         }
@@ -338,13 +581,13 @@ impl Stack {
     }
 
     fn vsum(&mut self) -> Result<(), ErrorCode> {
-        let v = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Vector(ref v) => {
                 let mut s = Amount::ZERO;
                 for i in 0..v.data.len() {
                     let x = v.data[i];
-                    s = s.checked_add(x).ok_or_else(|| ErrorCode::MathOverflow)?;
+                    s = s.checked_add(x).ok_or(ErrorCode::MathOverflow)?;
                 }
                 self.stack.push(Operand::Scalar(s));
             }
@@ -356,7 +599,7 @@ impl Stack {
     }
 
     fn vmin(&mut self) -> Result<(), ErrorCode> {
-        let v = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Vector(ref v) => {
                 let mut s = Amount::MAX;
@@ -374,7 +617,7 @@ impl Stack {
     }
 
     fn vmax(&mut self) -> Result<(), ErrorCode> {
-        let v = self.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Vector(ref v) => {
                 let mut s = Amount::ZERO;
@@ -391,13 +634,94 @@ impl Stack {
         Ok(())
     }
 
+    fn cumsum(&mut self) -> Result<(), ErrorCode> {
+        match self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Vector(ref mut v) => {
+                let mut s = Amount::ZERO;
+                for x in v.data.iter_mut() {
+                    s = s.checked_add(*x).ok_or(ErrorCode::MathOverflow)?;
+                    *x = s;
+                }
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn vprod(&mut self) -> Result<(), ErrorCode> {
+        let v = self.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
+        match v {
+            Operand::Vector(ref v) => {
+                let mut s = Amount::ONE;
+                for x in &v.data {
+                    s = s.checked_mul(*x).ok_or(ErrorCode::MathOverflow)?;
+                }
+                self.stack.push(Operand::Scalar(s));
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    impl_devil_compare_op!(gt, gt);
+    impl_devil_compare_op!(lt, lt);
+    impl_devil_compare_op!(eq, eq);
+
+    fn select(&mut self, pos_a: usize, pos_b: usize) -> Result<(), ErrorCode> {
+        let index_a = self.get_stack_index(pos_a)?;
+        let index_b = self.get_stack_index(pos_b)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let a = rest.get(index_a).ok_or(ErrorCode::OutOfRange)?;
+        let b = rest.get(index_b).ok_or(ErrorCode::OutOfRange)?;
+
+        // Component of Vector, or Scalar broadcast to all components
+        let pick = |operand: &Operand, i: usize, len: usize| match operand {
+            Operand::Vector(v) if v.data.len() == len => Ok(v.data[i]),
+            Operand::Vector(_) => Err(ErrorCode::NotAligned),
+            Operand::Scalar(x) => Ok(*x),
+            _ => Err(ErrorCode::InvalidOperand),
+        };
+
+        match v1 {
+            Operand::Vector(ref mut mask) => {
+                let len = mask.data.len();
+                for (i, x) in mask.data.iter_mut().enumerate() {
+                    *x = if x.is_not() {
+                        pick(b, i, len)?
+                    } else {
+                        pick(a, i, len)?
+                    };
+                }
+            }
+            Operand::Scalar(ref mut x) => match (a, b) {
+                (Operand::Scalar(xa), Operand::Scalar(xb)) => {
+                    *x = if x.is_not() { *xb } else { *xa };
+                }
+                _ => {
+                    Err(ErrorCode::InvalidOperand)?;
+                }
+            },
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
     fn min(&mut self, pos: usize) -> Result<(), ErrorCode> {
         let stack_index = self.get_stack_index(pos)?;
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
-        let v2 = rest.get(stack_index).ok_or_else(|| ErrorCode::OutOfRange)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
         match (v1, v2) {
             (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
                 if v1.data.len() != v2.data.len() {
@@ -424,8 +748,8 @@ impl Stack {
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
-        let v2 = rest.get(stack_index).ok_or_else(|| ErrorCode::OutOfRange)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
         match (v1, v2) {
             (Operand::Vector(ref mut v1), Operand::Vector(ref v2)) => {
                 if v1.data.len() != v2.data.len() {
@@ -492,10 +816,7 @@ impl Stack {
     }
 
     fn vpush(&mut self, value: u128) -> Result<(), ErrorCode> {
-        let v = self
-            .stack
-            .last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Vector(ref mut v) => {
                 v.data.push(Amount::from_u128_raw(value));
@@ -506,10 +827,7 @@ impl Stack {
     }
 
     fn lpush(&mut self, value: u128) -> Result<(), ErrorCode> {
-        let v = self
-            .stack
-            .last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Labels(ref mut v) => {
                 v.data.push(value);
@@ -520,13 +838,10 @@ impl Stack {
     }
 
     fn vpop(&mut self) -> Result<(), ErrorCode> {
-        let v = self
-            .stack
-            .last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Vector(ref mut v) => {
-                let val = v.data.pop().ok_or_else(|| ErrorCode::OutOfRange)?;
+                let val = v.data.pop().ok_or(ErrorCode::OutOfRange)?;
                 self.stack.push(Operand::Scalar(val));
             }
             _ => Err(ErrorCode::InvalidOperand)?,
@@ -535,13 +850,10 @@ impl Stack {
     }
 
     fn lpop(&mut self) -> Result<(), ErrorCode> {
-        let v = self
-            .stack
-            .last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+        let v = self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)?;
         match v {
             Operand::Labels(ref mut v) => {
-                let val = v.data.pop().ok_or_else(|| ErrorCode::OutOfRange)?;
+                let val = v.data.pop().ok_or(ErrorCode::OutOfRange)?;
                 self.stack.push(Operand::Label(val));
             }
             _ => Err(ErrorCode::InvalidOperand)?,
@@ -560,55 +872,258 @@ impl Stack {
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
-        let v2 = rest.get_mut(pos).ok_or_else(|| ErrorCode::OutOfRange)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get_mut(pos).ok_or(ErrorCode::OutOfRange)?;
         swap(v1, v2);
         Ok(())
     }
 
+    fn slice(&mut self, from: usize, len: usize) -> Result<(), ErrorCode> {
+        match self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Vector(ref mut v) => v.data = slice_of(&v.data, from, len)?,
+            Operand::Labels(ref mut v) => v.data = slice_of(&v.data, from, len)?,
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn gather(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        let res = match (&v1, v2) {
+            (Operand::Labels(indices), Operand::Vector(v2)) => Operand::Vector(Vector {
+                data: gather_from(&v2.data, &indices.data)?,
+            }),
+            (Operand::Labels(indices), Operand::Labels(v2)) => Operand::Labels(Labels {
+                data: gather_from(&v2.data, &indices.data)?,
+            }),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        *v1 = res;
+        Ok(())
+    }
+
+    fn scatter(&mut self, pos_values: usize, pos_indices: usize) -> Result<(), ErrorCode> {
+        let index_values = self.get_stack_index(pos_values)?;
+        let index_indices = self.get_stack_index(pos_indices)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let values = rest.get(index_values).ok_or(ErrorCode::OutOfRange)?;
+        let indices = match rest.get(index_indices).ok_or(ErrorCode::OutOfRange)? {
+            Operand::Labels(indices) => indices,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        match (v1, values) {
+            (Operand::Vector(ref mut v1), Operand::Vector(v2)) => {
+                scatter_into(&mut v1.data, &indices.data, &v2.data)?;
+            }
+            (Operand::Labels(ref mut v1), Operand::Labels(v2)) => {
+                scatter_into(&mut v1.data, &indices.data, &v2.data)?;
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn concat(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        match (v1, v2) {
+            (Operand::Vector(ref mut v1), Operand::Vector(v2)) => {
+                v1.data.extend_from_slice(&v2.data);
+            }
+            (Operand::Labels(ref mut v1), Operand::Labels(v2)) => {
+                v1.data.extend_from_slice(&v2.data);
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
     fn lunion(&mut self, pos: usize) -> Result<(), ErrorCode> {
         let stack_index = self.get_stack_index(pos)?;
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
 
-        let v2 = rest.get(stack_index).ok_or_else(|| ErrorCode::OutOfRange)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
 
         match (v1, v2) {
             (Operand::Labels(labels_a), Operand::Labels(labels_b)) => {
-                let mut result = Vec::new();
-                let mut j = 0;
-                for i in 0..labels_a.data.len() {
-                    let label_a = labels_a.data[i];
-                    let mut updated = false;
-                    while j < labels_b.data.len() {
-                        updated = true;
-                        let label_b = labels_b.data[j];
-                        if label_b < label_a {
-                            result.push(label_b);
-                            j += 1;
-                            continue;
-                        } else if label_a < label_b {
-                            result.push(label_a);
-                            break;
-                        } else {
-                            result.push(label_a);
-                            j += 1;
-                            break;
-                        }
-                    }
-                    if !updated {
-                        result.push(label_a);
-                    }
+                let (result, _, _) = labels_a.union(labels_b);
+                *labels_a = result;
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn linter(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        match (v1, v2) {
+            (Operand::Labels(labels_a), Operand::Labels(labels_b)) => {
+                *labels_a = labels_a.intersection(labels_b);
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn ldiff(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        match (v1, v2) {
+            (Operand::Labels(labels_a), Operand::Labels(labels_b)) => {
+                *labels_a = labels_a.difference(labels_b);
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn lfind(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let v2 = rest.get(stack_index).ok_or(ErrorCode::OutOfRange)?;
+        let found = match (&v1, v2) {
+            (Operand::Label(label), Operand::Labels(labels)) => labels.position(*label),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        let (position, flag) = match found {
+            Some(i) => (i as u128, Amount::ONE),
+            None => (0, Amount::ZERO),
+        };
+        *v1 = Operand::Scalar(Amount::from_u128_with_scale(position, 0));
+        self.push(Operand::Scalar(flag));
+        Ok(())
+    }
+
+    fn lsort(&mut self, pos: usize) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let labels = match v1 {
+            Operand::Labels(labels) => labels,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        if pos == 0 {
+            labels.sort();
+            return Ok(());
+        }
+        match rest.get_mut(stack_index).ok_or(ErrorCode::OutOfRange)? {
+            Operand::Vector(v2) => {
+                if v2.data.len() != labels.data.len() {
+                    Err(ErrorCode::NotAligned)?;
                 }
-                labels_a.data = result;
+                let permutation = labels.sort();
+                v2.data = permutation.iter().map(|&i| v2.data[i]).collect();
             }
             _ => Err(ErrorCode::InvalidOperand)?,
         }
         Ok(())
     }
 
+    fn lcheck(&mut self) -> Result<(), ErrorCode> {
+        match self.stack.last().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Labels(labels) => {
+                if !labels.is_sorted() {
+                    Err(ErrorCode::NotSorted)?;
+                }
+            }
+            _ => Err(ErrorCode::InvalidOperand)?,
+        }
+        Ok(())
+    }
+
+    fn map_labels(&mut self, f: impl Fn(u128) -> u128) -> Result<(), ErrorCode> {
+        match self.stack.last_mut().ok_or(ErrorCode::StackUnderflow)? {
+            Operand::Label(ref mut x) => *x = f(*x),
+            Operand::Labels(ref mut v) => {
+                for x in v.data.iter_mut() {
+                    *x = f(*x);
+                }
+            }
+            _ => {
+                Err(ErrorCode::InvalidOperand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn lside(&mut self) -> Result<(), ErrorCode> {
+        self.map_labels(get_side)
+    }
+
+    fn lasset(&mut self) -> Result<(), ErrorCode> {
+        self.map_labels(get_asset_id)
+    }
+
+    fn lmake(&mut self, side: u128) -> Result<(), ErrorCode> {
+        self.map_labels(|x| make_asset(x, side))
+    }
+
+    fn lfside(&mut self, pos: usize, side: u128) -> Result<(), ErrorCode> {
+        let stack_index = self.get_stack_index(pos)?;
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        let labels = match v1 {
+            Operand::Labels(labels) => labels,
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+        if pos != 0 {
+            match rest.get_mut(stack_index).ok_or(ErrorCode::OutOfRange)? {
+                Operand::Vector(v2) => {
+                    if v2.data.len() != labels.data.len() {
+                        Err(ErrorCode::NotAligned)?;
+                    }
+                    v2.data = v2
+                        .data
+                        .iter()
+                        .zip(&labels.data)
+                        .filter(|(_, &x)| get_side(x) == side)
+                        .map(|(v, _)| *v)
+                        .collect();
+                }
+                _ => Err(ErrorCode::InvalidOperand)?,
+            }
+        }
+        labels.data.retain(|&x| get_side(x) == side);
+        Ok(())
+    }
+
     fn jupd(
         &mut self,
         pos_vector_b: usize,
@@ -629,11 +1144,9 @@ impl Stack {
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
 
-        let v2 = rest
-            .get(stack_index_v2)
-            .ok_or_else(|| ErrorCode::OutOfRange)?;
+        let v2 = rest.get(stack_index_v2).ok_or(ErrorCode::OutOfRange)?;
         let labels_a = &rest[stack_index_labels_a];
         let labels_b = &rest[stack_index_labels_b];
 
@@ -673,6 +1186,10 @@ impl Stack {
                     }
                     // NOTE: if we didn't match any label in B, then preserve value in A, as A + None = A
                 }
+                if j < labels_b.data.len() {
+                    // Labels B past the last label of A are not in A either
+                    Err(ErrorCode::MathUnderflow)?
+                }
             }
             _ => Err(ErrorCode::InvalidOperand)?,
         }
@@ -700,11 +1217,9 @@ impl Stack {
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
 
-        let v2 = rest
-            .get(stack_index_v2)
-            .ok_or_else(|| ErrorCode::OutOfRange)?;
+        let v2 = rest.get(stack_index_v2).ok_or(ErrorCode::OutOfRange)?;
         let labels_a = &rest[stack_index_labels_a];
         let labels_b = &rest[stack_index_labels_b];
 
@@ -738,15 +1253,17 @@ impl Stack {
                         } else {
                             // Found matching label in B. Sum values in-place: A <- A + B
                             let x1 = &mut v1.data[i];
-                            *x1 = x1
-                                .checked_add(v2.data[j])
-                                .ok_or_else(|| ErrorCode::MathOverflow)?;
+                            *x1 = x1.checked_add(v2.data[j]).ok_or(ErrorCode::MathOverflow)?;
                             j += 1;
                             break;
                         }
                     }
                     // NOTE: if we didn't match any label in B, then preserve value in A, as A + None = A
                 }
+                if j < labels_b.data.len() {
+                    // Labels B past the last label of A are not in A either
+                    Err(ErrorCode::MathUnderflow)?
+                }
             }
             _ => Err(ErrorCode::InvalidOperand)?,
         }
@@ -754,6 +1271,56 @@ impl Stack {
         Ok(())
     }
 
+    fn junion(
+        &mut self,
+        pos_vector_b: usize,
+        pos_labels_a: usize,
+        pos_labels_b: usize,
+    ) -> Result<(), ErrorCode> {
+        if pos_vector_b < 1 || pos_labels_a < 2 || pos_labels_b < 2 {
+            // [TOS - 1, TOS] are reserved for values
+            Err(ErrorCode::InvalidOperand)?;
+        }
+        let stack_index_v2 = self.get_stack_index(pos_vector_b)?;
+        let stack_index_labels_a = self.get_stack_index(pos_labels_a)?;
+        let stack_index_labels_b = self.get_stack_index(pos_labels_b)?;
+
+        let labels_a = &self.stack[stack_index_labels_a];
+        let labels_b = &self.stack[stack_index_labels_b];
+        let (union, map_a, map_b) = match (labels_a, labels_b) {
+            (Operand::Labels(labels_a), Operand::Labels(labels_b)) => labels_a.union(labels_b),
+            _ => Err(ErrorCode::InvalidOperand)?,
+        };
+
+        // Expand vector into union space filling missing labels with zeros
+        let expand = |operand: &mut Operand, map: &[usize]| -> Result<(), ErrorCode> {
+            match operand {
+                Operand::Vector(v) => {
+                    if v.data.len() != map.len() {
+                        Err(ErrorCode::NotAligned)?;
+                    }
+                    let mut result = vec![Amount::ZERO; union.data.len()];
+                    for (x, &pos) in v.data.iter().zip(map) {
+                        result[pos] = *x;
+                    }
+                    v.data = result;
+                    Ok(())
+                }
+                _ => Err(ErrorCode::InvalidOperand),
+            }
+        };
+
+        let (v1, rest) = self
+            .stack
+            .split_last_mut()
+            .ok_or(ErrorCode::StackUnderflow)?;
+        expand(v1, &map_a)?;
+        expand(&mut rest[stack_index_v2], &map_b)?;
+
+        self.push(Operand::Labels(union));
+        Ok(())
+    }
+
     fn jflt(&mut self, pos_labels_a: usize, pos_labels_b: usize) -> Result<(), ErrorCode> {
         if pos_labels_a == pos_labels_b {
             // If both vectors use same labels, then no work needed
@@ -768,7 +1335,7 @@ impl Stack {
         let (v1, rest) = self
             .stack
             .split_last_mut()
-            .ok_or_else(|| ErrorCode::StackUnderflow)?;
+            .ok_or(ErrorCode::StackUnderflow)?;
 
         let labels_a = &rest[stack_index_labels_a];
         let labels_b = &rest[stack_index_labels_b];
@@ -800,6 +1367,10 @@ impl Stack {
                         }
                     }
                 }
+                if j < labels_b.data.len() {
+                    // Labels B past the last label of A are not in A either
+                    Err(ErrorCode::NotFound)?;
+                }
                 // Drop any further A as their labels didn't match any labels in B
                 drop(v1.data.drain(k..))
             }
@@ -823,6 +1394,7 @@ pub(crate) fn log_stack_fun(stack: &Stack) {
                 Operand::Vector(vector) => format!("Vector: {:0.5}", *vector),
                 Operand::Scalar(amount) => format!("Scalar: {:0.5}", *amount),
                 Operand::Label(label) => format!("Label: {}", label),
+                Operand::Matrix(matrix) => format!("Matrix: {:0.5}", *matrix),
             }
         );
     }
@@ -838,6 +1410,7 @@ pub(crate) fn log_stack_fun(stack: &Stack) {
                 Operand::Vector(vector) => format!("Vector: {:0.5}", *vector),
                 Operand::Scalar(amount) => format!("Scalar: {:0.5}", *amount),
                 Operand::Label(label) => format!("Label: {}", label),
+                Operand::Matrix(matrix) => format!("Matrix: {:0.5}", *matrix),
             }
         );
     }
@@ -864,15 +1437,45 @@ where
     VIO: VectorIO,
 {
     pub fn new(vio: &'vio mut VIO) -> Self {
-        Self { vio }
+        Self {
+            vio,
+            args: Vec::new(),
+        }
+    }
+
+    /// Create program with arguments
+    ///
+    /// Arguments are used to resolve parametric storage ids (see `vis::arg()`),
+    /// and are also bound to registers (R0-Rn) as Scalar values.
+    pub fn with_args(vio: &'vio mut VIO, args: Vec<u128>) -> Self {
+        Self { vio, args }
     }
 
     pub fn execute(&mut self, code_bytes: Vec<u8>, num_registers: usize) -> Result<(), ErrorCode> {
         let code = Labels::from_vec(code_bytes).data;
-        let mut stack = Stack::new(num_registers);
+        let mut stack = Stack::new(num_registers.max(self.args.len()));
+        for (reg, &value) in stack.registry.iter_mut().zip(&self.args) {
+            *reg = Operand::Scalar(Amount::from_u128_raw(value));
+        }
         self.execute_with_stack(code, &mut stack)
     }
 
+    fn resolve_id(&self, id: u128) -> Result<u128, ErrorCode> {
+        if id & ARG_FLAG == 0 {
+            return Ok(id);
+        }
+        let index = (id & !ARG_FLAG) as usize;
+        self.args.get(index).copied().ok_or(ErrorCode::NotFound)
+    }
+
+    /// Pop storage id for indirect load or store from Label operand on TOS
+    fn pop_id(&self, stack: &mut Stack) -> Result<u128, ErrorCode> {
+        match stack.pop()? {
+            Operand::Label(id) => self.resolve_id(id),
+            _ => Err(ErrorCode::InvalidOperand),
+        }
+    }
+
     pub(crate) fn execute_with_stack(
         &mut self,
         code: Vec<u128>,
@@ -888,19 +1491,19 @@ where
             pc += 1;
             match op_code {
                 OP_LDL => {
-                    let id = code[pc];
+                    let id = self.resolve_id(code[pc])?;
                     pc += 1;
                     let v = self.vio.load_labels(id)?;
                     stack.push(Operand::Labels(v));
                 }
                 OP_LDV => {
-                    let id = code[pc];
+                    let id = self.resolve_id(code[pc])?;
                     pc += 1;
                     let v = self.vio.load_vector(id)?;
                     stack.push(Operand::Vector(v));
                 }
                 OP_STL => {
-                    let id = code[pc];
+                    let id = self.resolve_id(code[pc])?;
                     pc += 1;
                     match stack.pop()? {
                         Operand::Labels(v) => {
@@ -912,7 +1515,7 @@ where
                     }
                 }
                 OP_STV => {
-                    let id = code[pc];
+                    let id = self.resolve_id(code[pc])?;
                     pc += 1;
                     match stack.pop()? {
                         Operand::Vector(v) => {
@@ -923,6 +1526,38 @@ where
                         }
                     }
                 }
+                OP_LDLI => {
+                    let id = self.pop_id(stack)?;
+                    let v = self.vio.load_labels(id)?;
+                    stack.push(Operand::Labels(v));
+                }
+                OP_LDVI => {
+                    let id = self.pop_id(stack)?;
+                    let v = self.vio.load_vector(id)?;
+                    stack.push(Operand::Vector(v));
+                }
+                OP_STLI => {
+                    let id = self.pop_id(stack)?;
+                    match stack.pop()? {
+                        Operand::Labels(v) => {
+                            self.vio.store_labels(id, v)?;
+                        }
+                        _ => {
+                            Err(ErrorCode::InvalidOperand)?;
+                        }
+                    }
+                }
+                OP_STVI => {
+                    let id = self.pop_id(stack)?;
+                    match stack.pop()? {
+                        Operand::Vector(v) => {
+                            self.vio.store_vector(id, v)?;
+                        }
+                        _ => {
+                            Err(ErrorCode::InvalidOperand)?;
+                        }
+                    }
+                }
                 OP_LDD => {
                     let pos = code[pc] as usize;
                     pc += 1;
@@ -989,6 +1624,20 @@ where
                 OP_SQRT => {
                     stack.sqrt()?;
                 }
+                #[cfg(feature = "amount-exp")]
+                OP_EXP => {
+                    stack.exp()?;
+                }
+                #[cfg(feature = "amount-exp")]
+                OP_LN => {
+                    stack.ln()?;
+                }
+                #[cfg(feature = "amount-exp")]
+                OP_POW => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.pow(pos)?;
+                }
                 OP_VSUM => {
                     stack.vsum()?;
                 }
@@ -1002,6 +1651,28 @@ where
                     pc += 1;
                     stack.max(pos)?;
                 }
+                OP_GT => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.gt(pos)?;
+                }
+                OP_LT => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.lt(pos)?;
+                }
+                OP_EQ => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.eq(pos)?;
+                }
+                OP_SELECT => {
+                    let pos_a = code[pc] as usize;
+                    pc += 1;
+                    let pos_b = code[pc] as usize;
+                    pc += 1;
+                    stack.select(pos_a, pos_b)?;
+                }
                 OP_LUNION => {
                     let pos = code[pc] as usize;
                     pc += 1;
@@ -1033,6 +1704,12 @@ where
                 OP_VMAX => {
                     stack.vmax()?;
                 }
+                OP_CUMSUM => {
+                    stack.cumsum()?;
+                }
+                OP_VPROD => {
+                    stack.vprod()?;
+                }
                 OP_VPUSH => {
                     let val = code[pc];
                     pc += 1;
@@ -1077,6 +1754,15 @@ where
                     pc += 1;
                     stack.jadd(pos_1, pos_2, pos_3)?;
                 }
+                OP_JUNION => {
+                    let pos_1 = code[pc] as usize;
+                    pc += 1;
+                    let pos_2 = code[pc] as usize;
+                    pc += 1;
+                    let pos_3 = code[pc] as usize;
+                    pc += 1;
+                    stack.junion(pos_1, pos_2, pos_3)?;
+                }
                 OP_JFLT => {
                     let pos_1 = code[pc] as usize;
                     pc += 1;
@@ -1086,7 +1772,7 @@ where
                 }
                 OP_B => {
                     // B <program_id> <num_inputs> <num_outputs> <num_registers>
-                    let code_address = code[pc];
+                    let code_address = self.resolve_id(code[pc])?;
                     pc += 1;
                     let num_inputs = code[pc] as usize;
                     pc += 1;
@@ -1095,31 +1781,32 @@ where
                     let num_regs = code[pc] as usize;
                     pc += 1;
                     let mut st = Stack::new(num_regs);
-                    let mut prg = Program::new(self.vio);
+                    let mut prg = Program::with_args(self.vio, self.args.clone());
                     let cod = prg.vio.load_labels(code_address)?;
+                    if let Some(arity) = prg.vio.load_arity(code_address)? {
+                        arity.check(num_inputs, num_outputs, num_regs)?;
+                    }
                     let frm = stack
                         .stack
                         .len()
                         .checked_sub(num_inputs)
-                        .ok_or_else(|| ErrorCode::StackUnderflow)?;
+                        .ok_or(ErrorCode::StackUnderflow)?;
                     st.stack.extend(stack.stack.drain(frm..));
-                    let res = prg.execute_with_stack(cod.data, &mut st);
-                    if let Err(err) = res {
+                    prg.execute_with_stack(cod.data, &mut st).inspect_err(|_| {
                         log_msg!("\n\nError occurred in procedure:");
                         log_stack!(&st);
                         log_msg!("^^^ Stack of the procedure\n\n");
-                        return Err(err);
-                    }
+                    })?;
                     let frm = st
                         .stack
                         .len()
                         .checked_sub(num_outputs)
-                        .ok_or_else(|| ErrorCode::StackUnderflow)?;
+                        .ok_or(ErrorCode::StackUnderflow)?;
                     stack.stack.extend(st.stack.drain(frm..));
                 }
                 OP_FOLD => {
                     // FOLD <program_id> <num_inputs> <num_outputs> <num_registers>
-                    let code_address = code[pc];
+                    let code_address = self.resolve_id(code[pc])?;
                     pc += 1;
                     let num_inputs = code[pc] as usize;
                     pc += 1;
@@ -1128,14 +1815,17 @@ where
                     let num_regs = code[pc] as usize;
                     pc += 1;
                     let mut st = Stack::new(num_regs);
-                    let mut prg = Program::new(self.vio);
+                    let mut prg = Program::with_args(self.vio, self.args.clone());
                     let cod = prg.vio.load_labels(code_address)?;
-                    let source = stack.stack.pop().ok_or_else(|| ErrorCode::StackUnderflow)?;
+                    if let Some(arity) = prg.vio.load_arity(code_address)? {
+                        arity.check(num_inputs, num_outputs, num_regs)?;
+                    }
+                    let source = stack.stack.pop().ok_or(ErrorCode::StackUnderflow)?;
                     let frm = stack
                         .stack
                         .len()
                         .checked_sub(num_inputs)
-                        .ok_or_else(|| ErrorCode::StackUnderflow)?;
+                        .ok_or(ErrorCode::StackUnderflow)?;
                     st.stack.extend(stack.stack.drain(frm..));
                     match source {
                         Operand::Labels(s) => {
@@ -1156,9 +1846,120 @@ where
                         .stack
                         .len()
                         .checked_sub(num_outputs)
-                        .ok_or_else(|| ErrorCode::StackUnderflow)?;
+                        .ok_or(ErrorCode::StackUnderflow)?;
                     stack.stack.extend(st.stack.drain(frm..));
                 }
+                OP_PKM => {
+                    let count = code[pc] as usize;
+                    pc += 1;
+                    stack.pkm(count)?;
+                }
+                OP_UNPKM => {
+                    stack.unpkm()?;
+                }
+                OP_MATVEC => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.matvec(pos)?;
+                }
+                OP_DOT => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.dot(pos)?;
+                }
+                OP_MROW => {
+                    let row = code[pc] as usize;
+                    pc += 1;
+                    stack.mrow(row)?;
+                }
+                OP_MCOL => {
+                    let col = code[pc] as usize;
+                    pc += 1;
+                    stack.mcol(col)?;
+                }
+                OP_LDMX => {
+                    let id = self.resolve_id(code[pc])?;
+                    pc += 1;
+                    let m = self.vio.load_matrix(id)?;
+                    stack.push(Operand::Matrix(m));
+                }
+                OP_STMX => {
+                    let id = self.resolve_id(code[pc])?;
+                    pc += 1;
+                    match stack.pop()? {
+                        Operand::Matrix(m) => {
+                            self.vio.store_matrix(id, m)?;
+                        }
+                        _ => {
+                            Err(ErrorCode::InvalidOperand)?;
+                        }
+                    }
+                }
+                OP_SLICE => {
+                    let from = code[pc] as usize;
+                    pc += 1;
+                    let len = code[pc] as usize;
+                    pc += 1;
+                    stack.slice(from, len)?;
+                }
+                OP_GATHER => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.gather(pos)?;
+                }
+                OP_SCATTER => {
+                    let pos_values = code[pc] as usize;
+                    pc += 1;
+                    let pos_indices = code[pc] as usize;
+                    pc += 1;
+                    stack.scatter(pos_values, pos_indices)?;
+                }
+                OP_CONCAT => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.concat(pos)?;
+                }
+                OP_LINTER => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.linter(pos)?;
+                }
+                OP_LDIFF => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.ldiff(pos)?;
+                }
+                OP_LFIND => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.lfind(pos)?;
+                }
+                OP_LSORT => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    stack.lsort(pos)?;
+                }
+                OP_LCHECK => {
+                    stack.lcheck()?;
+                }
+                OP_LSIDE => {
+                    stack.lside()?;
+                }
+                OP_LASSET => {
+                    stack.lasset()?;
+                }
+                OP_LMAKE => {
+                    let side = code[pc];
+                    pc += 1;
+                    stack.lmake(side)?;
+                }
+                OP_LFSIDE => {
+                    let pos = code[pc] as usize;
+                    pc += 1;
+                    let side = code[pc];
+                    pc += 1;
+                    stack.lfside(pos, side)?;
+                }
                 _ => {
                     Err(ErrorCode::InvalidInstruction)?;
                 }
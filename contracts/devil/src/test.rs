@@ -1,9 +1,17 @@
 use std::collections::HashMap;
 
-use deli::{labels::Labels, log_msg, vector::Vector};
+use deli::{labels::Labels, log_msg, matrix::Matrix, testing::Rng, vector::Vector};
 use devil_macros::devil;
+use icore::vil::accrue_management_fee::accrue_management_fee;
+use icore::vil::execute_buy_batch::{batch_fill_order, batch_sum_collateral, execute_buy_batch};
 use icore::vil::execute_buy_order::execute_buy_order;
+use icore::vil::execute_sell_order::execute_sell_order;
+use icore::vil::match_market::match_market;
+use icore::vil::queue::{dequeue_order, enqueue_order};
 use icore::vil::solve_quadratic::solve_quadratic;
+use icore::vil::submit_order::{create_order, update_order};
+use icore::vil::update_quote::update_quote;
+use icore::vil::update_supply::update_supply;
 use labels_macros::label_vec;
 use vector_macros::amount_vec;
 
@@ -13,6 +21,8 @@ use crate::program::*; // Use glob import for tidiness
 struct TestVectorIO {
     labels: HashMap<u128, Labels>,
     vectors: HashMap<u128, Vector>,
+    matrices: HashMap<u128, Matrix>,
+    arities: HashMap<u128, (usize, usize, usize)>,
 }
 
 impl TestVectorIO {
@@ -20,20 +30,33 @@ impl TestVectorIO {
         Self {
             labels: HashMap::new(),
             vectors: HashMap::new(),
+            matrices: HashMap::new(),
+            arities: HashMap::new(),
         }
     }
+
+    fn register_program(
+        &mut self,
+        id: u128,
+        num_inputs: usize,
+        num_outputs: usize,
+        num_registers: usize,
+    ) {
+        self.arities
+            .insert(id, (num_inputs, num_outputs, num_registers));
+    }
 }
 
 impl VectorIO for TestVectorIO {
     fn load_labels(&self, id: u128) -> Result<Labels, ErrorCode> {
-        let v = self.labels.get(&id).ok_or_else(|| ErrorCode::NotFound)?;
+        let v = self.labels.get(&id).ok_or(ErrorCode::NotFound)?;
         Ok(Labels {
             data: v.data.clone(),
         })
     }
 
     fn load_vector(&self, id: u128) -> Result<Vector, ErrorCode> {
-        let v = self.vectors.get(&id).ok_or_else(|| ErrorCode::NotFound)?;
+        let v = self.vectors.get(&id).ok_or(ErrorCode::NotFound)?;
         Ok(Vector {
             data: v.data.clone(),
         })
@@ -48,6 +71,30 @@ impl VectorIO for TestVectorIO {
         self.vectors.insert(id, input);
         Ok(())
     }
+
+    fn load_matrix(&self, id: u128) -> Result<Matrix, ErrorCode> {
+        let v = self.matrices.get(&id).ok_or(ErrorCode::NotFound)?;
+        Ok(Matrix {
+            num_cols: v.num_cols,
+            data: v.data.clone(),
+        })
+    }
+
+    fn store_matrix(&mut self, id: u128, input: Matrix) -> Result<(), ErrorCode> {
+        self.matrices.insert(id, input);
+        Ok(())
+    }
+
+    fn load_arity(&self, id: u128) -> Result<Option<Arity>, ErrorCode> {
+        Ok(self
+            .arities
+            .get(&id)
+            .map(|&(num_inputs, num_outputs, num_registers)| Arity {
+                num_inputs,
+                num_outputs,
+                num_registers,
+            }))
+    }
 }
 
 /// All round test verifies that majority of VIL functionality works as expected.
@@ -82,6 +129,8 @@ fn test_compute_1() {
     let asset_names_id = 1001;
     let weights_id = 1002;
     let quote_id = 1003;
+    let fees_id = 1004;
+    let supply_id = 1005;
     let market_asset_names_id = 101;
     let supply_long_id = 102;
     let supply_short_id = 103;
@@ -89,7 +138,9 @@ fn test_compute_1() {
     let demand_short_id = 105;
     let delta_long_id = 106;
     let delta_short_id = 107;
+    let fee_accumulator_id = 201;
     let solve_quadratic_id = 10;
+    let match_market_id = 13;
 
     vio.store_labels(asset_names_id, label_vec![51, 53, 54])
         .unwrap();
@@ -103,6 +154,13 @@ fn test_compute_1() {
     vio.store_vector(index_order_id, amount_vec![1000.00, 0, 0])
         .unwrap();
 
+    vio.store_vector(fees_id, amount_vec![0, 0, 0]).unwrap();
+
+    vio.store_vector(supply_id, amount_vec![0]).unwrap();
+
+    vio.store_vector(fee_accumulator_id, amount_vec![0, 0, 0])
+        .unwrap();
+
     vio.store_labels(market_asset_names_id, label_vec![51, 52, 53, 54, 55])
         .unwrap();
 
@@ -131,6 +189,16 @@ fn test_compute_1() {
         },
     )
     .unwrap();
+    vio.register_program(solve_quadratic_id, 3, 1, 4);
+
+    vio.store_labels(
+        match_market_id,
+        Labels {
+            data: match_market(),
+        },
+    )
+    .unwrap();
+    vio.register_program(match_market_id, 7, 4, 7);
 
     let code = execute_buy_order(
         index_order_id,
@@ -139,6 +207,8 @@ fn test_compute_1() {
         asset_names_id,
         weights_id,
         quote_id,
+        fees_id,
+        supply_id,
         market_asset_names_id,
         supply_long_id,
         supply_short_id,
@@ -146,16 +216,18 @@ fn test_compute_1() {
         demand_short_id,
         delta_long_id,
         delta_short_id,
+        fee_accumulator_id,
         solve_quadratic_id,
+        match_market_id,
     );
 
     let order_before = vio.load_vector(index_order_id).unwrap();
 
-    let num_registers = 16;
+    let num_registers = 20;
 
     let mut program = Program::new(&mut vio);
     let mut stack = Stack::new(num_registers);
-    let result = program.execute_with_stack(code, &mut stack);
+    let result = program.execute_with_stack(code.clone(), &mut stack);
 
     if let Err(err) = result {
         log_stack!(&stack);
@@ -211,68 +283,2461 @@ fn test_compute_1() {
         amount_vec![0.05999001995, 0.05, 0.0399001995, 9.95001995, 0.15].data
     );
     assert_eq!(delta_long.data, amount_vec![0, 0, 0, 0, 0].data);
+
+    // no fees, and supply grows by minted quantity
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![0.0999001995].data
+    );
+    assert_eq!(
+        vio.load_vector(fee_accumulator_id).unwrap().data,
+        amount_vec![0, 0, 0].data
+    );
+
+    // Order topped up with 1000 is executed again, and it keeps collateral
+    // spent and quantity minted so far
+    vio.store_vector(
+        index_order_id,
+        amount_vec![1000, 999.999999986013980025, 0.0999001995],
+    )
+    .unwrap();
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    program.execute_with_stack(code, &mut stack).unwrap();
+    assert_eq!(
+        vio.load_vector(index_order_id).unwrap().data,
+        amount_vec![0.000000013986019975, 1999.99999997202796005, 0.199800399].data
+    );
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![0.199800399].data
+    );
 }
 
 #[test]
-fn test_transpose() {
+fn test_execute_buy_batch() {
     let mut vio = TestVectorIO::new();
-    let num_registers = 8;
-
-    // --- 1. Setup VIO Inputs ---
-    let vector1_id = 100;
-    let vector2_id = 101;
-    let expected1_id = 102; // T1: [1, 4]
-    let expected2_id = 103; // T2: [2, 5]
-    let expected3_id = 104; // T3: [3, 6]
-    let delta_id = 105;
-
-    vio.store_vector(vector1_id, amount_vec![1, 2, 3]).unwrap();
-    vio.store_vector(vector2_id, amount_vec![4, 5, 6]).unwrap();
-    vio.store_vector(expected1_id, amount_vec![1, 4]).unwrap();
-    vio.store_vector(expected2_id, amount_vec![2, 5]).unwrap();
-    vio.store_vector(expected3_id, amount_vec![3, 6]).unwrap();
+    let queue_id = 10000;
+    let executed_asset_quantities_id = 10002;
+    let executed_index_quantities_id = 10003;
+    let executed_order_quantities_id = 10004;
+    let asset_names_id = 1001;
+    let weights_id = 1002;
+    let quote_id = 1003;
+    let fees_id = 1004;
+    let supply_id = 1005;
+    let market_asset_names_id = 101;
+    let supply_long_id = 102;
+    let supply_short_id = 103;
+    let demand_long_id = 104;
+    let demand_short_id = 105;
+    let delta_long_id = 106;
+    let delta_short_id = 107;
+    let fee_accumulator_id = 201;
+    let solve_quadratic_id = 10;
+    let batch_sum_collateral_id = 11;
+    let batch_fill_order_id = 12;
+    let match_market_id = 13;
 
-    // --- 2. VIL Code Execution ---
-    let code = devil![
-        // 1. Setup Transposition
-        LDV         vector1_id              // Stack: [V1]
-        LDV         vector2_id              // Stack: [V1, V2]
-        T           2                       // Stack: [T1, T2, T3] (3 vectors)
+    // Three orders with total collateral of 1000
+    vio.store_labels(queue_id, label_vec![20001, 20002, 20003])
+        .unwrap();
+    vio.store_vector(20001, amount_vec![300, 0, 0]).unwrap();
+    vio.store_vector(20002, amount_vec![500, 10, 0.1]).unwrap();
+    vio.store_vector(20003, amount_vec![200, 0, 0]).unwrap();
 
-        // 2. Load Expected Vectors for comparison
-        LDV         expected1_id            // [T1, T2, T3, E1]
-        LDV         expected2_id            // [T1, T2, T3, E1, E2]
-        LDV         expected3_id            // [T1, T2, T3, E1, E2, E3] (6 vectors)
+    vio.store_labels(asset_names_id, label_vec![51, 53])
+        .unwrap();
+    vio.store_vector(weights_id, amount_vec![0.5, 2]).unwrap();
 
-        // 3. D3 = T3 - E3
-        SUB         3                       // Stack: [T1, T2, T3, E1, E2, D3]
+    // Capacity of 2 ITP is less than total demand
+    vio.store_vector(quote_id, amount_vec![2, 100, 10]).unwrap();
+    vio.store_vector(fees_id, amount_vec![0.01, 0, 0]).unwrap();
+    vio.store_vector(supply_id, amount_vec![5]).unwrap();
+    vio.store_vector(fee_accumulator_id, amount_vec![0, 0, 0])
+        .unwrap();
 
-        // 4. D2 = T2 - E2
-        SWAP        1                       // Stack: [T1, T2, T3, E1, D3, E2]
-        SUB         4                       // Stack: [T1, T2, T3, E1, D3, D2]
+    vio.store_labels(market_asset_names_id, label_vec![51, 52, 53])
+        .unwrap();
+    vio.store_vector(supply_long_id, amount_vec![0.5, 0.5, 0.5])
+        .unwrap();
+    vio.store_vector(supply_short_id, amount_vec![0, 0, 0])
+        .unwrap();
+    vio.store_vector(demand_long_id, amount_vec![0, 0, 0])
+        .unwrap();
+    vio.store_vector(demand_short_id, amount_vec![0, 0, 1])
+        .unwrap();
+    vio.store_vector(delta_long_id, amount_vec![0, 0, 0])
+        .unwrap();
+    vio.store_vector(delta_short_id, amount_vec![0, 0, 0])
+        .unwrap();
 
-        // 5. D1 = T1 - E1
-        SWAP        2                       // Stack: [T1, T2, T3, D2, D3, E1]
-        SUB         5                       // Stack: [T1, T2, T3, D2, D3, D1]
+    for (id, code, arity) in [
+        (solve_quadratic_id, solve_quadratic(), (3, 1, 4)),
+        (batch_sum_collateral_id, batch_sum_collateral(), (1, 1, 0)),
+        (batch_fill_order_id, batch_fill_order(), (4, 4, 4)),
+        (match_market_id, match_market(), (7, 4, 7)),
+    ] {
+        vio.store_labels(id, Labels { data: code }).unwrap();
+        vio.register_program(id, arity.0, arity.1, arity.2);
+    }
 
-        // 6. Compute total delta - should be zero
-        ADD         1                       // Stack: [T1, T2, T3, D2, D3, D1 + D3]
-        ADD         2                       // Stack: [T1, T2, T3, D2, D3, D1 + D3 + D2]
+    let code = execute_buy_batch(
+        queue_id,
+        executed_index_quantities_id,
+        executed_asset_quantities_id,
+        executed_order_quantities_id,
+        asset_names_id,
+        weights_id,
+        quote_id,
+        fees_id,
+        supply_id,
+        market_asset_names_id,
+        supply_long_id,
+        supply_short_id,
+        demand_long_id,
+        demand_short_id,
+        delta_long_id,
+        delta_short_id,
+        fee_accumulator_id,
+        solve_quadratic_id,
+        match_market_id,
+        batch_sum_collateral_id,
+        batch_fill_order_id,
+    );
 
-        // 7. Store the final zero vector
-        STV         delta_id
-    ];
+    let num_registers = 16;
 
-    let mut stack = Stack::new(num_registers);
     let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    let result = program.execute_with_stack(code.clone(), &mut stack);
 
-    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+    if let Err(err) = result {
         log_stack!(&stack);
         panic!("Failed to execute test: {:?}", err);
     }
 
-    // --- 3. Assertion ---
-    let delta = vio.load_vector(delta_id).unwrap();
+    // Batch buys 2 ITP for (100 + 10 * 2) * 2 = 240 plus 1% entry fee, i.e.
+    // each order spends 24% of its collateral and mints 0.002 ITP per unit
+    assert_eq!(
+        vio.load_vector(20001).unwrap().data,
+        amount_vec![227.28, 72, 0.6].data
+    );
+    assert_eq!(
+        vio.load_vector(20002).unwrap().data,
+        amount_vec![378.8, 130, 1.1].data
+    );
+    assert_eq!(
+        vio.load_vector(20003).unwrap().data,
+        amount_vec![151.52, 48, 0.4].data
+    );
 
-    assert_eq!(delta.data, amount_vec![0, 0].data);
+    // Filled quantities are recorded in queue order
+    assert_eq!(
+        vio.load_vector(executed_order_quantities_id).unwrap().data,
+        amount_vec![0.6, 1, 0.4].data
+    );
+
+    let index_quantites = vio.load_vector(executed_index_quantities_id).unwrap();
+    assert_eq!(index_quantites.data[0], amount_vec![2].data[0]);
+    assert_eq!(
+        vio.load_vector(executed_asset_quantities_id).unwrap().data,
+        amount_vec![1, 4].data
+    );
+
+    // Market is updated once for the whole batch
+    assert_eq!(
+        vio.load_vector(demand_long_id).unwrap().data,
+        amount_vec![1, 0, 3].data
+    );
+    assert_eq!(
+        vio.load_vector(demand_short_id).unwrap().data,
+        amount_vec![0, 0, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(delta_long_id).unwrap().data,
+        amount_vec![0, 0.5, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(delta_short_id).unwrap().data,
+        amount_vec![0.5, 0, 2.5].data
+    );
+
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![7].data
+    );
+    assert_eq!(
+        vio.load_vector(fee_accumulator_id).unwrap().data,
+        amount_vec![2.4, 0, 0].data
+    );
+
+    // Orders with zero collateral are skipped, and batch of only such orders
+    // executes nothing
+    vio.store_labels(queue_id, label_vec![20004, 20005])
+        .unwrap();
+    vio.store_vector(20004, amount_vec![0, 100, 1]).unwrap();
+    vio.store_vector(20005, amount_vec![0, 0, 0]).unwrap();
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    program.execute_with_stack(code, &mut stack).unwrap();
+
+    assert_eq!(
+        vio.load_vector(20004).unwrap().data,
+        amount_vec![0, 100, 1].data
+    );
+    assert_eq!(
+        vio.load_vector(executed_index_quantities_id).unwrap().data,
+        amount_vec![0, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(executed_order_quantities_id).unwrap().data,
+        amount_vec![0, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![7].data
+    );
+    assert_eq!(
+        vio.load_vector(demand_long_id).unwrap().data,
+        amount_vec![1, 0, 3].data
+    );
+}
+
+#[test]
+fn test_order_queue() {
+    let mut vio = TestVectorIO::new();
+    let queue_id = 10000;
+
+    vio.store_labels(queue_id, label_vec![20001, 20003])
+        .unwrap();
+
+    let run = |vio: &mut TestVectorIO, code: Vec<u128>| {
+        let mut program = Program::new(vio);
+        let mut stack = Stack::new(0);
+        if let Err(err) = program.execute_with_stack(code, &mut stack) {
+            log_stack!(&stack);
+            panic!("Failed to execute test: {:?}", err);
+        }
+    };
+
+    // Queue stays sorted, and order is queued only once
+    run(&mut vio, enqueue_order(queue_id, 20002));
+    run(&mut vio, enqueue_order(queue_id, 20002));
+    assert_eq!(
+        vio.load_labels(queue_id).unwrap().data,
+        label_vec![20001, 20002, 20003].data
+    );
+
+    // Cancelled orders leave the queue
+    run(&mut vio, dequeue_order(queue_id, 20001));
+    run(&mut vio, dequeue_order(queue_id, 20004));
+    assert_eq!(
+        vio.load_labels(queue_id).unwrap().data,
+        label_vec![20002, 20003].data
+    );
+}
+
+#[test]
+fn test_execute_sell_order() {
+    let mut vio = TestVectorIO::new();
+    let index_order_id = 10001;
+    let executed_asset_quantities_id = 10002;
+    let executed_index_quantities_id = 10003;
+    let asset_names_id = 1001;
+    let weights_id = 1002;
+    let quote_id = 1003;
+    let fees_id = 1004;
+    let supply_id = 1005;
+    let market_asset_names_id = 101;
+    let supply_long_id = 102;
+    let supply_short_id = 103;
+    let demand_long_id = 104;
+    let demand_short_id = 105;
+    let delta_long_id = 106;
+    let delta_short_id = 107;
+    let fee_accumulator_id = 201;
+    let match_market_id = 13;
+
+    vio.store_labels(
+        match_market_id,
+        Labels {
+            data: match_market(),
+        },
+    )
+    .unwrap();
+    vio.register_program(match_market_id, 7, 4, 7);
+
+    vio.store_labels(asset_names_id, label_vec![51, 53, 54])
+        .unwrap();
+
+    vio.store_vector(weights_id, amount_vec![0.100, 1.000, 100.0])
+        .unwrap();
+
+    vio.store_vector(quote_id, amount_vec![10.00, 10_000, 100.0])
+        .unwrap();
+
+    // Sell 20 ITP, having previously redeemed 1 ITP for 500 collateral
+    vio.store_vector(index_order_id, amount_vec![20, 1, 500])
+        .unwrap();
+
+    // Exit fee of 1%
+    vio.store_vector(fees_id, amount_vec![0.005, 0.01, 0])
+        .unwrap();
+
+    vio.store_vector(supply_id, amount_vec![100]).unwrap();
+
+    vio.store_vector(fee_accumulator_id, amount_vec![5, 0, 0])
+        .unwrap();
+
+    vio.store_labels(market_asset_names_id, label_vec![51, 52, 53, 54, 55])
+        .unwrap();
+
+    vio.store_vector(demand_short_id, amount_vec![0, 0, 0.01, 0, 0])
+        .unwrap();
+
+    vio.store_vector(demand_long_id, amount_vec![0.1, 0.1, 0, 0.01, 0.2])
+        .unwrap();
+
+    vio.store_vector(supply_short_id, amount_vec![0, 0, 0, 0, 0])
+        .unwrap();
+
+    vio.store_vector(supply_long_id, amount_vec![0.05, 0.05, 0.05, 0.05, 0.05])
+        .unwrap();
+
+    vio.store_vector(delta_short_id, amount_vec![0, 0, 0, 0, 0])
+        .unwrap();
+
+    vio.store_vector(delta_long_id, amount_vec![0, 0, 0, 0, 0])
+        .unwrap();
+
+    let code = execute_sell_order(
+        index_order_id,
+        executed_index_quantities_id,
+        executed_asset_quantities_id,
+        asset_names_id,
+        weights_id,
+        quote_id,
+        fees_id,
+        supply_id,
+        market_asset_names_id,
+        supply_long_id,
+        supply_short_id,
+        demand_long_id,
+        demand_short_id,
+        delta_long_id,
+        delta_short_id,
+        fee_accumulator_id,
+        match_market_id,
+    );
+
+    let num_registers = 20;
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    let result = program.execute_with_stack(code.clone(), &mut stack);
+
+    if let Err(err) = result {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    let order_after = vio.load_vector(index_order_id).unwrap();
+    let index_quantites = vio.load_vector(executed_index_quantities_id).unwrap();
+    let asset_quantites = vio.load_vector(executed_asset_quantities_id).unwrap();
+    let demand_short = vio.load_vector(demand_short_id).unwrap();
+    let demand_long = vio.load_vector(demand_long_id).unwrap();
+    let delta_short = vio.load_vector(delta_short_id).unwrap();
+    let delta_long = vio.load_vector(delta_long_id).unwrap();
+
+    log_msg!("\n-= Program complete =-");
+    log_msg!("\n[out] Index Order = {:0.9}", order_after);
+    log_msg!("[out] Index Quantities = {:0.9}", index_quantites);
+    log_msg!("[out] Asset Quantities = {:0.9}", asset_quantites);
+    log_msg!("\n[out] Demand Short = {:0.9}", demand_short);
+    log_msg!("[out] Demand Long = {:0.9}", demand_long);
+    log_msg!("\n[out] Delta Short = {:0.9}", delta_short);
+    log_msg!("[out] Delta Long = {:0.9}", delta_long);
+
+    // Capped at capacity of 10 ITP, which return (10000 - 100 * 10) * 10 less 1% exit fee,
+    // while remaining 10 ITP are not kept in the order
+    assert_eq!(order_after.data, amount_vec![0, 11, 89_600].data);
+    assert_eq!(index_quantites.data, amount_vec![10, 10, 89_100].data);
+    assert_eq!(asset_quantites.data, amount_vec![1, 10, 1000].data);
+
+    // Demand long is consumed first, and then the rest goes to demand short
+    assert_eq!(demand_long.data, amount_vec![0, 0.1, 0, 0, 0.2].data);
+    assert_eq!(
+        demand_short.data,
+        amount_vec![0.9, 0, 10.01, 999.99, 0].data
+    );
+    assert_eq!(
+        delta_long.data,
+        amount_vec![0.95, 0, 10.06, 1000.04, 0].data
+    );
+    assert_eq!(delta_short.data, amount_vec![0, 0.05, 0, 0, 0.15].data);
+
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![90].data
+    );
+    assert_eq!(
+        vio.load_vector(fee_accumulator_id).unwrap().data,
+        amount_vec![5, 900, 0].data
+    );
+
+    // Steep slope caps at 10000 / (2 * 1000) = 5 ITP, beyond which collateral
+    // returned would fall, so that it returns (10000 - 1000 * 5) * 5 less 1%
+    vio.store_vector(quote_id, amount_vec![10.00, 10_000, 1000])
+        .unwrap();
+    vio.store_vector(index_order_id, amount_vec![10, 11, 89_600])
+        .unwrap();
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    program
+        .execute_with_stack(code.clone(), &mut stack)
+        .unwrap();
+    assert_eq!(
+        vio.load_vector(index_order_id).unwrap().data,
+        amount_vec![0, 16, 114_350].data
+    );
+    assert_eq!(
+        vio.load_vector(executed_index_quantities_id).unwrap().data,
+        amount_vec![5, 5, 24_750].data
+    );
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![85].data
+    );
+
+    // Zero slope doesn't cap, and index supply can't go below zero
+    vio.store_vector(quote_id, amount_vec![10.00, 10_000, 0])
+        .unwrap();
+    vio.store_vector(supply_id, amount_vec![1]).unwrap();
+    vio.store_vector(index_order_id, amount_vec![10, 16, 114_350])
+        .unwrap();
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::MathOverflow)));
+}
+
+#[test]
+fn test_fees() {
+    let mut vio = TestVectorIO::new();
+    let index_order_id = 10001;
+    let executed_asset_quantities_id = 10002;
+    let executed_index_quantities_id = 10003;
+    let asset_names_id = 1001;
+    let weights_id = 1002;
+    let quote_id = 1003;
+    let fees_id = 1004;
+    let supply_id = 1005;
+    let market_asset_names_id = 101;
+    let supply_long_id = 102;
+    let supply_short_id = 103;
+    let demand_long_id = 104;
+    let demand_short_id = 105;
+    let delta_long_id = 106;
+    let delta_short_id = 107;
+    let fee_accumulator_id = 201;
+    let solve_quadratic_id = 10;
+    let match_market_id = 13;
+
+    vio.store_labels(asset_names_id, label_vec![51]).unwrap();
+    vio.store_vector(weights_id, amount_vec![1]).unwrap();
+    vio.store_vector(quote_id, amount_vec![0.5, 1000, 100])
+        .unwrap();
+
+    // Entry fee of 1%, and management fee of 0.0001% per time unit
+    vio.store_vector(fees_id, amount_vec![0.01, 0.02, 0.000001])
+        .unwrap();
+    vio.store_vector(supply_id, amount_vec![0]).unwrap();
+    vio.store_vector(fee_accumulator_id, amount_vec![0, 0, 0])
+        .unwrap();
+    vio.store_vector(index_order_id, amount_vec![1010, 0, 0])
+        .unwrap();
+
+    vio.store_labels(market_asset_names_id, label_vec![51])
+        .unwrap();
+    for id in [
+        supply_long_id,
+        supply_short_id,
+        demand_long_id,
+        demand_short_id,
+        delta_long_id,
+        delta_short_id,
+    ] {
+        vio.store_vector(id, amount_vec![0]).unwrap();
+    }
+
+    vio.store_labels(
+        solve_quadratic_id,
+        Labels {
+            data: solve_quadratic(),
+        },
+    )
+    .unwrap();
+    vio.register_program(solve_quadratic_id, 3, 1, 4);
+
+    vio.store_labels(
+        match_market_id,
+        Labels {
+            data: match_market(),
+        },
+    )
+    .unwrap();
+    vio.register_program(match_market_id, 7, 4, 7);
+
+    let code = execute_buy_order(
+        index_order_id,
+        executed_index_quantities_id,
+        executed_asset_quantities_id,
+        asset_names_id,
+        weights_id,
+        quote_id,
+        fees_id,
+        supply_id,
+        market_asset_names_id,
+        supply_long_id,
+        supply_short_id,
+        demand_long_id,
+        demand_short_id,
+        delta_long_id,
+        delta_short_id,
+        fee_accumulator_id,
+        solve_quadratic_id,
+        match_market_id,
+    );
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(20);
+    program.execute_with_stack(code, &mut stack).unwrap();
+
+    // Capped at capacity of 0.5 ITP, which cost (1000 + 100 * 0.5) * 0.5 plus 1% entry fee
+    assert_eq!(
+        vio.load_vector(index_order_id).unwrap().data,
+        amount_vec![479.75, 525, 0.5].data
+    );
+    assert_eq!(
+        vio.load_vector(supply_id).unwrap().data,
+        amount_vec![0.5].data
+    );
+    assert_eq!(
+        vio.load_vector(fee_accumulator_id).unwrap().data,
+        amount_vec![5.25, 0, 0].data
+    );
+
+    // Management fee for 3600 time units on value of 0.5 ITP at price of 1000,
+    // where elapsed time is first argument bound to R0
+    let elapsed = deli::amount::Amount::from_u128_with_scale(3600, 0).to_u128_raw();
+    let code = accrue_management_fee(fees_id, quote_id, supply_id, fee_accumulator_id);
+    let num_registers = deli::vis::num_registers(&code);
+    assert_eq!(num_registers, 4);
+
+    let mut program = Program::with_args(&mut vio, vec![elapsed]);
+    program
+        .execute(Labels { data: code }.to_vec(), num_registers as usize)
+        .unwrap();
+
+    assert_eq!(
+        vio.load_vector(fee_accumulator_id).unwrap().data,
+        amount_vec![5.25, 0, 1.8].data
+    );
+}
+
+#[test]
+fn test_update_quote() {
+    let mut vio = TestVectorIO::new();
+    let asset_names_id = 1001;
+    let weights_id = 1002;
+    let quote_id = 1003;
+    let market_asset_names_id = 101;
+    let asset_prices_id = 102;
+    let asset_slopes_id = 103;
+    let asset_liquidity_id = 104;
+    let delta_long_id = 105;
+    let delta_short_id = 106;
+
+    vio.store_labels(asset_names_id, label_vec![51, 53, 54])
+        .unwrap();
+
+    vio.store_vector(weights_id, amount_vec![0.100, 1.000, 100.0])
+        .unwrap();
+
+    vio.store_labels(market_asset_names_id, label_vec![51, 52, 53, 54, 55])
+        .unwrap();
+
+    vio.store_vector(asset_prices_id, amount_vec![100, 200, 50, 10, 300])
+        .unwrap();
+
+    vio.store_vector(asset_slopes_id, amount_vec![0.1, 0.2, 0.5, 0.01, 1])
+        .unwrap();
+
+    vio.store_vector(asset_liquidity_id, amount_vec![10, 20, 30, 500, 40])
+        .unwrap();
+
+    vio.store_vector(delta_long_id, amount_vec![0, 0, 5, 0, 0])
+        .unwrap();
+
+    vio.store_vector(delta_short_id, amount_vec![0.5, 0, 0, 100, 0])
+        .unwrap();
+
+    let code = update_quote(
+        asset_names_id,
+        weights_id,
+        quote_id,
+        market_asset_names_id,
+        asset_prices_id,
+        asset_slopes_id,
+        asset_liquidity_id,
+        delta_long_id,
+        delta_short_id,
+    );
+
+    let num_registers = 16;
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(num_registers);
+    let result = program.execute_with_stack(code, &mut stack);
+
+    if let Err(err) = result {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    let quote = vio.load_vector(quote_id).unwrap();
+
+    log_msg!("\n-= Program complete =-");
+    log_msg!("[out] Index Quote = {:0.9}", quote);
+
+    // Capacity = MIN(9.5 / 0.1, 35 / 1, 400 / 100)
+    // Price = 0.1 * 100 + 1 * 50 + 100 * 10
+    // Slope = 0.01 * 0.1 + 1 * 0.5 + 10000 * 0.01
+    assert_eq!(quote.data, amount_vec![4, 1060, 100.501].data);
+
+    // inputs are not modified
+    assert_eq!(
+        vio.load_vector(delta_short_id).unwrap().data,
+        amount_vec![0.5, 0, 0, 100, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(weights_id).unwrap().data,
+        amount_vec![0.1, 1, 100].data
+    );
+}
+
+struct MarketIds {
+    asset_names_id: u128,
+    asset_quantities_long_id: u128,
+    asset_quantities_short_id: u128,
+    market_asset_names_id: u128,
+    supply_long_id: u128,
+    supply_short_id: u128,
+    demand_long_id: u128,
+    demand_short_id: u128,
+    delta_long_id: u128,
+    delta_short_id: u128,
+    asset_liquidity_id: u128,
+    asset_prices_id: u128,
+    asset_slopes_id: u128,
+}
+
+const MARKET_IDS: MarketIds = MarketIds {
+    asset_names_id: 1001,
+    asset_quantities_long_id: 1002,
+    asset_quantities_short_id: 1003,
+    market_asset_names_id: 101,
+    supply_long_id: 102,
+    supply_short_id: 103,
+    demand_long_id: 104,
+    demand_short_id: 105,
+    delta_long_id: 106,
+    delta_short_id: 107,
+    asset_liquidity_id: 108,
+    asset_prices_id: 109,
+    asset_slopes_id: 110,
+};
+
+fn run_update_supply(vio: &mut TestVectorIO) -> Result<(), ErrorCode> {
+    let ids = &MARKET_IDS;
+    let code = update_supply(
+        ids.asset_names_id,
+        ids.asset_quantities_long_id,
+        ids.asset_quantities_short_id,
+        ids.market_asset_names_id,
+        ids.supply_long_id,
+        ids.supply_short_id,
+        ids.demand_long_id,
+        ids.demand_short_id,
+        ids.delta_long_id,
+        ids.delta_short_id,
+        ids.asset_liquidity_id,
+        ids.asset_prices_id,
+        ids.asset_slopes_id,
+    );
+
+    let num_registers = 16;
+
+    let mut program = Program::new(vio);
+    let mut stack = Stack::new(num_registers);
+    program
+        .execute_with_stack(code, &mut stack)
+        .inspect_err(|_| {
+            log_stack!(&stack);
+        })
+}
+
+#[test]
+fn test_update_supply() {
+    let mut vio = TestVectorIO::new();
+    let ids = &MARKET_IDS;
+
+    vio.store_labels(ids.market_asset_names_id, label_vec![51, 52, 53, 54, 55])
+        .unwrap();
+
+    vio.store_vector(
+        ids.supply_long_id,
+        amount_vec![0.05, 0.05, 0.05, 0.05, 0.05],
+    )
+    .unwrap();
+
+    vio.store_vector(ids.supply_short_id, amount_vec![0, 0, 0, 0.02, 0])
+        .unwrap();
+
+    vio.store_vector(ids.demand_long_id, amount_vec![0.11, 0.1, 0.09, 10, 0.2])
+        .unwrap();
+
+    vio.store_vector(ids.demand_short_id, amount_vec![0, 0, 0, 0, 0.3])
+        .unwrap();
+
+    vio.store_vector(ids.delta_long_id, amount_vec![0, 0, 0, 0, 0])
+        .unwrap();
+
+    vio.store_vector(ids.delta_short_id, amount_vec![0, 0, 0, 0, 0])
+        .unwrap();
+
+    vio.store_vector(ids.asset_liquidity_id, amount_vec![10, 20, 30, 40, 50])
+        .unwrap();
+
+    vio.store_vector(ids.asset_prices_id, amount_vec![100, 200, 50, 10, 300])
+        .unwrap();
+
+    vio.store_vector(ids.asset_slopes_id, amount_vec![0.1, 0.2, 0.5, 0.01, 1])
+        .unwrap();
+
+    // Vendor supplies long of 52, and short of 54
+    vio.store_labels(ids.asset_names_id, label_vec![52, 54])
+        .unwrap();
+
+    vio.store_vector(ids.asset_quantities_long_id, amount_vec![1, 0])
+        .unwrap();
+
+    vio.store_vector(ids.asset_quantities_short_id, amount_vec![0, 0.5])
+        .unwrap();
+
+    run_update_supply(&mut vio).unwrap();
+
+    let market_asset_names = vio.load_labels(ids.market_asset_names_id).unwrap();
+    let supply_long = vio.load_vector(ids.supply_long_id).unwrap();
+    let supply_short = vio.load_vector(ids.supply_short_id).unwrap();
+    let demand_long = vio.load_vector(ids.demand_long_id).unwrap();
+    let demand_short = vio.load_vector(ids.demand_short_id).unwrap();
+    let delta_long = vio.load_vector(ids.delta_long_id).unwrap();
+    let delta_short = vio.load_vector(ids.delta_short_id).unwrap();
+
+    log_msg!("\n-= Program complete =-");
+    log_msg!("[out] Supply Long = {:0.9}", supply_long);
+    log_msg!("[out] Supply Short = {:0.9}", supply_short);
+    log_msg!("\n[out] Delta Short = {:0.9}", delta_short);
+    log_msg!("[out] Delta Long = {:0.9}", delta_long);
+
+    assert_eq!(market_asset_names.data, label_vec![51, 52, 53, 54, 55].data);
+
+    // supply short of 54 is netted against supply long
+    assert_eq!(
+        supply_long.data,
+        amount_vec![0.05, 1.05, 0.05, 0, 0.05].data
+    );
+    assert_eq!(supply_short.data, amount_vec![0, 0, 0, 0.47, 0].data);
+
+    // demand is not modified
+    assert_eq!(demand_long.data, amount_vec![0.11, 0.1, 0.09, 10, 0.2].data);
+    assert_eq!(demand_short.data, amount_vec![0, 0, 0, 0, 0.3].data);
+
+    assert_eq!(delta_long.data, amount_vec![0, 0.95, 0, 0, 0.15].data);
+    assert_eq!(delta_short.data, amount_vec![0.06, 0, 0.04, 10.47, 0].data);
+
+    // market doesn't grow, so liquidity, prices and slopes are not modified
+    assert_eq!(
+        vio.load_vector(ids.asset_liquidity_id).unwrap().data,
+        amount_vec![10, 20, 30, 40, 50].data
+    );
+    assert_eq!(
+        vio.load_vector(ids.asset_prices_id).unwrap().data,
+        amount_vec![100, 200, 50, 10, 300].data
+    );
+    assert_eq!(
+        vio.load_vector(ids.asset_slopes_id).unwrap().data,
+        amount_vec![0.1, 0.2, 0.5, 0.01, 1].data
+    );
+}
+
+#[test]
+fn test_update_supply_new_assets() {
+    let mut vio = TestVectorIO::new();
+    let ids = &MARKET_IDS;
+
+    vio.store_labels(ids.market_asset_names_id, label_vec![51, 53, 55])
+        .unwrap();
+
+    vio.store_vector(ids.supply_long_id, amount_vec![1, 1, 1])
+        .unwrap();
+
+    vio.store_vector(ids.supply_short_id, amount_vec![0, 0, 0])
+        .unwrap();
+
+    vio.store_vector(ids.demand_long_id, amount_vec![0.5, 2, 0])
+        .unwrap();
+
+    vio.store_vector(ids.demand_short_id, amount_vec![0, 0, 0.25])
+        .unwrap();
+
+    vio.store_vector(ids.delta_long_id, amount_vec![0.5, 0, 1.25])
+        .unwrap();
+
+    vio.store_vector(ids.delta_short_id, amount_vec![0, 1, 0])
+        .unwrap();
+
+    vio.store_vector(ids.asset_liquidity_id, amount_vec![10, 30, 50])
+        .unwrap();
+
+    vio.store_vector(ids.asset_prices_id, amount_vec![100, 50, 300])
+        .unwrap();
+
+    vio.store_vector(ids.asset_slopes_id, amount_vec![0.1, 0.5, 1])
+        .unwrap();
+
+    // Vendor reports new assets 52 and 56
+    vio.store_labels(ids.asset_names_id, label_vec![52, 53, 56])
+        .unwrap();
+
+    vio.store_vector(ids.asset_quantities_long_id, amount_vec![3, 0, 0])
+        .unwrap();
+
+    vio.store_vector(ids.asset_quantities_short_id, amount_vec![0, 1.5, 0.1])
+        .unwrap();
+
+    run_update_supply(&mut vio).unwrap();
+
+    let market_asset_names = vio.load_labels(ids.market_asset_names_id).unwrap();
+    let supply_long = vio.load_vector(ids.supply_long_id).unwrap();
+    let supply_short = vio.load_vector(ids.supply_short_id).unwrap();
+    let demand_long = vio.load_vector(ids.demand_long_id).unwrap();
+    let demand_short = vio.load_vector(ids.demand_short_id).unwrap();
+    let delta_long = vio.load_vector(ids.delta_long_id).unwrap();
+    let delta_short = vio.load_vector(ids.delta_short_id).unwrap();
+
+    // market grows, and all market vectors are expanded with zeros
+    assert_eq!(market_asset_names.data, label_vec![51, 52, 53, 55, 56].data);
+    assert_eq!(supply_long.data, amount_vec![1, 3, 0, 1, 0].data);
+    assert_eq!(supply_short.data, amount_vec![0, 0, 0.5, 0, 0.1].data);
+    assert_eq!(demand_long.data, amount_vec![0.5, 0, 2, 0, 0].data);
+    assert_eq!(demand_short.data, amount_vec![0, 0, 0, 0.25, 0].data);
+    assert_eq!(delta_long.data, amount_vec![0.5, 3, 0, 1.25, 0].data);
+    assert_eq!(delta_short.data, amount_vec![0, 0, 2.5, 0, 0.1].data);
+    assert_eq!(
+        vio.load_vector(ids.asset_liquidity_id).unwrap().data,
+        amount_vec![10, 0, 30, 50, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(ids.asset_prices_id).unwrap().data,
+        amount_vec![100, 0, 50, 300, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(ids.asset_slopes_id).unwrap().data,
+        amount_vec![0.1, 0, 0.5, 1, 0].data
+    );
+
+    // Index holding new asset 52 can be quoted, where asset 52 has no price
+    // and no liquidity yet, so its capacity is only vendor's supply
+    let index_asset_names_id = 2001;
+    let weights_id = 2002;
+    let quote_id = 2003;
+    vio.store_labels(index_asset_names_id, label_vec![51, 52])
+        .unwrap();
+    vio.store_vector(weights_id, amount_vec![1, 2]).unwrap();
+    let code = update_quote(
+        index_asset_names_id,
+        weights_id,
+        quote_id,
+        ids.market_asset_names_id,
+        ids.asset_prices_id,
+        ids.asset_slopes_id,
+        ids.asset_liquidity_id,
+        ids.delta_long_id,
+        ids.delta_short_id,
+    );
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(16);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+    // Capacity = MIN(10.5 / 1, 3 / 2)
+    // Price = 1 * 100 + 2 * 0
+    // Slope = 1 * 0.1 + 4 * 0
+    assert_eq!(
+        vio.load_vector(quote_id).unwrap().data,
+        amount_vec![1.5, 100, 0.1].data
+    );
+
+    // Vendor asset names must be sorted
+    vio.store_labels(ids.asset_names_id, label_vec![56, 52, 53])
+        .unwrap();
+    let result = run_update_supply(&mut vio);
+    assert!(matches!(result, Err(ErrorCode::NotSorted)));
+}
+
+#[test]
+fn test_transpose() {
+    let mut vio = TestVectorIO::new();
+    let num_registers = 8;
+
+    // --- 1. Setup VIO Inputs ---
+    let vector1_id = 100;
+    let vector2_id = 101;
+    let expected1_id = 102; // T1: [1, 4]
+    let expected2_id = 103; // T2: [2, 5]
+    let expected3_id = 104; // T3: [3, 6]
+    let delta_id = 105;
+
+    vio.store_vector(vector1_id, amount_vec![1, 2, 3]).unwrap();
+    vio.store_vector(vector2_id, amount_vec![4, 5, 6]).unwrap();
+    vio.store_vector(expected1_id, amount_vec![1, 4]).unwrap();
+    vio.store_vector(expected2_id, amount_vec![2, 5]).unwrap();
+    vio.store_vector(expected3_id, amount_vec![3, 6]).unwrap();
+
+    // --- 2. VIL Code Execution ---
+    let code = devil![
+        // 1. Setup Transposition
+        LDV         vector1_id              // Stack: [V1]
+        LDV         vector2_id              // Stack: [V1, V2]
+        T           2                       // Stack: [T1, T2, T3] (3 vectors)
+
+        // 2. Load Expected Vectors for comparison
+        LDV         expected1_id            // [T1, T2, T3, E1]
+        LDV         expected2_id            // [T1, T2, T3, E1, E2]
+        LDV         expected3_id            // [T1, T2, T3, E1, E2, E3] (6 vectors)
+
+        // 3. D3 = T3 - E3
+        SUB         3                       // Stack: [T1, T2, T3, E1, E2, D3]
+
+        // 4. D2 = T2 - E2
+        SWAP        1                       // Stack: [T1, T2, T3, E1, D3, E2]
+        SUB         4                       // Stack: [T1, T2, T3, E1, D3, D2]
+
+        // 5. D1 = T1 - E1
+        SWAP        2                       // Stack: [T1, T2, T3, D2, D3, E1]
+        SUB         5                       // Stack: [T1, T2, T3, D2, D3, D1]
+
+        // 6. Compute total delta - should be zero
+        ADD         1                       // Stack: [T1, T2, T3, D2, D3, D1 + D3]
+        ADD         2                       // Stack: [T1, T2, T3, D2, D3, D1 + D3 + D2]
+
+        // 7. Store the final zero vector
+        STV         delta_id
+    ];
+
+    let mut stack = Stack::new(num_registers);
+    let mut program = Program::new(&mut vio);
+
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    // --- 3. Assertion ---
+    let delta = vio.load_vector(delta_id).unwrap();
+
+    assert_eq!(delta.data, amount_vec![0, 0].data);
+}
+
+#[test]
+fn test_indirect_addressing() {
+    let mut vio = TestVectorIO::new();
+    let index_ids_id = 100;
+    let index_names_id = 101;
+    let result_id = 102;
+    let scale_id = 10;
+
+    vio.store_labels(index_ids_id, label_vec![201, 202, 203])
+        .unwrap();
+    vio.store_vector(201, amount_vec![0.1, 1, 100]).unwrap();
+    vio.store_vector(202, amount_vec![2, 4]).unwrap();
+    vio.store_vector(203, amount_vec![5]).unwrap();
+
+    // Scale vector whose id is on TOS, and store it back under same id
+    vio.store_labels(
+        scale_id,
+        Labels {
+            data: devil![
+                LDD         0               // Stack: [Id, Id]
+                LDVI                        // Stack: [Id, V]
+                IMMS        2               // Stack: [Id, V, 2]
+                SWAP        1               // Stack: [Id, 2, V]
+                MUL         1               // Stack: [Id, 2, 2V]
+                SWAP        1               // Stack: [Id, 2V, 2]
+                POPN        1               // Stack: [Id, 2V]
+                SWAP        1               // Stack: [2V, Id]
+                STVI                        // Stack: []
+            ],
+        },
+    )
+    .unwrap();
+
+    let code = devil![
+        LDL         index_ids_id            // Stack: [Ids]
+        LDD         0                       // Stack: [Ids, Ids]
+        FOLD        scale_id  0  0  0       // Stack: [Ids]
+        IMML        index_names_id          // Stack: [Ids, Id]
+        STLI                                // Stack: []
+        IMML        index_names_id          // Stack: [Id]
+        LDLI                                // Stack: [Ids]
+        LPOP                                // Stack: [Ids, Id]
+        LDVI                                // Stack: [Ids, V]
+        STV         result_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_vector(201).unwrap().data,
+        amount_vec![0.2, 2, 200].data
+    );
+    assert_eq!(vio.load_vector(202).unwrap().data, amount_vec![4, 8].data);
+    assert_eq!(vio.load_vector(203).unwrap().data, amount_vec![10].data);
+    assert_eq!(
+        vio.load_labels(index_names_id).unwrap().data,
+        label_vec![201, 202, 203].data
+    );
+    assert_eq!(
+        vio.load_vector(result_id).unwrap().data,
+        amount_vec![10].data
+    );
+
+    // Storage id must be a Label
+    let code = devil![
+        IMMS        201
+        LDVI
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::InvalidOperand)));
+}
+
+#[test]
+fn test_matrix() {
+    let mut vio = TestVectorIO::new();
+    let basket_weights_id = 100;
+    let asset_prices_id = 101;
+    let index_prices_id = 102;
+    let asset_weights_id = 103;
+    let basket_id = 104;
+    let index_value_id = 105;
+
+    vio.store_vector(asset_prices_id, amount_vec![10, 10_000, 100])
+        .unwrap();
+
+    // Basket of two indexes (rows) over three assets (columns)
+    let code = devil![
+        IMMS        0.1
+        IMMS        1
+        IMMS        100
+        PKV         3                   // Stack: [W1]
+        LDD         0                   // Stack: [W1, W1]
+        IMMS        2
+        SWAP        1                   // Stack: [W1, 2, W1]
+        MUL         1                   // Stack: [W1, 2, W2]
+        SWAP        1                   // Stack: [W1, W2, 2]
+        POPN        1                   // Stack: [W1, W2]
+        PKM         2                   // Stack: [M]
+        STMX        basket_weights_id
+        LDMX        basket_weights_id   // Stack: [M]
+        LDV         asset_prices_id     // Stack: [M, P]
+        MATVEC      1                   // Stack: [M, M.P]
+        STV         index_prices_id     // Stack: [M]
+        MCOL        2                   // Stack: [M, C3]
+        STV         asset_weights_id    // Stack: [M]
+        MROW        1                   // Stack: [M, W2]
+        LDV         asset_prices_id     // Stack: [M, W2, P]
+        DOT         1                   // Stack: [M, W2, W2.P]
+        PKV         1                   // Stack: [M, W2, [W2.P]]
+        STV         index_value_id      // Stack: [M, W2]
+        POPN        1                   // Stack: [M]
+        UNPKM                           // Stack: [W1, W2]
+        SWAP        1                   // Stack: [W2, W1]
+        PKM         2                   // Stack: [M']
+        STMX        basket_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_vector(index_prices_id).unwrap().data,
+        amount_vec![20001, 40002].data
+    );
+    assert_eq!(
+        vio.load_vector(asset_weights_id).unwrap().data,
+        amount_vec![100, 200].data
+    );
+    assert_eq!(
+        vio.load_vector(index_value_id).unwrap().data,
+        amount_vec![40002].data
+    );
+    let basket = vio.load_matrix(basket_id).unwrap();
+    assert_eq!(basket.num_cols, 3);
+    assert_eq!(basket.data, amount_vec![0.2, 2, 200, 0.1, 1, 100].data);
+
+    // Matrix columns must match vector length
+    let code = devil![
+        LDMX        basket_weights_id
+        LDV         index_prices_id
+        MATVEC      1
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::NotAligned)));
+
+    // Rows out of range
+    let code = devil![
+        LDMX        basket_weights_id
+        MROW        2
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::OutOfRange)));
+}
+
+#[test]
+fn test_load_matrix() {
+    use crate::Devil;
+    use alloy_primitives::U128;
+    use stylus_sdk::testing::*;
+
+    let vm = TestVM::default();
+    let mut devil = Devil::from(&vm);
+    let weights_id = 1002;
+    devil
+        .submit(U128::from(weights_id), amount_vec![0.1, 1, 100].to_vec())
+        .unwrap();
+
+    // Vector blob is not a matrix, and it fails cleanly instead of aborting
+    let code = devil![
+        LDMX        weights_id
+    ];
+    let mut program = Program::new(&mut devil);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::InvalidOperand)));
+}
+
+#[test]
+fn test_aggregation() {
+    let mut vio = TestVectorIO::new();
+    let weights_id = 100;
+    let prices_id = 101;
+    let depth_id = 102;
+    let result_id = 103;
+
+    vio.store_vector(weights_id, amount_vec![0.1, 1, 100])
+        .unwrap();
+    vio.store_vector(prices_id, amount_vec![10, 10_000, 100])
+        .unwrap();
+
+    let code = devil![
+        LDV         weights_id          // Stack: [W]
+        LDV         prices_id           // Stack: [W, P]
+        DOT         1                   // Stack: [W, W.P]
+        LDD         1                   // Stack: [W, W.P, W]
+        VPROD                           // Stack: [W, W.P, prod(W)]
+        PKV         2                   // Stack: [W, [W.P, prod(W)]]
+        STV         result_id           // Stack: [W]
+        CUMSUM                          // Stack: [cumsum(W)]
+        STV         depth_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_vector(result_id).unwrap().data,
+        amount_vec![20001, 10].data
+    );
+    assert_eq!(
+        vio.load_vector(depth_id).unwrap().data,
+        amount_vec![0.1, 1.1, 101.1].data
+    );
+
+    // Single overflow fails whole instruction
+    let code = devil![
+        IMMS        1000000000
+        IMMS        1000000000
+        IMMS        1000000000
+        PKV         3
+        VPROD
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::MathOverflow)));
+}
+
+#[test]
+fn test_compare_select() {
+    let mut vio = TestVectorIO::new();
+    let supply_id = 100;
+    let delta_short_id = 101;
+    let result_id = 102;
+    let lt_mask_id = 103;
+    let eq_mask_id = 104;
+
+    vio.store_vector(supply_id, amount_vec![0.05, 0.1, 0.2, 0.3])
+        .unwrap();
+    vio.store_vector(delta_short_id, amount_vec![0, 0.01, 0, 0.5])
+        .unwrap();
+
+    // Use supply where delta is short, else zero
+    let code = devil![
+        LDV         supply_id           // Stack: [S]
+        IMMS        0                   // Stack: [S, 0]
+        LDV         delta_short_id      // Stack: [S, 0, D]
+        GT          1                   // Stack: [S, 0, D > 0]
+        SELECT      2  1                // Stack: [S, 0, D > 0 ? S : 0]
+        STV         result_id           // Stack: [S, 0]
+        POPN        1                   // Stack: [S]
+        LDV         delta_short_id      // Stack: [S, D]
+        LT          1                   // Stack: [S, D < S]
+        STV         lt_mask_id          // Stack: [S]
+        IMMS        0.1                 // Stack: [S, 0.1]
+        LDD         1                   // Stack: [S, 0.1, S]
+        EQ          1                   // Stack: [S, 0.1, S == 0.1]
+        STV         eq_mask_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_vector(result_id).unwrap().data,
+        amount_vec![0, 0.1, 0, 0.3].data
+    );
+    assert_eq!(
+        vio.load_vector(lt_mask_id).unwrap().data,
+        amount_vec![1, 1, 1, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(eq_mask_id).unwrap().data,
+        amount_vec![0, 1, 0, 0].data
+    );
+
+    // Mask must be aligned with selected vectors
+    let code = devil![
+        LDV         supply_id
+        IMMS        0
+        IMMS        1
+        IMMS        0
+        PKV         2
+        SELECT      2  1
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::NotAligned)));
+}
+
+#[test]
+fn test_slicing() {
+    let mut vio = TestVectorIO::new();
+    let queue_id = 100;
+    let names_id = 101;
+    let slice_id = 102;
+    let gather_id = 103;
+    let scatter_id = 104;
+    let concat_id = 105;
+    let names_concat_id = 106;
+
+    vio.store_vector(queue_id, amount_vec![10, 20, 30, 40, 50])
+        .unwrap();
+    vio.store_labels(names_id, label_vec![51, 52, 53]).unwrap();
+
+    let code = devil![
+        LDV         queue_id            // Stack: [Q]
+        LDD         0                   // Stack: [Q, Q]
+        SLICE       1  3                // Stack: [Q, Q[1..4]]
+        STV         slice_id            // Stack: [Q]
+        IMML        4
+        IMML        0
+        PKL         2                   // Stack: [Q, I]
+        GATHER      1                   // Stack: [Q, Q[I]]
+        LDD         0                   // Stack: [Q, Q[I], Q[I]]
+        STV         gather_id           // Stack: [Q, Q[I]]
+        IMML        2
+        IMML        3
+        PKL         2                   // Stack: [Q, Q[I], J]
+        ZEROS       2                   // Stack: [Q, Q[I], J, 0]
+        SCATTER     2  1                // Stack: [Q, Q[I], J, 0 with 0[J] = Q[I]]
+        STV         scatter_id          // Stack: [Q, Q[I], J]
+        POPN        1                   // Stack: [Q, Q[I]]
+        CONCAT      1                   // Stack: [Q, Q[I] ++ Q]
+        STV         concat_id           // Stack: [Q]
+        LDL         names_id            // Stack: [Q, N]
+        LDD         0                   // Stack: [Q, N, N]
+        SLICE       2  1                // Stack: [Q, N, N[2..3]]
+        CONCAT      1                   // Stack: [Q, N, N[2..3] ++ N]
+        STL         names_concat_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_vector(slice_id).unwrap().data,
+        amount_vec![20, 30, 40].data
+    );
+    assert_eq!(
+        vio.load_vector(gather_id).unwrap().data,
+        amount_vec![50, 10].data
+    );
+    assert_eq!(
+        vio.load_vector(scatter_id).unwrap().data,
+        amount_vec![0, 0, 50, 10, 0].data
+    );
+    assert_eq!(
+        vio.load_vector(concat_id).unwrap().data,
+        amount_vec![50, 10, 10, 20, 30, 40, 50].data
+    );
+    assert_eq!(
+        vio.load_labels(names_concat_id).unwrap().data,
+        label_vec![53, 51, 52, 53].data
+    );
+
+    // Range exceeds length
+    let code = devil![
+        LDV         queue_id
+        SLICE       3  3
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::OutOfRange)));
+
+    // Index exceeds length
+    let code = devil![
+        LDV         queue_id
+        IMML        5
+        PKL         1
+        GATHER      1
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::OutOfRange)));
+
+    // Values and indices differ in length
+    let code = devil![
+        LDV         queue_id
+        IMML        0
+        PKL         1
+        LDV         queue_id
+        SCATTER     2  1
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::NotAligned)));
+}
+
+#[test]
+fn test_labels_algebra() {
+    let mut vio = TestVectorIO::new();
+    let market_names_id = 100;
+    let index_names_id = 101;
+    let new_names_id = 102;
+    let new_supply_id = 103;
+    let common_id = 104;
+    let missing_id = 105;
+    let sorted_names_id = 106;
+    let sorted_supply_id = 107;
+    let found_id = 108;
+
+    vio.store_labels(market_names_id, label_vec![51, 52, 53, 54, 55])
+        .unwrap();
+    vio.store_labels(index_names_id, label_vec![50, 53, 55, 56])
+        .unwrap();
+    vio.store_labels(new_names_id, label_vec![58, 57, 56])
+        .unwrap();
+    vio.store_vector(new_supply_id, amount_vec![0.8, 0.7, 0.6])
+        .unwrap();
+
+    let code = devil![
+        LDL         market_names_id     // Stack: [M]
+        LCHECK
+        LDL         index_names_id      // Stack: [M, I]
+        LCHECK
+        LINTER      1                   // Stack: [M, I & M]
+        STL         common_id           // Stack: [M]
+        LDL         index_names_id      // Stack: [M, I]
+        LDIFF       1                   // Stack: [M, I - M]
+        STL         missing_id          // Stack: [M]
+        LDV         new_supply_id       // Stack: [M, S]
+        LDL         new_names_id        // Stack: [M, S, N]
+        LSORT       1                   // Stack: [M, S', N']
+        LCHECK
+        STL         sorted_names_id     // Stack: [M, S']
+        STV         sorted_supply_id    // Stack: [M]
+        IMML        54                  // Stack: [M, 54]
+        LFIND       1                   // Stack: [M, 3, 1]
+        IMML        56                  // Stack: [M, 3, 1, 56]
+        LFIND       3                   // Stack: [M, 3, 1, 0, 0]
+        PKV         4                   // Stack: [M, [3, 1, 0, 0]]
+        STV         found_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_labels(common_id).unwrap().data,
+        label_vec![53, 55].data
+    );
+    assert_eq!(
+        vio.load_labels(missing_id).unwrap().data,
+        label_vec![50, 56].data
+    );
+    assert_eq!(
+        vio.load_labels(sorted_names_id).unwrap().data,
+        label_vec![56, 57, 58].data
+    );
+    assert_eq!(
+        vio.load_vector(sorted_supply_id).unwrap().data,
+        amount_vec![0.6, 0.7, 0.8].data
+    );
+    assert_eq!(
+        vio.load_vector(found_id).unwrap().data,
+        amount_vec![3, 1, 0, 0].data
+    );
+
+    // Unsorted labels are rejected
+    let code = devil![
+        LDL         new_names_id
+        LCHECK
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::NotSorted)));
+}
+
+fn random_labels(rng: &mut Rng) -> std::collections::BTreeMap<u128, u128> {
+    let len = rng.next_u64() % 8;
+    (0..len)
+        .map(|_| {
+            (
+                (rng.next_u64() % 16) as u128,
+                (rng.next_u64() % 100) as u128,
+            )
+        })
+        .collect()
+}
+
+/// Property test of labels joins against BTreeSet/BTreeMap model
+///
+/// We generate random sorted label sets A and B with values, and verify that:
+/// - LUNION produces sorted union of A and B
+/// - JUPD updates values of A with values of B, when B is subset of A
+/// - JADD adds values of B to values of A, when B is subset of A
+/// - JFLT filters values of A to labels of B, when B is subset of A
+/// - JUNION expands values of A and B with zeros to union of A and B
+///
+#[test]
+fn test_join_properties() {
+    use deli::amount::Amount;
+    use std::collections::BTreeSet;
+
+    let (labels_a_id, labels_b_id, vector_a_id, vector_b_id) = (1, 2, 3, 4);
+    let (result_id, expanded_a_id, expanded_b_id) = (5, 6, 7);
+
+    let to_vector = |values: Vec<u128>| Vector {
+        data: values
+            .into_iter()
+            .map(|x| Amount::from_u128_with_scale(x, 0))
+            .collect(),
+    };
+
+    let mut rng = Rng(0x5eed);
+    for _ in 0..500 {
+        let (map_a, map_b) = (random_labels(&mut rng), random_labels(&mut rng));
+        let set_a: BTreeSet<u128> = map_a.keys().copied().collect();
+        let set_b: BTreeSet<u128> = map_b.keys().copied().collect();
+        let is_subset = set_b.is_subset(&set_a);
+
+        let mut vio = TestVectorIO::new();
+        vio.store_labels(
+            labels_a_id,
+            Labels {
+                data: set_a.iter().copied().collect(),
+            },
+        )
+        .unwrap();
+        vio.store_labels(
+            labels_b_id,
+            Labels {
+                data: set_b.iter().copied().collect(),
+            },
+        )
+        .unwrap();
+        vio.store_vector(vector_a_id, to_vector(map_a.values().copied().collect()))
+            .unwrap();
+        vio.store_vector(vector_b_id, to_vector(map_b.values().copied().collect()))
+            .unwrap();
+
+        let run = |vio: &mut TestVectorIO, code: Vec<u128>| {
+            let mut program = Program::new(vio);
+            let mut stack = Stack::new(0);
+            program.execute_with_stack(code, &mut stack)
+        };
+
+        // LUNION
+        let code = devil![
+            LDL         labels_b_id
+            LDL         labels_a_id
+            LUNION      1
+            STL         result_id
+        ];
+        run(&mut vio, code).unwrap();
+        assert_eq!(
+            vio.load_labels(result_id).unwrap().data,
+            set_a.union(&set_b).copied().collect::<Vec<_>>()
+        );
+
+        // JUPD
+        let code = devil![
+            LDL         labels_a_id
+            LDL         labels_b_id
+            LDV         vector_b_id
+            LDV         vector_a_id
+            JUPD        1  3  2
+            STV         result_id
+        ];
+        let result = run(&mut vio, code);
+        if is_subset {
+            result.unwrap();
+            let expected = map_a
+                .iter()
+                .map(|(k, v)| *map_b.get(k).unwrap_or(v))
+                .collect();
+            assert_eq!(
+                vio.load_vector(result_id).unwrap().data,
+                to_vector(expected).data
+            );
+        } else {
+            assert!(matches!(result, Err(ErrorCode::MathUnderflow)));
+        }
+
+        // JADD
+        let code = devil![
+            LDL         labels_a_id
+            LDL         labels_b_id
+            LDV         vector_b_id
+            LDV         vector_a_id
+            JADD        1  3  2
+            STV         result_id
+        ];
+        let result = run(&mut vio, code);
+        if is_subset {
+            result.unwrap();
+            let expected = map_a
+                .iter()
+                .map(|(k, v)| v + map_b.get(k).unwrap_or(&0))
+                .collect();
+            assert_eq!(
+                vio.load_vector(result_id).unwrap().data,
+                to_vector(expected).data
+            );
+        } else {
+            assert!(matches!(result, Err(ErrorCode::MathUnderflow)));
+        }
+
+        // JFLT
+        let code = devil![
+            LDL         labels_a_id
+            LDL         labels_b_id
+            LDV         vector_b_id
+            LDV         vector_a_id
+            JFLT        3  2
+            STV         result_id
+        ];
+        let result = run(&mut vio, code);
+        if is_subset {
+            result.unwrap();
+            let expected = map_a
+                .iter()
+                .filter(|(k, _)| set_b.contains(k))
+                .map(|(_, v)| *v)
+                .collect();
+            assert_eq!(
+                vio.load_vector(result_id).unwrap().data,
+                to_vector(expected).data
+            );
+        } else {
+            assert!(matches!(result, Err(ErrorCode::NotFound)));
+        }
+
+        // JUNION
+        let code = devil![
+            LDL         labels_a_id
+            LDL         labels_b_id
+            LDV         vector_b_id
+            LDV         vector_a_id
+            JUNION      1  3  2
+            STL         result_id
+            STV         expanded_a_id
+            STV         expanded_b_id
+        ];
+        run(&mut vio, code).unwrap();
+        let union: Vec<u128> = set_a.union(&set_b).copied().collect();
+        let expand = |map: &std::collections::BTreeMap<u128, u128>| {
+            to_vector(union.iter().map(|k| *map.get(k).unwrap_or(&0)).collect())
+        };
+        assert_eq!(vio.load_labels(result_id).unwrap().data, union);
+        assert_eq!(
+            vio.load_vector(expanded_a_id).unwrap().data,
+            expand(&map_a).data
+        );
+        assert_eq!(
+            vio.load_vector(expanded_b_id).unwrap().data,
+            expand(&map_b).data
+        );
+    }
+}
+
+#[test]
+fn test_expanding_join() {
+    let mut vio = TestVectorIO::new();
+    let market_asset_names_id = 100;
+    let demand_long_id = 101;
+    let asset_names_id = 102;
+    let asset_quantities_id = 103;
+
+    vio.store_labels(market_asset_names_id, label_vec![51, 52, 53])
+        .unwrap();
+    vio.store_vector(demand_long_id, amount_vec![0.1, 0.2, 0.3])
+        .unwrap();
+    vio.store_labels(asset_names_id, label_vec![52, 54])
+        .unwrap();
+    vio.store_vector(asset_quantities_id, amount_vec![1, 2])
+        .unwrap();
+
+    // New index lists asset 54, which market doesn't list yet
+    let code = devil![
+        LDL         market_asset_names_id   // Stack: [MAN]
+        LDL         asset_names_id          // Stack: [MAN, AN]
+        LDV         asset_quantities_id     // Stack: [MAN, AN, AQ]
+        LDV         demand_long_id          // Stack: [MAN, AN, AQ, DL]
+        JUNION      1  3  2                 // Stack: [MAN, AN, AQ', DL', MAN']
+        STL         market_asset_names_id   // Stack: [MAN, AN, AQ', DL']
+        ADD         1                       // Stack: [MAN, AN, AQ', DL' + AQ']
+        STV         demand_long_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_labels(market_asset_names_id).unwrap().data,
+        label_vec![51, 52, 53, 54].data
+    );
+    assert_eq!(
+        vio.load_vector(demand_long_id).unwrap().data,
+        amount_vec![0.1, 1.2, 0.3, 2].data
+    );
+
+    // Same with JADD fails as market doesn't list asset 54
+    vio.store_labels(market_asset_names_id, label_vec![51, 52, 53])
+        .unwrap();
+    vio.store_vector(demand_long_id, amount_vec![0.1, 0.2, 0.3])
+        .unwrap();
+    let code = devil![
+        LDL         market_asset_names_id
+        LDL         asset_names_id
+        LDV         asset_quantities_id
+        LDV         demand_long_id
+        JADD        1  3  2
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::MathUnderflow)));
+
+    // JUNION fails when values don't match their labels in length
+    vio.store_vector(asset_quantities_id, amount_vec![1, 2, 3])
+        .unwrap();
+    let code = devil![
+        LDL         market_asset_names_id
+        LDL         asset_names_id
+        LDV         asset_quantities_id
+        LDV         demand_long_id
+        JUNION      1  3  2
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::NotAligned)));
+}
+
+#[test]
+fn test_asset_labels() {
+    use deli::asset::{make_asset, SIDE_LONG, SIDE_SHORT};
+
+    let mut vio = TestVectorIO::new();
+    let exposure_names_id = 100;
+    let exposure_id = 101;
+    let long_names_id = 102;
+    let long_exposure_id = 103;
+    let short_names_id = 104;
+    let sides_id = 105;
+
+    // Long and short exposures stored in one labelled vector
+    vio.store_labels(
+        exposure_names_id,
+        Labels {
+            data: vec![
+                make_asset(51, SIDE_LONG),
+                make_asset(51, SIDE_SHORT),
+                make_asset(52, SIDE_LONG),
+                make_asset(53, SIDE_SHORT),
+            ],
+        },
+    )
+    .unwrap();
+    vio.store_vector(exposure_id, amount_vec![0.1, 0.2, 0.3, 0.4])
+        .unwrap();
+
+    let code = devil![
+        LDV         exposure_id         // Stack: [E]
+        LDL         exposure_names_id   // Stack: [E, N]
+        LFSIDE      1  SIDE_LONG        // Stack: [E long, N long]
+        LASSET                          // Stack: [E long, A long]
+        STL         long_names_id       // Stack: [E long]
+        STV         long_exposure_id    // Stack: []
+        LDL         exposure_names_id   // Stack: [N]
+        LFSIDE      0  SIDE_SHORT       // Stack: [N short]
+        LASSET                          // Stack: [A short]
+        LMAKE       SIDE_LONG           // Stack: [A short as long]
+        STL         short_names_id      // Stack: []
+        LDL         exposure_names_id   // Stack: [N]
+        LSIDE                           // Stack: [S]
+        IMML        51                  // Stack: [S, 51]
+        LMAKE       SIDE_SHORT          // Stack: [S, 51 short]
+        LSIDE                           // Stack: [S, SIDE_SHORT]
+        PKL         1                   // Stack: [S, [SIDE_SHORT]]
+        CONCAT      1                   // Stack: [S, [SIDE_SHORT] + S]
+        STL         sides_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    assert_eq!(
+        vio.load_labels(long_names_id).unwrap().data,
+        label_vec![51, 52].data
+    );
+    assert_eq!(
+        vio.load_vector(long_exposure_id).unwrap().data,
+        amount_vec![0.1, 0.3].data
+    );
+    assert_eq!(
+        vio.load_labels(short_names_id).unwrap().data,
+        vec![make_asset(51, SIDE_LONG), make_asset(53, SIDE_LONG)]
+    );
+    assert_eq!(
+        vio.load_labels(sides_id).unwrap().data,
+        vec![SIDE_SHORT, SIDE_LONG, SIDE_SHORT, SIDE_LONG, SIDE_SHORT]
+    );
+}
+
+/// Run with: cargo test -p devil --features amount-exp
+#[test]
+#[cfg(feature = "amount-exp")]
+fn test_exp_ln_pow() {
+    let mut vio = TestVectorIO::new();
+    let rates_id = 100;
+    let result_id = 101;
+    let accrued_id = 102;
+
+    vio.store_vector(rates_id, amount_vec![1.01, 1.02, 2])
+        .unwrap();
+
+    // Fee accrual over fractional number of periods, e.g. 1.5 periods
+    let code = devil![
+        IMMS        1.5                 // Stack: [1.5]
+        LDV         rates_id            // Stack: [1.5, R]
+        POW         1                   // Stack: [1.5, R^1.5]
+        STV         accrued_id          // Stack: [1.5]
+        LDV         rates_id            // Stack: [1.5, R]
+        LN                              // Stack: [1.5, ln R]
+        EXP                             // Stack: [1.5, R]
+        VMAX                            // Stack: [1.5, max(R)]
+        PKV         2                   // Stack: [[1.5, max(R)]]
+        STV         result_id
+    ];
+
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+
+    // exact fixed point decimal values truncated to 18 decimals
+    assert_eq!(
+        vio.load_vector(accrued_id).unwrap().data,
+        amount_vec![
+            1.015037437733209917,
+            1.030149503712931951,
+            2.828427124746190097
+        ]
+        .data
+    );
+    // ln() truncates to 18 decimals, so exp(ln(2)) is within last decimal
+    assert_eq!(
+        vio.load_vector(result_id).unwrap().data,
+        amount_vec![1.5, 1.999999999999999999].data
+    );
+
+    // Logarithm of values below 1 is negative
+    let code = devil![
+        IMMS        0.5
+        LN
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(0);
+    let result = program.execute_with_stack(code, &mut stack);
+    assert!(matches!(result, Err(ErrorCode::MathOverflow)));
+}
+
+#[test]
+fn test_vector_storage() {
+    use crate::Devil;
+    use alloy_primitives::{keccak256, B256, U128};
+    use alloy_sol_types::SolEvent;
+    use deli::events::{VectorDeleted, VectorStored};
+    use stylus_sdk::testing::*;
+
+    let vm = TestVM::default();
+    let mut devil = Devil::from(&vm);
+
+    let weights_id = U128::from(1002);
+    let quote_id = U128::from(1003);
+    let weights = amount_vec![0.1, 1, 100].to_vec();
+    let weights_fixed = amount_vec![0.2, 1, 100].to_vec();
+    let quote = amount_vec![10, 10_000, 100].to_vec();
+
+    // get() must fail when there is no data, and succeed once submitted
+    assert!(devil.get(weights_id).is_err());
+    devil.submit(weights_id, weights.clone()).unwrap();
+    assert_eq!(devil.get(weights_id).unwrap(), weights);
+
+    // submit() never overwrites
+    assert!(devil.submit(weights_id, weights_fixed.clone()).is_err());
+
+    // update() with stale hash is rejected, and with matching hash succeeds
+    assert!(devil
+        .update(weights_id, weights_fixed.clone(), keccak256(&quote))
+        .is_err());
+    devil
+        .update(weights_id, weights_fixed.clone(), keccak256(&weights))
+        .unwrap();
+    assert_eq!(devil.get(weights_id).unwrap(), weights_fixed);
+
+    // update() with zero hash is unconditional, but requires existing data
+    devil
+        .update(weights_id, weights.clone(), B256::ZERO)
+        .unwrap();
+    assert!(devil.update(quote_id, quote.clone(), B256::ZERO).is_err());
+
+    // submit_many() requires aligned ids and data, and rejects duplicates
+    assert!(devil
+        .submit_many(vec![quote_id], vec![quote.clone(), quote.clone()])
+        .is_err());
+    assert!(devil
+        .submit_many(
+            vec![quote_id, weights_id],
+            vec![quote.clone(), weights.clone()]
+        )
+        .is_err());
+
+    devil
+        .submit_many(vec![quote_id], vec![quote.clone()])
+        .unwrap();
+    assert_eq!(devil.get(quote_id).unwrap(), quote);
+
+    // delete() clears storage
+    devil.delete(quote_id).unwrap();
+    assert!(devil.get(quote_id).is_err());
+    assert!(devil.delete(quote_id).is_err());
+
+    // every successful mutation is logged
+    let logs = vm.get_emitted_logs();
+    assert_eq!(logs.len(), 5);
+
+    let decode_stored = |(topics, data): &(Vec<B256>, Vec<u8>)| {
+        VectorStored::decode_raw_log(topics.iter().copied(), data, true).unwrap()
+    };
+
+    let event = decode_stored(&logs[0]);
+    assert_eq!(event.id, 1002);
+    assert_eq!(event.hash, keccak256(&weights));
+    assert_eq!(event.length, weights.len() as u128);
+
+    assert_eq!(decode_stored(&logs[1]).hash, keccak256(&weights_fixed));
+    assert_eq!(decode_stored(&logs[2]).hash, keccak256(&weights));
+
+    let event = decode_stored(&logs[3]);
+    assert_eq!(event.id, 1003);
+    assert_eq!(event.hash, keccak256(&quote));
+
+    let (topics, data) = &logs[4];
+    let event = VectorDeleted::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+    assert_eq!(event.id, 1003);
+}
+
+#[test]
+fn test_submit_order() {
+    let mut vio = TestVectorIO::new();
+    let order_id = 10001;
+
+    let run = |vio: &mut TestVectorIO, code: Vec<u128>| {
+        let mut program = Program::new(vio);
+        let mut stack = Stack::new(0);
+        if let Err(err) = program.execute_with_stack(code, &mut stack) {
+            log_stack!(&stack);
+            panic!("Failed to execute test: {:?}", err);
+        }
+    };
+
+    let collateral = amount_vec![1000, 500];
+
+    run(
+        &mut vio,
+        create_order(order_id, collateral.data[0].to_u128_raw()),
+    );
+    assert_eq!(
+        vio.load_vector(order_id).unwrap().data,
+        amount_vec![1000, 0, 0].data
+    );
+
+    // Collateral is topped up, while spent and minted are kept
+    vio.store_vector(order_id, amount_vec![400, 600, 5])
+        .unwrap();
+    run(
+        &mut vio,
+        update_order(order_id, collateral.data[1].to_u128_raw()),
+    );
+    assert_eq!(
+        vio.load_vector(order_id).unwrap().data,
+        amount_vec![900, 600, 5].data
+    );
+}
+
+#[test]
+fn test_operators() {
+    use crate::Devil;
+    use alloy_primitives::{Address, B256, U128};
+    use alloy_sol_types::SolEvent;
+    use deli::events::OperatorSet;
+    use deli::vis::namespace;
+    use stylus_sdk::testing::*;
+
+    let vm = TestVM::default();
+    let mut devil = Devil::from(&vm);
+
+    let owner = Address::from([0x11; 20]);
+    let operator = Address::from([0x22; 20]);
+    let other = Address::from([0x33; 20]);
+    let queue_id = U128::from(namespace(operator.into_array()) | 10000);
+    let other_id = U128::from(namespace(other.into_array()) | 10000);
+    let queue = Labels { data: vec![20001] }.to_vec();
+
+    vm.set_sender(owner);
+    devil.setup(owner).unwrap();
+
+    // Only owner can grant operator access
+    vm.set_sender(operator);
+    assert!(devil.submit(queue_id, queue.clone()).is_err());
+    assert!(devil.set_operator(operator, true).is_err());
+
+    vm.set_sender(owner);
+    devil.set_operator(operator, true).unwrap();
+    devil.set_operator(other, true).unwrap();
+    devil.submit(U128::from(10000), queue.clone()).unwrap();
+
+    // Operator can manage vectors, but cannot change ownership
+    vm.set_sender(operator);
+    devil.submit(queue_id, queue.clone()).unwrap();
+    devil.update(queue_id, queue.clone(), B256::ZERO).unwrap();
+    assert_eq!(devil.get(queue_id).unwrap(), queue);
+    devil.delete(queue_id).unwrap();
+
+    // Operator may read any id, but write only ids in its namespace, also
+    // when they are stored by program it executes
+    assert_eq!(devil.get(U128::from(10000)).unwrap(), queue);
+    assert!(devil.submit(other_id, queue.clone()).is_err());
+    assert!(devil
+        .submit_many(vec![queue_id, other_id], vec![queue.clone(), queue.clone()])
+        .is_err());
+    assert!(devil
+        .update(U128::from(10000), queue.clone(), B256::ZERO)
+        .is_err());
+    assert!(devil.delete(U128::from(10000)).is_err());
+    let store = |id: U128| {
+        let id = id.to::<u128>();
+        let code = devil! {
+            LDL     10000
+            STL     id
+        };
+        Labels { data: code }.to_vec()
+    };
+    assert!(devil.execute(store(other_id), 0).is_err());
+    devil.execute(store(queue_id), 0).unwrap();
+    assert_eq!(devil.get(queue_id).unwrap(), queue);
+    devil.delete(queue_id).unwrap();
+
+    vm.set_sender(other);
+    devil.submit(other_id, queue.clone()).unwrap();
+    assert!(devil.setup(operator).is_err());
+    assert!(devil.set_operator(operator, false).is_err());
+
+    vm.set_sender(owner);
+    devil.set_operator(operator, false).unwrap();
+
+    vm.set_sender(operator);
+    assert!(devil.submit(queue_id, queue).is_err());
+
+    let logs = vm.get_emitted_logs();
+    let decode = |(topics, data): &(Vec<B256>, Vec<u8>)| {
+        OperatorSet::decode_raw_log(topics.iter().copied(), data, true).ok()
+    };
+    let events: Vec<_> = logs.iter().filter_map(decode).collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].operator, operator);
+    assert!(events[0].enabled);
+    assert_eq!(events[1].operator, other);
+    assert_eq!(events[2].operator, operator);
+    assert!(!events[2].enabled);
+}
+
+#[test]
+fn test_execute_events() {
+    use crate::Devil;
+    use alloy_primitives::{keccak256, Address, U128};
+    use alloy_sol_types::SolEvent;
+    use deli::events::{ProgramExecuted, VectorStored};
+    use stylus_sdk::testing::*;
+
+    let vm = TestVM::default();
+    let mut devil = Devil::from(&vm);
+    let caller = Address::from([0x11; 20]);
+    vm.set_sender(caller);
+
+    let weights_id = 1002;
+    let scaled_id = 1003;
+    devil
+        .submit(U128::from(weights_id), amount_vec![0.1, 1, 100].to_vec())
+        .unwrap();
+
+    let code = Labels {
+        data: devil![
+            LDV         weights_id
+            IMMS        2
+            SWAP        1
+            MUL         1
+            STV         scaled_id
+        ],
+    }
+    .to_vec();
+
+    devil.execute(code.clone(), 0).unwrap();
+
+    let scaled = amount_vec![0.2, 2, 200].to_vec();
+    assert_eq!(devil.get(U128::from(scaled_id)).unwrap(), scaled);
+
+    let logs = vm.get_emitted_logs();
+    assert_eq!(logs.len(), 3);
+
+    let (topics, data) = &logs[1];
+    let event = VectorStored::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+    assert_eq!(event.id, scaled_id);
+    assert_eq!(event.hash, keccak256(&scaled));
+    assert_eq!(event.length, scaled.len() as u128);
+
+    let (topics, data) = &logs[2];
+    let event = ProgramExecuted::decode_raw_log(topics.iter().copied(), data, true).unwrap();
+    assert_eq!(event.program_hash, keccak256(&code));
+    assert_eq!(event.caller, caller);
+}
+
+#[test]
+fn test_program_arity() {
+    let mut vio = TestVectorIO::new();
+    let solve_quadratic_id = 10;
+    let result_id = 100;
+
+    vio.store_labels(
+        solve_quadratic_id,
+        Labels {
+            data: solve_quadratic(),
+        },
+    )
+    .unwrap();
+    vio.register_program(solve_quadratic_id, 3, 1, 4);
+
+    // Call with declared arity succeeds: 1 * Q^2 + 2 * Q - 8 = 0 => Q = 2
+    let code = devil![
+        IMMS        1
+        IMMS        2
+        IMMS        8
+        B           solve_quadratic_id  3  1  4
+        STR         _Result
+        LDM         _Result
+        PKV         1
+        STV         result_id
+    ];
+    let mut program = Program::new(&mut vio);
+    let mut stack = Stack::new(1);
+    if let Err(err) = program.execute_with_stack(code, &mut stack) {
+        log_stack!(&stack);
+        panic!("Failed to execute test: {:?}", err);
+    }
+    assert_eq!(
+        vio.load_vector(result_id).unwrap().data,
+        amount_vec![2].data
+    );
+
+    // Call with wrong number of inputs, outputs, or too few registers fails
+    for (num_inputs, num_outputs, num_registers) in [(2, 1, 4), (3, 2, 4), (3, 1, 3)] {
+        let code = devil![
+            IMMS        1
+            IMMS        2
+            IMMS        8
+            B           solve_quadratic_id  num_inputs  num_outputs  num_registers
+        ];
+        let mut program = Program::new(&mut vio);
+        let mut stack = Stack::new(0);
+        let result = program.execute_with_stack(code, &mut stack);
+        assert!(matches!(result, Err(ErrorCode::InvalidArity)));
+    }
+}
+
+#[test]
+fn test_program_registry() {
+    use crate::Devil;
+    use alloy_primitives::{B256, U128};
+    use alloy_sol_types::SolEvent;
+    use deli::events::ProgramInstalled;
+    use icore::vil::names::SOLVE_QUADRATIC;
+    use stylus_sdk::testing::*;
+
+    let vm = TestVM::default();
+    let mut devil = Devil::from(&vm);
+
+    let name = B256::from(SOLVE_QUADRATIC);
+    let code_v1 = Labels {
+        data: solve_quadratic(),
+    }
+    .to_vec();
+    let code_v2 = Labels {
+        data: devil![
+            STR         _C
+            STR         _P
+            STR         _S
+            LDM         _C
+            LDM         _P
+            DIV         1
+        ],
+    }
+    .to_vec();
+
+    let v1_id = U128::from(10);
+    let v2_id = U128::from(11);
+    let (one, three, four) = (U128::from(1), U128::from(3), U128::from(4));
+
+    assert!(devil.get_program(name).is_err());
+    assert!(devil
+        .install_program(B256::ZERO, v1_id, code_v1.clone(), three, one, four)
+        .is_err());
+
+    let version = devil
+        .install_program(name, v1_id, code_v1.clone(), three, one, four)
+        .unwrap();
+    assert_eq!(version, one);
+    assert_eq!(devil.get_program(name).unwrap(), (v1_id, one));
+
+    // Id already holds code
+    assert!(devil
+        .install_program(name, v1_id, code_v2.clone(), three, one, four)
+        .is_err());
+
+    let version = devil
+        .install_program(name, v2_id, code_v2.clone(), three, one, three)
+        .unwrap();
+    assert_eq!(version, U128::from(2));
+    assert_eq!(devil.get_program(name).unwrap(), (v2_id, U128::from(2)));
+    assert_eq!(devil.get_program_version(name, one).unwrap(), v1_id);
+    assert_eq!(
+        devil.get_program_version(name, U128::from(2)).unwrap(),
+        v2_id
+    );
+    assert!(devil.get_program_version(name, U128::ZERO).is_err());
+    assert!(devil.get_program_version(name, three).is_err());
+    assert_eq!(devil.get_program_arity(v1_id).unwrap(), (three, one, four));
+    assert_eq!(devil.get_program_arity(v2_id).unwrap(), (three, one, three));
+
+    // Code of installed procedures cannot be modified outside of install
+    assert!(devil.update(v1_id, code_v2.clone(), B256::ZERO).is_err());
+    assert!(devil.delete(v1_id).is_err());
+
+    // Nor can VIL programs overwrite it, which would replace procedure code
+    let program_id = v1_id.to::<u128>();
+    let overwrite = Labels {
+        data: devil![
+            LDL         program_id
+            STL         program_id
+        ],
+    }
+    .to_vec();
+    let result = Program::new(&mut devil).execute(overwrite.clone(), 0);
+    assert!(matches!(result, Err(ErrorCode::Locked)));
+    assert!(devil.execute(overwrite, 0).is_err());
+    assert_eq!(devil.get(v1_id).unwrap(), code_v1);
+
+    let logs = vm.get_emitted_logs();
+    let installed: Vec<_> = logs
+        .iter()
+        .filter_map(|(topics, data)| {
+            ProgramInstalled::decode_raw_log(topics.iter().copied(), data, true).ok()
+        })
+        .collect();
+    assert_eq!(installed.len(), 2);
+    assert_eq!(installed[1].name, name);
+    assert_eq!(installed[1].id, 11);
+    assert_eq!(installed[1].version, 2);
+    assert_eq!(installed[1].num_registers, 3);
+}
+
+#[test]
+fn test_execute_stored() {
+    use crate::Devil;
+    use alloy_primitives::{B256, U128};
+    use deli::vis::arg;
+    use icore::vil::names::{EXECUTE_BUY_ORDER, MATCH_MARKET, SOLVE_QUADRATIC};
+    use stylus_sdk::testing::*;
+
+    let vm = TestVM::default();
+    let mut devil = Devil::from(&vm);
+
+    let index_order_id = 10001;
+    let executed_asset_quantities_id = 10002;
+    let executed_index_quantities_id = 10003;
+    let asset_names_id = 1001;
+    let weights_id = 1002;
+    let quote_id = 1003;
+    let fees_id = 1004;
+    let supply_id = 1005;
+    let market_asset_names_id = 101;
+    let supply_long_id = 102;
+    let supply_short_id = 103;
+    let demand_long_id = 104;
+    let demand_short_id = 105;
+    let delta_long_id = 106;
+    let delta_short_id = 107;
+    let fee_accumulator_id = 201;
+    let solve_quadratic_id = 10;
+    let execute_buy_order_id = 11;
+    let match_market_id = 13;
+
+    let inputs = [
+        (asset_names_id, label_vec![51, 53, 54].to_vec()),
+        (weights_id, amount_vec![0.100, 1.000, 100.0].to_vec()),
+        (quote_id, amount_vec![10.00, 10_000, 100.0].to_vec()),
+        (index_order_id, amount_vec![1000.00, 0, 0].to_vec()),
+        (fees_id, amount_vec![0, 0, 0].to_vec()),
+        (supply_id, amount_vec![0].to_vec()),
+        (fee_accumulator_id, amount_vec![0, 0, 0].to_vec()),
+        (
+            market_asset_names_id,
+            label_vec![51, 52, 53, 54, 55].to_vec(),
+        ),
+        (demand_short_id, amount_vec![0, 0, 0.01, 0, 0].to_vec()),
+        (demand_long_id, amount_vec![0.1, 0.1, 0, 0.01, 0.2].to_vec()),
+        (supply_short_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+        (
+            supply_long_id,
+            amount_vec![0.05, 0.05, 0.05, 0.05, 0.05].to_vec(),
+        ),
+        (delta_short_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+        (delta_long_id, amount_vec![0, 0, 0, 0, 0].to_vec()),
+    ];
+    let (ids, data): (Vec<_>, Vec<_>) = inputs
+        .into_iter()
+        .map(|(id, data)| (U128::from(id), data))
+        .unzip();
+    devil.submit_many(ids, data).unwrap();
+
+    // Procedure is installed once with parametric ids...
+    let code = execute_buy_order(
+        arg(0),
+        arg(1),
+        arg(2),
+        arg(3),
+        arg(4),
+        arg(5),
+        arg(6),
+        arg(7),
+        arg(8),
+        arg(9),
+        arg(10),
+        arg(11),
+        arg(12),
+        arg(13),
+        arg(14),
+        arg(15),
+        arg(16),
+        arg(17),
+    );
+    devil
+        .install_program(
+            B256::from(SOLVE_QUADRATIC),
+            U128::from(solve_quadratic_id),
+            Labels {
+                data: solve_quadratic(),
+            }
+            .to_vec(),
+            U128::from(3),
+            U128::from(1),
+            U128::from(4),
+        )
+        .unwrap();
+    devil
+        .install_program(
+            B256::from(MATCH_MARKET),
+            U128::from(match_market_id),
+            Labels {
+                data: match_market(),
+            }
+            .to_vec(),
+            U128::from(7),
+            U128::from(4),
+            U128::from(7),
+        )
+        .unwrap();
+    devil
+        .install_program(
+            B256::from(EXECUTE_BUY_ORDER),
+            U128::from(execute_buy_order_id),
+            Labels { data: code }.to_vec(),
+            U128::ZERO,
+            U128::ZERO,
+            U128::from(20),
+        )
+        .unwrap();
+
+    // ...and then executed with actual ids as arguments
+    let (program_id, _) = devil.get_program(B256::from(EXECUTE_BUY_ORDER)).unwrap();
+    assert_eq!(program_id, U128::from(execute_buy_order_id));
+
+    let args = [
+        index_order_id,
+        executed_index_quantities_id,
+        executed_asset_quantities_id,
+        asset_names_id,
+        weights_id,
+        quote_id,
+        fees_id,
+        supply_id,
+        market_asset_names_id,
+        supply_long_id,
+        supply_short_id,
+        demand_long_id,
+        demand_short_id,
+        delta_long_id,
+        delta_short_id,
+        fee_accumulator_id,
+        solve_quadratic_id,
+        match_market_id,
+    ];
+    devil
+        .execute_stored(program_id, args.map(U128::from).to_vec())
+        .unwrap();
+
+    let load = |id: u128| Vector::from_vec(devil.get(U128::from(id)).unwrap()).data;
+
+    // same results as test_compute_1
+    assert_eq!(
+        load(index_order_id),
+        amount_vec![0.000000013986019975, 999.999999986013980025, 0.0999001995].data
+    );
+    assert_eq!(
+        load(executed_asset_quantities_id),
+        amount_vec![0.00999001995, 0.0999001995, 9.99001995].data
+    );
+    assert_eq!(
+        load(delta_short_id),
+        amount_vec![0.05999001995, 0.05, 0.0399001995, 9.95001995, 0.15].data
+    );
+
+    // Only installed procedures can be executed, and arguments must be given
+    assert!(devil
+        .execute_stored(U128::from(weights_id), args.map(U128::from).to_vec())
+        .is_err());
+    assert!(devil.execute_stored(program_id, Vec::new()).is_err());
 }
@@ -8,15 +8,22 @@ extern crate alloc;
 use alloc::vec::Vec;
 
 use alloy_primitives::{Address, U128};
-use alloy_sol_types::sol;
+use alloy_sol_types::{sol, SolCall};
+use deli::{amount::Amount, labels::Labels, vector::Vector, vis::namespace};
 use stylus_sdk::{
     prelude::*,
-    storage::{StorageAddress, StorageU128},
+    storage::{StorageAddress, StorageBool, StorageMap, StorageU128},
 };
 
 sol! {
-    event SomeEvent(address sender);
+    event VendorSet(address indexed vendor, bool enabled);
 
+    event SupplySubmitted(address indexed vendor);
+
+    /// Vector IL (VIL) virtual machine, where Market is an operator
+    interface IDevil  {
+        function submitMany(uint128[] memory ids, uint8[][] memory data) external;
+    }
 }
 
 #[storage]
@@ -24,15 +31,18 @@ sol! {
 pub struct Market {
     owner: StorageAddress,
     devil: StorageAddress,
-    supply_long: StorageU128,  // Vector = [+Supply; num_assets]
-    supply_short: StorageU128, // Vector = [-Supply; num_assets]
-    demand_long: StorageU128,  // Vector = [+Demand; num_assets]
-    demand_short: StorageU128, // Vector = [-Demand; num_assets]
-    delta_long: StorageU128,   // Vector = [+Delta; num_assets]
-    delta_short: StorageU128,  // Vector = [-Delta; num_assets]
-    liquidity: StorageU128,    // Vector = [Liquidity; num_assets]
-    prices: StorageU128,       // Vector = [Price; num_assets]
-    slopes: StorageU128,       // Vector = [Slope; num_assets]
+    assets: StorageU128,                       // Labels = [u128; num_assets]
+    supply_long: StorageU128,                  // Vector = [+Supply; num_assets]
+    supply_short: StorageU128,                 // Vector = [-Supply; num_assets]
+    demand_long: StorageU128,                  // Vector = [+Demand; num_assets]
+    demand_short: StorageU128,                 // Vector = [-Demand; num_assets]
+    delta_long: StorageU128,                   // Vector = [+Delta; num_assets]
+    delta_short: StorageU128,                  // Vector = [-Delta; num_assets]
+    liquidity: StorageU128,                    // Vector = [Liquidity; num_assets]
+    prices: StorageU128,                       // Vector = [Price; num_assets]
+    slopes: StorageU128,                       // Vector = [Slope; num_assets]
+    next_id: StorageU128,                      // Counter of ids allocated on DeVIL
+    vendors: StorageMap<Address, StorageBool>, // Authorised providers of supply
 }
 
 impl Market {
@@ -43,20 +53,105 @@ impl Market {
         }
         Ok(())
     }
+
+    /// Allocate new id for vector on DeVIL
+    ///
+    /// Ids are namespaced by Market address same as ids of Vaults.
+    fn allocate_id(&mut self) -> U128 {
+        let next_id = self.next_id.get() + U128::from(1);
+        self.next_id.set(next_id);
+        let namespace = namespace(self.vm().contract_address().into_array());
+        U128::from(namespace | next_id.to::<u128>())
+    }
 }
 
 #[public]
 impl Market {
+    /// Set up Market, and allocate ids of its vectors on DeVIL
+    ///
+    /// Ids are allocated once, so that Daxos may cache them.
     pub fn setup(&mut self, owner: Address, devil: Address) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().msg_sender())?;
         self.owner.set(owner);
         self.devil.set(devil);
+        if self.assets.get().is_zero() {
+            let ids = [(); 10].map(|_| self.allocate_id());
+            self.assets.set(ids[0]);
+            self.supply_long.set(ids[1]);
+            self.supply_short.set(ids[2]);
+            self.demand_long.set(ids[3]);
+            self.demand_short.set(ids[4]);
+            self.delta_long.set(ids[5]);
+            self.delta_short.set(ids[6]);
+            self.liquidity.set(ids[7]);
+            self.prices.set(ids[8]);
+            self.slopes.set(ids[9]);
+        }
         Ok(())
     }
 
-    pub fn submit_supply(&mut self) -> Result<(), Vec<u8>> {
+    /// Submit initial market vectors to DeVIL for sorted asset names
+    ///
+    /// All per-asset vectors start at zeros, and supply submitted by vendors
+    /// may add more assets later.
+    pub fn submit_assets(&mut self, assets: Vec<u8>) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let num_assets = Labels::from_vec(assets.clone()).data.len();
+        if num_assets == 0 {
+            Err(b"No assets")?;
+        }
+        let zeros = Vector {
+            data: vec![Amount::ZERO; num_assets],
+        }
+        .to_vec();
+        let ids = [
+            self.assets.get(),
+            self.supply_long.get(),
+            self.supply_short.get(),
+            self.demand_long.get(),
+            self.demand_short.get(),
+            self.delta_long.get(),
+            self.delta_short.get(),
+            self.liquidity.get(),
+            self.prices.get(),
+            self.slopes.get(),
+        ];
+        let mut data = vec![zeros; ids.len()];
+        data[0] = assets;
+        let submit = IDevil::submitManyCall {
+            ids: ids.map(|id| id.to::<u128>()).to_vec(),
+            data,
+        };
+        self.vm()
+            .call(&self, self.devil.get(), &submit.abi_encode())?;
+        Ok(())
+    }
+
+    /// Authorise provider to submit supply, or revoke it
+    pub fn set_vendor(&mut self, vendor: Address, enabled: bool) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        self.vendors.setter(vendor).set(enabled);
+        log(self.vm(), VendorSet { vendor, enabled });
+        Ok(())
+    }
+
+    pub fn is_vendor(&self, vendor: Address) -> bool {
+        self.vendors.get(vendor)
+    }
+
+    /// Accept supply submitted by vendor, which Daxos then applies to market
+    /// vectors on DeVIL
+    pub fn submit_supply(&mut self, vendor: Address) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().msg_sender())?;
-        todo!()
+        if !self.vendors.get(vendor) {
+            Err(b"Must be vendor")?;
+        }
+        log(self.vm(), SupplySubmitted { vendor });
+        Ok(())
+    }
+
+    pub fn get_assets(&self) -> U128 {
+        self.assets.get()
     }
 
     pub fn get_supply(&self) -> (U128, U128) {
@@ -85,4 +180,94 @@ impl Market {
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_market() {
+        let vm = TestVM::default();
+        let mut market = Market::from(&vm);
+
+        let daxos = Address::from([0x01; 20]);
+        let devil = Address::from([0x0d; 20]);
+        let vendor = Address::from([0x0e; 20]);
+        let stop = b"Stop".to_vec();
+
+        vm.set_contract_address(Address::from([0x0a; 20]));
+        vm.set_sender(daxos);
+        market.setup(daxos, devil).unwrap();
+
+        // Ids are allocated once in Market namespace
+        let namespace = namespace([0x0a; 20]);
+        let ids = [
+            market.get_assets(),
+            market.get_supply().0,
+            market.get_supply().1,
+            market.get_demand().0,
+            market.get_demand().1,
+            market.get_delta().0,
+            market.get_delta().1,
+            market.get_liquidity(),
+            market.get_prices(),
+            market.get_slopes(),
+        ]
+        .map(|id| id.to::<u128>());
+        assert_eq!(ids, core::array::from_fn(|i| namespace | (i as u128 + 1)));
+        market.setup(daxos, devil).unwrap();
+        assert_eq!(market.get_slopes(), U128::from(namespace | 10));
+
+        // Only Daxos may submit assets, authorise vendors and submit supply
+        vm.set_sender(vendor);
+        let assets = Labels {
+            data: vec![51, 52, 53],
+        }
+        .to_vec();
+        assert!(market.submit_assets(assets.clone()).is_err());
+        assert!(market.set_vendor(vendor, true).is_err());
+        assert!(market.submit_supply(vendor).is_err());
+        vm.set_sender(daxos);
+
+        // Market starts with zeros for each asset
+        assert_eq!(market.submit_assets(Vec::new()).unwrap_err(), b"No assets");
+        let zeros = Vector {
+            data: vec![Amount::ZERO; 3],
+        }
+        .to_vec();
+        let mut data = vec![zeros; 10];
+        data[0] = assets.clone();
+        let submit = IDevil::submitManyCall {
+            ids: ids.to_vec(),
+            data,
+        }
+        .abi_encode();
+        vm.mock_call(devil, submit.clone(), Err(stop.clone()));
+        assert_eq!(market.submit_assets(assets.clone()).unwrap_err(), stop);
+        vm.mock_call(devil, submit, Ok(Vec::new()));
+        market.submit_assets(assets).unwrap();
+
+        // Only authorised vendor may submit supply
+        assert_eq!(market.submit_supply(vendor).unwrap_err(), b"Must be vendor");
+        market.set_vendor(vendor, true).unwrap();
+        assert!(market.is_vendor(vendor));
+        market.submit_supply(vendor).unwrap();
+        market.set_vendor(vendor, false).unwrap();
+        assert_eq!(market.submit_supply(vendor).unwrap_err(), b"Must be vendor");
+
+        let logs = vm.get_emitted_logs();
+        let vendors: Vec<_> = logs
+            .iter()
+            .filter_map(|(topics, data)| {
+                VendorSet::decode_raw_log(topics.iter().copied(), data, true).ok()
+            })
+            .map(|event| (event.vendor, event.enabled))
+            .collect();
+        assert_eq!(vendors, vec![(vendor, true), (vendor, false)]);
+        let submitted = logs
+            .iter()
+            .filter(|(topics, _)| topics[0] == SupplySubmitted::SIGNATURE_HASH)
+            .count();
+        assert_eq!(submitted, 1);
+    }
+}
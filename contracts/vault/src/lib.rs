@@ -5,18 +5,39 @@
 #[macro_use]
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
-use alloy_primitives::{Address, U128};
-use alloy_sol_types::sol;
+use alloy_primitives::{Address, B256, U128, U256};
+use alloy_sol_types::{sol, SolCall};
+use deli::{amount::Amount, labels::Labels, vector::Vector, vis::namespace};
+use icore::vil::{
+    queue::{dequeue_order, enqueue_order},
+    submit_order::{create_order, update_order},
+};
 use stylus_sdk::{
     prelude::*,
-    storage::{StorageAddress, StorageMap, StorageU128},
+    storage::{StorageAddress, StorageMap, StorageString, StorageU128, StorageU256},
 };
 
 sol! {
-    event SomeEvent(address sender);
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    /// Vector IL (VIL) virtual machine, where Vault is an operator
+    interface IDevil  {
+        function submit(uint128 id, uint8[] memory data) external;
+
+        function submitMany(uint128[] memory ids, uint8[][] memory data) external;
+
+        function update(uint128 id, uint8[] memory data, bytes32 expected_hash) external;
+
+        function delete(uint128 id) external;
 
+        function get(uint128 id) external view returns (uint8[] memory);
+
+        function execute(uint8[] memory code, uint128 num_registry) external;
+    }
 }
 
 #[storage]
@@ -25,10 +46,25 @@ pub struct Vault {
     owner: StorageAddress,
     devil: StorageAddress,
     orders: StorageMap<Address, StorageU128>, // Mapping = {User Address => Vector = [USDC Remaining, USDC Spent, ITP Minted]}
+    order_users: StorageMap<U128, StorageAddress>, // Mapping = {Order Id => User Address}
     queue: StorageU128,                       // Labels  = [u128; num_orders]
     assets: StorageU128,                      // Labels  = [u128; num_assets]
     weights: StorageU128,                     // Vector  = [Amount; num_assets]
     quote: StorageU128,                       // Vector  = [Capacity, Price, Slope]
+    fees: StorageU128,                        // Vector  = [Entry Fee, Exit Fee, Management Fee]
+    supply: StorageU128,                      // Vector  = [ITP Supply]
+    accrued_fees: StorageU128,                // Vector  = [Entry Fees, Exit Fees, Management Fees]
+    next_id: StorageU128,                     // Counter of ids allocated on DeVIL
+
+    // Mapping = {User Address => Vector = [ITP Remaining, ITP Redeemed, USDC Withdrawn]}
+    sell_orders: StorageMap<Address, StorageU128>,
+
+    // ERC-20 ITP Token
+    name: StorageString,
+    symbol: StorageString,
+    total_supply: StorageU256,
+    balances: StorageMap<Address, StorageU256>,
+    allowances: StorageMap<Address, StorageMap<Address, StorageU256>>,
 }
 
 impl Vault {
@@ -39,28 +75,422 @@ impl Vault {
         }
         Ok(())
     }
+
+    /// Allocate new id for vector on DeVIL
+    ///
+    /// Ids are namespaced by Vault address, so that Vaults sharing DeVIL do not
+    /// clash, and DeVIL only lets Vault write ids in its namespace.
+    fn allocate_id(&mut self) -> U128 {
+        let next_id = self.next_id.get() + U128::from(1);
+        self.next_id.set(next_id);
+        let namespace = namespace(self.vm().contract_address().into_array());
+        U128::from(namespace | next_id.to::<u128>())
+    }
+
+    /// Index vectors, i.e. [Assets, Weights, Quote, Fees, Supply, Accrued Fees]
+    fn index_ids(&self) -> [U128; 6] {
+        [
+            self.assets.get(),
+            self.weights.get(),
+            self.quote.get(),
+            self.fees.get(),
+            self.supply.get(),
+            self.accrued_fees.get(),
+        ]
+    }
+
+    fn check_fees(fees: &[u8]) -> Result<(), Vec<u8>> {
+        // Fees = [Entry Fee, Exit Fee, Management Fee]
+        if fees.len() != 3 * size_of::<Amount>() {
+            Err(b"Invalid fees")?;
+        }
+        Ok(())
+    }
+
+    fn queue_id(&mut self) -> U128 {
+        let queue_id = self.queue.get();
+        if !queue_id.is_zero() {
+            return queue_id;
+        }
+        let queue_id = self.allocate_id();
+        self.queue.set(queue_id);
+        queue_id
+    }
+
+    /// Load vector (or labels) blob from DeVIL, if there is any
+    ///
+    /// DeVIL reverts with "No data" when id holds no data, and any other
+    /// failure is propagated.
+    fn load_from_devil(&self, id: U128) -> Result<Option<Vec<u8>>, Vec<u8>> {
+        let get = IDevil::getCall { id: id.to() };
+        let result = match self
+            .vm()
+            .static_call(&self, self.devil.get(), &get.abi_encode())
+        {
+            Ok(result) => result,
+            Err(err) => {
+                let err: Vec<u8> = err.into();
+                if err == b"No data" {
+                    return Ok(None);
+                }
+                return Err(err);
+            }
+        };
+        let data = IDevil::getCall::abi_decode_returns(&result, true)
+            .map_err(|_| b"Invalid data".to_vec())?;
+        Ok(Some(data._0))
+    }
+
+    fn call_devil(&mut self, calldata: Vec<u8>) -> Result<(), Vec<u8>> {
+        let devil_address = self.devil.get();
+        self.vm().call(&self, devil_address, &calldata)?;
+        Ok(())
+    }
+
+    fn execute_on_devil(&mut self, code: Vec<u128>, num_registry: u128) -> Result<(), Vec<u8>> {
+        let execute = IDevil::executeCall {
+            code: Labels { data: code }.to_vec(),
+            num_registry,
+        };
+        self.call_devil(execute.abi_encode())
+    }
+
+    fn transfer_internal(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+    ) -> Result<(), Vec<u8>> {
+        let mut from_balance = self.balances.setter(from);
+        let balance = from_balance.get();
+        if balance < value {
+            Err(b"Insufficient balance")?;
+        }
+        from_balance.set(balance - value);
+        let mut to_balance = self.balances.setter(to);
+        let balance = to_balance.get();
+        to_balance.set(balance + value);
+        log(self.vm(), Transfer { from, to, value });
+        Ok(())
+    }
+
+    /// Create order vector or add amount to its first component
+    ///
+    /// Buy order (Collateral, Spent, Minted) and sell order (Quantity,
+    /// Redeemed, Withdrawn) have same layout.
+    fn submit_order_vector(&mut self, order_id: U128, amount: U128) -> Result<U128, Vec<u8>> {
+        let (order_id, code) = if order_id.is_zero() {
+            let order_id = self.allocate_id();
+            (order_id, create_order(order_id.to(), amount.to()))
+        } else {
+            (order_id, update_order(order_id.to(), amount.to()))
+        };
+        self.execute_on_devil(code, 0)?;
+        Ok(order_id)
+    }
+
+    fn order_id(&self, user: Address) -> Result<U128, Vec<u8>> {
+        let order_id = self.orders.get(user);
+        if order_id.is_zero() {
+            Err(b"No order")?;
+        }
+        Ok(order_id)
+    }
+
+    /// Add order to queue, which is created with first order
+    fn enqueue(&mut self, order_id: U128) -> Result<(), Vec<u8>> {
+        let queue_id = self.queue_id();
+        if self.load_from_devil(queue_id)?.is_some() {
+            let code = enqueue_order(queue_id.to(), order_id.to());
+            self.execute_on_devil(code, 0)
+        } else {
+            let submit = IDevil::submitCall {
+                id: queue_id.to(),
+                data: Labels {
+                    data: vec![order_id.to()],
+                }
+                .to_vec(),
+            };
+            self.call_devil(submit.abi_encode())
+        }
+    }
+
+    /// Remove order from queue, which is cleared with last order
+    fn dequeue(&mut self, order_id: U128) -> Result<(), Vec<u8>> {
+        let queue_id = self.queue.get();
+        if queue_id.is_zero() || self.load_from_devil(queue_id)?.is_none() {
+            return Ok(());
+        }
+        let code = dequeue_order(queue_id.to(), order_id.to());
+        self.execute_on_devil(code, 0)
+    }
 }
 
 #[public]
 impl Vault {
-    pub fn setup(&mut self, owner: Address, devil: Address) -> Result<(), Vec<u8>> {
+    /// Set up Vault, and allocate ids of its index vectors on DeVIL
+    ///
+    /// Ids are allocated once, so that Daxos may cache them.
+    pub fn setup(
+        &mut self,
+        owner: Address,
+        devil: Address,
+        name: String,
+        symbol: String,
+    ) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().msg_sender())?;
         self.owner.set(owner);
         self.devil.set(devil);
+        self.name.set_str(name);
+        self.symbol.set_str(symbol);
+        if self.assets.get().is_zero() {
+            let ids = [(); 6].map(|_| self.allocate_id());
+            self.assets.set(ids[0]);
+            self.weights.set(ids[1]);
+            self.quote.set(ids[2]);
+            self.fees.set(ids[3]);
+            self.supply.set(ids[4]);
+            self.accrued_fees.set(ids[5]);
+        }
         Ok(())
     }
 
-    pub fn submit_order(&mut self, user: Address, collateral_amount: U128) -> Result<(), Vec<u8>> {
+    /// Submit index vectors to DeVIL, i.e. asset names, their weights, and
+    /// fees (Entry Fee, Exit Fee, Management Fee)
+    ///
+    /// Supply and accrued fees start at zeros, while quote is stored by first
+    /// quote update.
+    pub fn submit_index(
+        &mut self,
+        assets: Vec<u8>,
+        weights: Vec<u8>,
+        fees: Vec<u8>,
+    ) -> Result<(), Vec<u8>> {
         self.check_owner(self.vm().msg_sender())?;
-        let mut order_access = self.orders.setter(user);
-        let order = order_access.get();
-        if order == U128::ZERO {
-            let _ = collateral_amount;
-            let _ = &mut order_access;
-            todo!("Interact with DeVIL to create new order");
-        } else {
-            todo!("Interact with DeVIL to update existing order");
+        if assets.is_empty() || assets.len() != weights.len() {
+            Err(b"Invalid weights")?;
         }
+        Self::check_fees(&fees)?;
+        let [assets_id, weights_id, _, fees_id, supply_id, accrued_fees_id] =
+            self.index_ids().map(|id| id.to::<u128>());
+        let supply = Vector {
+            data: vec![Amount::ZERO],
+        };
+        let accrued_fees = Vector {
+            data: vec![Amount::ZERO; 3],
+        };
+        let submit = IDevil::submitManyCall {
+            ids: vec![assets_id, weights_id, fees_id, supply_id, accrued_fees_id],
+            data: vec![
+                assets,
+                weights,
+                fees,
+                supply.to_vec(),
+                accrued_fees.to_vec(),
+            ],
+        };
+        self.call_devil(submit.abi_encode())
+    }
+
+    /// Set fees (Entry Fee, Exit Fee, Management Fee) of submitted index
+    pub fn set_fees(&mut self, fees: Vec<u8>) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        Self::check_fees(&fees)?;
+        let update = IDevil::updateCall {
+            id: self.fees.get().to(),
+            data: fees,
+            expected_hash: B256::ZERO,
+        };
+        self.call_devil(update.abi_encode())
+    }
+
+    pub fn name(&self) -> String {
+        self.name.get_string()
+    }
+
+    pub fn symbol(&self) -> String {
+        self.symbol.get_string()
+    }
+
+    /// ITP Token has same precision as Amount
+    pub fn decimals(&self) -> u8 {
+        18
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        self.total_supply.get()
+    }
+
+    pub fn balance_of(&self, owner: Address) -> U256 {
+        self.balances.get(owner)
+    }
+
+    pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowances.getter(owner).get(spender)
+    }
+
+    pub fn transfer(&mut self, to: Address, value: U256) -> Result<bool, Vec<u8>> {
+        let from = self.vm().msg_sender();
+        self.transfer_internal(from, to, value)?;
+        Ok(true)
+    }
+
+    pub fn approve(&mut self, spender: Address, value: U256) -> Result<bool, Vec<u8>> {
+        let owner = self.vm().msg_sender();
+        self.allowances.setter(owner).insert(spender, value);
+        log(
+            self.vm(),
+            Approval {
+                owner,
+                spender,
+                value,
+            },
+        );
+        Ok(true)
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        value: U256,
+    ) -> Result<bool, Vec<u8>> {
+        let spender = self.vm().msg_sender();
+        let mut from_allowances = self.allowances.setter(from);
+        let mut allowance_access = from_allowances.setter(spender);
+        let allowance = allowance_access.get();
+        if allowance < value {
+            Err(b"Insufficient allowance")?;
+        }
+        allowance_access.set(allowance - value);
+        self.transfer_internal(from, to, value)?;
+        Ok(true)
+    }
+
+    /// Mint ITP Token for filled order (Daxos only)
+    pub fn mint(&mut self, to: Address, value: U256) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let mut balance_access = self.balances.setter(to);
+        let balance = balance_access.get();
+        balance_access.set(balance + value);
+        self.total_supply.set(self.total_supply.get() + value);
+        log(
+            self.vm(),
+            Transfer {
+                from: Address::ZERO,
+                to,
+                value,
+            },
+        );
+        Ok(())
+    }
+
+    /// Burn ITP Token for redeemed order (Daxos only)
+    pub fn burn(&mut self, from: Address, value: U256) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let mut balance_access = self.balances.setter(from);
+        let balance = balance_access.get();
+        if balance < value {
+            Err(b"Insufficient balance")?;
+        }
+        balance_access.set(balance - value);
+        self.total_supply.set(self.total_supply.get() - value);
+        log(
+            self.vm(),
+            Transfer {
+                from,
+                to: Address::ZERO,
+                value,
+            },
+        );
+        Ok(())
+    }
+
+    /// Submit new order or add collateral to existing order of the user
+    ///
+    /// Returns id of the order vector on DeVIL.
+    pub fn submit_order(
+        &mut self,
+        user: Address,
+        collateral_amount: U128,
+    ) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let order_id = self.submit_order_vector(self.orders.get(user), collateral_amount)?;
+        self.orders.setter(user).set(order_id);
+        self.order_users.setter(order_id).set(user);
+        Ok(order_id)
+    }
+
+    /// Submit new sell order or add quantity to existing sell order of the user
+    ///
+    /// Sell order is executed immediately, and its unfilled quantity is zeroed,
+    /// so that the vector only keeps redeemed quantity and withdrawn collateral.
+    ///
+    /// Returns id of the sell order vector on DeVIL.
+    pub fn submit_sell_order(&mut self, user: Address, quantity: U128) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let order_id = self.submit_order_vector(self.sell_orders.get(user), quantity)?;
+        self.sell_orders.setter(user).set(order_id);
+        Ok(order_id)
+    }
+
+    /// Queue order of the user for later execution, e.g. partially filled order
+    pub fn enqueue_order(&mut self, user: Address) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let order_id = self.order_id(user)?;
+        self.enqueue(order_id)
+    }
+
+    /// Remove order of the user from the queue, e.g. once it was fully filled
+    pub fn dequeue_order(&mut self, user: Address) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let order_id = self.order_id(user)?;
+        self.dequeue(order_id)
+    }
+
+    /// Cancel remaining order of the user
+    ///
+    /// Order is removed from the queue and its remaining collateral is zeroed,
+    /// while spent collateral and minted quantity are kept.
+    ///
+    /// Returns remaining collateral to be withdrawn.
+    pub fn cancel_order(&mut self, user: Address) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let order_id = self.order_id(user)?;
+        let data = self.load_from_devil(order_id)?.ok_or(b"No order")?;
+        let mut order = Vector::from_vec(data);
+        let collateral = *order.data.first().ok_or(b"Invalid order")?;
+        order.data[0] = Amount::ZERO;
+        let update = IDevil::updateCall {
+            id: order_id.to(),
+            data: order.to_vec(),
+            expected_hash: B256::ZERO,
+        };
+        self.call_devil(update.abi_encode())?;
+        self.dequeue(order_id)?;
+        Ok(collateral.to_u128())
+    }
+
+    /// Remove all orders from the queue, e.g. once batch filled them all
+    pub fn clear_queue(&mut self) -> Result<(), Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        let queue_id = self.queue.get();
+        if queue_id.is_zero() || self.load_from_devil(queue_id)?.is_none() {
+            return Ok(());
+        }
+        let delete = IDevil::deleteCall { id: queue_id.to() };
+        self.call_devil(delete.abi_encode())
+    }
+
+    pub fn get_order(&self, user: Address) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        Ok(self.orders.get(user))
+    }
+
+    /// Get user owning order, e.g. to mint ITP Token for orders filled in batch
+    pub fn get_order_user(&self, order_id: U128) -> Result<Address, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        Ok(self.order_users.get(order_id))
     }
 
     pub fn get_queue(&self) -> Result<U128, Vec<u8>> {
@@ -82,7 +512,444 @@ impl Vault {
         self.check_owner(self.vm().msg_sender())?;
         Ok(self.quote.get())
     }
+
+    pub fn get_fees(&self) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        Ok(self.fees.get())
+    }
+
+    pub fn get_supply(&self) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        Ok(self.supply.get())
+    }
+
+    pub fn get_accrued_fees(&self) -> Result<U128, Vec<u8>> {
+        self.check_owner(self.vm().msg_sender())?;
+        Ok(self.accrued_fees.get())
+    }
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_erc20() {
+        let vm = TestVM::default();
+        let mut vault = Vault::from(&vm);
+
+        let daxos = Address::from([0x01; 20]);
+        let devil = Address::from([0x0d; 20]);
+        let alice = Address::from([0x0a; 20]);
+        let bob = Address::from([0x0b; 20]);
+        let value = |x: u64| U256::from(x);
+
+        vm.set_sender(daxos);
+        vault
+            .setup(daxos, devil, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+        assert_eq!(vault.name(), "Top 10 Index");
+        assert_eq!(vault.symbol(), "TOP10");
+        assert_eq!(vault.decimals(), 18);
+
+        // Only Daxos can mint and burn
+        vm.set_sender(alice);
+        assert!(vault.mint(alice, value(100)).is_err());
+
+        vm.set_sender(daxos);
+        vault.mint(alice, value(100)).unwrap();
+        assert_eq!(vault.total_supply(), value(100));
+        assert_eq!(vault.balance_of(alice), value(100));
+
+        vm.set_sender(alice);
+        assert!(vault.transfer(bob, value(101)).is_err());
+        assert!(vault.transfer(bob, value(30)).unwrap());
+        assert_eq!(vault.balance_of(alice), value(70));
+        assert_eq!(vault.balance_of(bob), value(30));
+
+        // Spender is limited by allowance
+        assert!(vault.approve(bob, value(50)).unwrap());
+        assert_eq!(vault.allowance(alice, bob), value(50));
+
+        vm.set_sender(bob);
+        assert!(vault.transfer_from(alice, bob, value(60)).is_err());
+        assert!(vault.transfer_from(alice, bob, value(20)).unwrap());
+        assert_eq!(vault.allowance(alice, bob), value(30));
+        assert_eq!(vault.balance_of(alice), value(50));
+        assert_eq!(vault.balance_of(bob), value(50));
+
+        assert!(vault.burn(bob, value(50)).is_err());
+        vm.set_sender(daxos);
+        assert!(vault.burn(bob, value(51)).is_err());
+        vault.burn(bob, value(50)).unwrap();
+        assert_eq!(vault.total_supply(), value(50));
+        assert_eq!(vault.balance_of(bob), U256::ZERO);
+
+        let logs = vm.get_emitted_logs();
+        let transfers: Vec<_> = logs
+            .iter()
+            .filter_map(|(topics, data)| {
+                Transfer::decode_raw_log(topics.iter().copied(), data, true).ok()
+            })
+            .map(|event| (event.from, event.to, event.value))
+            .collect();
+        assert_eq!(
+            transfers,
+            vec![
+                (Address::ZERO, alice, value(100)),
+                (alice, bob, value(30)),
+                (alice, bob, value(20)),
+                (bob, Address::ZERO, value(50)),
+            ]
+        );
+        let approvals = logs
+            .iter()
+            .filter(|(topics, _)| topics[0] == Approval::SIGNATURE_HASH)
+            .count();
+        assert_eq!(approvals, 1);
+    }
+
+    #[test]
+    fn test_submit_order() {
+        let vm = TestVM::default();
+        let mut vault = Vault::from(&vm);
+
+        let daxos = Address::from([0x01; 20]);
+        let devil = Address::from([0x0d; 20]);
+        let alice = Address::from([0x0a; 20]);
+        let bob = Address::from([0x0b; 20]);
+        let stop = b"Stop".to_vec();
+        let execute = |code: Vec<u128>| {
+            IDevil::executeCall {
+                code: Labels { data: code }.to_vec(),
+                num_registry: 0,
+            }
+            .abi_encode()
+        };
+
+        // Top bit of Vault address is cleared in namespace, as it marks
+        // arguments of stored programs
+        let vault_address = Address::from([0xff; 20]);
+        let namespace = namespace(vault_address.into_array());
+        assert_eq!(namespace, (u64::MAX as u128 >> 1) << 64);
+
+        vm.set_contract_address(vault_address);
+        vm.set_sender(daxos);
+        vault
+            .setup(daxos, devil, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+
+        // Only Daxos can submit orders
+        vm.set_sender(alice);
+        assert!(vault.submit_order(alice, U128::from(1000)).is_err());
+        assert!(vault.submit_sell_order(alice, U128::from(10)).is_err());
+        vm.set_sender(daxos);
+
+        // Index vectors took first 6 ids in setup, and first order of the
+        // user is created under newly allocated id...
+        assert_eq!(vault.get_assets().unwrap(), U128::from(namespace | 1));
+        assert_eq!(vault.get_accrued_fees().unwrap(), U128::from(namespace | 6));
+        let alice_order = namespace | 7;
+        let create = execute(create_order(alice_order, 1000));
+        vm.mock_call(devil, create.clone(), Err(stop.clone()));
+        assert_eq!(
+            vault.submit_order(alice, U128::from(1000)).unwrap_err(),
+            stop
+        );
+        assert!(vault.get_order(alice).unwrap().is_zero());
+
+        // TestVM does not revert storage of failed call, so id is released
+        vault.next_id.set(U128::from(6));
+        vm.mock_call(devil, create, Ok(Vec::new()));
+        assert_eq!(
+            vault.submit_order(alice, U128::from(1000)).unwrap(),
+            U128::from(alice_order)
+        );
+        assert_eq!(vault.get_order(alice).unwrap(), U128::from(alice_order));
+        assert_eq!(
+            vault.get_order_user(U128::from(alice_order)).unwrap(),
+            alice
+        );
+
+        // ...and next orders top it up
+        vm.mock_call(
+            devil,
+            execute(update_order(alice_order, 500)),
+            Err(stop.clone()),
+        );
+        assert_eq!(
+            vault.submit_order(alice, U128::from(500)).unwrap_err(),
+            stop
+        );
+        vm.mock_call(
+            devil,
+            execute(update_order(alice_order, 500)),
+            Ok(Vec::new()),
+        );
+        assert_eq!(
+            vault.submit_order(alice, U128::from(500)).unwrap(),
+            U128::from(alice_order)
+        );
+
+        // Each user and each sell order gets its own id
+        assert_eq!(
+            vault.submit_order(bob, U128::from(300)).unwrap(),
+            U128::from(namespace | 8)
+        );
+        assert_eq!(
+            vault.submit_sell_order(alice, U128::from(10)).unwrap(),
+            U128::from(namespace | 9)
+        );
+        vm.mock_call(
+            devil,
+            execute(update_order(namespace | 9, 5)),
+            Err(stop.clone()),
+        );
+        assert_eq!(
+            vault.submit_sell_order(alice, U128::from(5)).unwrap_err(),
+            stop
+        );
+        assert_eq!(vault.get_order(alice).unwrap(), U128::from(alice_order));
+    }
+
+    #[test]
+    fn test_order_queue() {
+        let vm = TestVM::default();
+        let mut vault = Vault::from(&vm);
+
+        let daxos = Address::from([0x01; 20]);
+        let devil = Address::from([0x0d; 20]);
+        let alice = Address::from([0x0a; 20]);
+        let bob = Address::from([0x0b; 20]);
+        let carol = Address::from([0x0c; 20]);
+        let stop = b"Stop".to_vec();
+        let get = |id: u128| IDevil::getCall { id }.abi_encode();
+        let found = |data: Vec<u8>| Ok(IDevil::getCall::abi_encode_returns(&(data,)));
+        let execute = |code: Vec<u128>| {
+            IDevil::executeCall {
+                code: Labels { data: code }.to_vec(),
+                num_registry: 0,
+            }
+            .abi_encode()
+        };
+
+        vm.set_contract_address(Address::from([0x0f; 20]));
+        vm.set_sender(daxos);
+        vault
+            .setup(daxos, devil, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+
+        let namespace = namespace([0x0f; 20]);
+        let alice_order: u128 = vault.submit_order(alice, U128::from(1000)).unwrap().to();
+        let bob_order: u128 = vault.submit_order(bob, U128::from(500)).unwrap().to();
+        let queue_id = namespace | 9;
+
+        // Only Daxos can manage the queue
+        vm.set_sender(alice);
+        assert!(vault.enqueue_order(alice).is_err());
+        assert!(vault.dequeue_order(alice).is_err());
+        assert!(vault.cancel_order(alice).is_err());
+        assert!(vault.clear_queue().is_err());
+
+        vm.set_sender(daxos);
+        assert_eq!(vault.enqueue_order(carol).unwrap_err(), b"No order");
+
+        // Queue is submitted with first order...
+        let submit = IDevil::submitCall {
+            id: queue_id,
+            data: Labels {
+                data: vec![alice_order],
+            }
+            .to_vec(),
+        }
+        .abi_encode();
+        vm.mock_static_call(devil, get(queue_id), Err(b"No data".to_vec()));
+        vm.mock_call(devil, submit.clone(), Err(stop.clone()));
+        assert_eq!(vault.enqueue_order(alice).unwrap_err(), stop);
+        vm.mock_call(devil, submit, Ok(Vec::new()));
+        vault.enqueue_order(alice).unwrap();
+        assert_eq!(vault.get_queue().unwrap(), U128::from(queue_id));
+
+        // ...and next orders are added to it by program
+        let queue = Labels {
+            data: vec![alice_order],
+        };
+        vm.mock_static_call(devil, get(queue_id), found(queue.to_vec()));
+        vm.mock_call(
+            devil,
+            execute(enqueue_order(queue_id, bob_order)),
+            Err(stop.clone()),
+        );
+        assert_eq!(vault.enqueue_order(bob).unwrap_err(), stop);
+
+        // Errors other than missing data are not mistaken for empty queue
+        vm.mock_static_call(devil, get(queue_id), Err(b"Paused".to_vec()));
+        assert_eq!(vault.enqueue_order(bob).unwrap_err(), b"Paused");
+        assert_eq!(vault.dequeue_order(bob).unwrap_err(), b"Paused");
+        assert_eq!(vault.clear_queue().unwrap_err(), b"Paused");
+
+        vm.mock_static_call(devil, get(queue_id), found(queue.to_vec()));
+        vm.mock_call(
+            devil,
+            execute(dequeue_order(queue_id, bob_order)),
+            Err(stop.clone()),
+        );
+        assert_eq!(vault.dequeue_order(bob).unwrap_err(), stop);
+
+        // Cancelled order has its remaining collateral zeroed and leaves the
+        // queue
+        let order = |x: u128| Vector {
+            data: vec![
+                Amount::from_u128_with_scale(x, 0),
+                Amount::ZERO,
+                Amount::ZERO,
+            ],
+        };
+        let update = IDevil::updateCall {
+            id: alice_order,
+            data: order(0).to_vec(),
+            expected_hash: B256::ZERO,
+        }
+        .abi_encode();
+        vm.mock_static_call(devil, get(alice_order), found(order(1000).to_vec()));
+        vm.mock_call(devil, update.clone(), Err(stop.clone()));
+        assert_eq!(vault.cancel_order(alice).unwrap_err(), stop);
+        vm.mock_call(devil, update, Ok(Vec::new()));
+        vm.mock_call(
+            devil,
+            execute(dequeue_order(queue_id, alice_order)),
+            Err(stop.clone()),
+        );
+        assert_eq!(vault.cancel_order(alice).unwrap_err(), stop);
+        vm.mock_call(
+            devil,
+            execute(dequeue_order(queue_id, alice_order)),
+            Ok(Vec::new()),
+        );
+        assert_eq!(
+            vault.cancel_order(alice).unwrap(),
+            Amount::from_u128_with_scale(1000, 0).to_u128()
+        );
+
+        vm.mock_static_call(devil, get(bob_order), Err(b"No data".to_vec()));
+        assert_eq!(vault.cancel_order(bob).unwrap_err(), b"No order");
+
+        // Queue is deleted when cleared, unless it is already gone
+        let delete = IDevil::deleteCall { id: queue_id }.abi_encode();
+        vm.mock_call(devil, delete, Err(stop.clone()));
+        assert_eq!(vault.clear_queue().unwrap_err(), stop);
+        vm.mock_static_call(devil, get(queue_id), Err(b"No data".to_vec()));
+        vault.clear_queue().unwrap();
+        vault.dequeue_order(bob).unwrap();
+    }
+
+    #[test]
+    fn test_submit_index() {
+        let vm = TestVM::default();
+        let mut vault = Vault::from(&vm);
+
+        let daxos = Address::from([0x01; 20]);
+        let devil = Address::from([0x0d; 20]);
+        let alice = Address::from([0x0a; 20]);
+        let stop = b"Stop".to_vec();
+        let amounts = |data: &[u128]| Vector {
+            data: data
+                .iter()
+                .map(|x| Amount::from_u128_with_scale(*x, 3))
+                .collect(),
+        };
+
+        vm.set_contract_address(Address::from([0x0f; 20]));
+        vm.set_sender(daxos);
+        vault
+            .setup(daxos, devil, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+
+        // Ids are allocated once, and they are not changed by setting up again
+        let namespace = namespace([0x0f; 20]);
+        let ids = [
+            vault.get_assets().unwrap(),
+            vault.get_weights().unwrap(),
+            vault.get_quote().unwrap(),
+            vault.get_fees().unwrap(),
+            vault.get_supply().unwrap(),
+            vault.get_accrued_fees().unwrap(),
+        ]
+        .map(|id| id.to::<u128>());
+        assert_eq!(ids, [1, 2, 3, 4, 5, 6].map(|id| namespace | id));
+        vault
+            .setup(daxos, devil, "Top 10 Index".into(), "TOP10".into())
+            .unwrap();
+        assert_eq!(vault.get_fees().unwrap(), U128::from(ids[3]));
+
+        let assets = Labels {
+            data: vec![51, 53, 54],
+        }
+        .to_vec();
+        let weights = amounts(&[100, 1000, 100_000]).to_vec();
+        let fees = amounts(&[5, 10, 0]).to_vec();
+
+        // Only Daxos can submit index and set fees
+        vm.set_sender(alice);
+        assert!(vault
+            .submit_index(assets.clone(), weights.clone(), fees.clone())
+            .is_err());
+        assert!(vault.set_fees(fees.clone()).is_err());
+        vm.set_sender(daxos);
+
+        // Each asset has weight, and there are three fees
+        assert_eq!(
+            vault
+                .submit_index(assets.clone(), amounts(&[100]).to_vec(), fees.clone())
+                .unwrap_err(),
+            b"Invalid weights"
+        );
+        assert_eq!(
+            vault
+                .submit_index(assets.clone(), weights.clone(), amounts(&[5]).to_vec())
+                .unwrap_err(),
+            b"Invalid fees"
+        );
+        assert_eq!(
+            vault.set_fees(amounts(&[5, 10]).to_vec()).unwrap_err(),
+            b"Invalid fees"
+        );
+
+        // Index vectors are submitted at once with zero supply and accrued fees
+        let submit = IDevil::submitManyCall {
+            ids: vec![ids[0], ids[1], ids[3], ids[4], ids[5]],
+            data: vec![
+                assets.clone(),
+                weights.clone(),
+                fees.clone(),
+                amounts(&[0]).to_vec(),
+                amounts(&[0, 0, 0]).to_vec(),
+            ],
+        }
+        .abi_encode();
+        vm.mock_call(devil, submit.clone(), Err(stop.clone()));
+        assert_eq!(
+            vault
+                .submit_index(assets.clone(), weights.clone(), fees.clone())
+                .unwrap_err(),
+            stop
+        );
+        vm.mock_call(devil, submit, Ok(Vec::new()));
+        vault.submit_index(assets, weights, fees).unwrap();
+
+        // Fees are updated in place
+        let fees = amounts(&[5, 10, 1]).to_vec();
+        let update = IDevil::updateCall {
+            id: ids[3],
+            data: fees.clone(),
+            expected_hash: B256::ZERO,
+        }
+        .abi_encode();
+        vm.mock_call(devil, update.clone(), Err(stop.clone()));
+        assert_eq!(vault.set_fees(fees.clone()).unwrap_err(), stop);
+        vm.mock_call(devil, update, Ok(Vec::new()));
+        vault.set_fees(fees).unwrap();
+    }
+}
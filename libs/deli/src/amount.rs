@@ -60,6 +60,96 @@ fn sqrt_u256(n: U256) -> Option<U256> {
     Some(current)
 }
 
+/// Scale of intermediate results in `exp`, `ln` and `pow`, i.e. 36 decimals,
+/// which is twice the precision of `Amount`.
+#[cfg(feature = "amount-exp")]
+const EXP_SCALE: U256 = const_from_u128(1_000_000_000_000_000_000_000_000_000_000_000_000);
+
+/// Natural logarithm of 2 in 36 decimals
+#[cfg(feature = "amount-exp")]
+const EXP_LN2: U256 = const_from_u128(693_147_180_559_945_309_417_232_121_458_176_568);
+
+#[cfg(feature = "amount-exp")]
+const fn const_from_u128(value: u128) -> U256 {
+    U256::from_limbs([value as u64, (value >> 64) as u64, 0, 0])
+}
+
+/// Exponent of `x` given in 36 decimals
+///
+/// We reduce `x = k ln 2 + r` where `0 <= r < ln 2`, so that:
+///  exp(x) = 2^k exp(r)
+///
+/// and Taylor series of `exp(r)` converges fast. When `negative` is set, we
+/// compute `exp(-x) = 1 / exp(x)`, which underflows to zero for large `x`.
+#[cfg(feature = "amount-exp")]
+fn exp_u256(x: U256, negative: bool) -> Option<U256> {
+    let k = x / EXP_LN2;
+    if k > convert_from_u128(120) {
+        // Result beyond 2^120 is out of range of Amount anyway
+        return if negative { Some(U256::ZERO) } else { None };
+    }
+    let k = k.to::<usize>();
+    let r = x - EXP_LN2 * convert_from_u128(k as u128);
+
+    let mut sum = EXP_SCALE;
+    let mut term = EXP_SCALE;
+    let mut n = U256::ONE;
+    while !term.is_zero() {
+        term = term.checked_mul(r)? / EXP_SCALE / n;
+        sum = sum.checked_add(term)?;
+        n += U256::ONE;
+    }
+
+    let result = sum << k;
+    if negative {
+        Some(EXP_SCALE.checked_mul(EXP_SCALE)? / result)
+    } else {
+        Some(result)
+    }
+}
+
+/// Natural logarithm of `x` given in 36 decimals
+///
+/// Returns magnitude and whether logarithm is negative, i.e. `x < 1`. We
+/// reduce `x = 2^k m` where `1 <= m < 2`, so that:
+///  ln(x) = k ln 2 + ln(m)
+///
+/// and then use series:
+///  ln(m) = 2 atanh(z) = 2 (z + z^3 / 3 + z^5 / 5 + ...), where z = (m - 1) / (m + 1)
+///
+/// which converges fast as `z < 1/3`.
+#[cfg(feature = "amount-exp")]
+fn ln_u256(x: U256) -> Option<(U256, bool)> {
+    if x.is_zero() {
+        return None;
+    }
+    if x < EXP_SCALE {
+        let (result, _) = ln_u256(EXP_SCALE.checked_mul(EXP_SCALE)? / x)?;
+        return Some((result, true));
+    }
+    let mut k = 0u128;
+    let mut m = x;
+    let two = EXP_SCALE << 1;
+    while m >= two {
+        m >>= 1;
+        k += 1;
+    }
+
+    let z = (m - EXP_SCALE).checked_mul(EXP_SCALE)? / (m + EXP_SCALE);
+    let z_squared = z.checked_mul(z)? / EXP_SCALE;
+    let mut sum = U256::ZERO;
+    let mut term = z;
+    let mut n = U256::ONE;
+    while !term.is_zero() {
+        sum += term / n;
+        term = term.checked_mul(z_squared)? / EXP_SCALE;
+        n += convert_from_u128(2);
+    }
+
+    let result = EXP_LN2 * convert_from_u128(k) + (sum << 1);
+    Some((result, false))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct Amount(pub u128);
 
@@ -70,6 +160,7 @@ impl Amount {
     pub const ONE: Amount = Amount(Self::SCALE);
     pub const TWO: Amount = Amount(2 * Self::SCALE);
     pub const FOUR: Amount = Amount(4 * Self::SCALE);
+    #[allow(clippy::inconsistent_digit_grouping)]
     pub const SCALE: u128 = 1_000_000_000__000_000_000;
     pub const SCALE_SQRT: u128 = 1_000_000_000;
     pub const DECIMALS: usize = 18;
@@ -113,6 +204,53 @@ impl Amount {
         Some(Self(try_convert_to_u128(result)?))
     }
 
+    /// Exponent `e^x`
+    ///
+    /// Computed with 36 decimals, so that relative error is below 1e-30
+    /// before result is truncated to 18 decimals. Returns `None` if result
+    /// does not fit `Amount`, i.e. for `x` above approx. 47.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_exp(self) -> Option<Self> {
+        let x = self.to_u256() * Self::u256_scale();
+        let result = exp_u256(x, false)? / Self::u256_scale();
+        Some(Self(try_convert_to_u128(result)?))
+    }
+
+    /// Natural logarithm `ln(x)`
+    ///
+    /// Computed with 36 decimals, so that absolute error is below 1e-30
+    /// before result is truncated to 18 decimals. As `Amount` is unsigned,
+    /// returns `None` for `x < 1`, where logarithm is negative, and for zero.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_ln(self) -> Option<Self> {
+        let (result, negative) = ln_u256(self.to_u256() * Self::u256_scale())?;
+        if negative && !result.is_zero() {
+            return None;
+        }
+        Some(Self(try_convert_to_u128(result / Self::u256_scale())?))
+    }
+
+    /// Power `x^y` with fractional exponent
+    ///
+    /// Computed as `exp(y ln(x))` with 36 decimals, so that relative error is
+    /// below `y` × 1e-30 before result is truncated to 18 decimals, i.e. for
+    /// any practical exponent result is exact up to last decimal. Works for
+    /// `x < 1` too, and `0^y` is zero except for `0^0`, which is one. Returns
+    /// `None` if result does not fit `Amount`.
+    #[cfg(feature = "amount-exp")]
+    pub fn checked_pow(self, exponent: Self) -> Option<Self> {
+        if exponent.is_not() {
+            return Some(Self::ONE);
+        }
+        if self.is_not() {
+            return Some(Self::ZERO);
+        }
+        let (ln, negative) = ln_u256(self.to_u256() * Self::u256_scale())?;
+        let x = ln.checked_mul(exponent.to_u256())? / Self::u256_scale();
+        let result = exp_u256(x, negative)? / Self::u256_scale();
+        Some(Self(try_convert_to_u128(result)?))
+    }
+
     #[inline]
     pub fn is_less_than(&self, other: &Self) -> bool {
         self.0 < other.0
@@ -241,6 +379,7 @@ impl core::fmt::Display for Amount {
 }
 
 #[cfg(test)]
+#[allow(clippy::zero_prefixed_literal)]
 mod test {
     use super::*;
 
@@ -311,4 +450,78 @@ mod test {
             Amount::from_u128_with_scale(2, 1).is_less_than(&Amount::from_u128_with_scale(1, 0))
         );
     }
+
+    /// Assert `value` differs from `expected`, which is exact result truncated
+    /// to 18 decimals, by no more than `relative_error` × 1e-30 of `expected`
+    /// plus one unit of last decimal lost in truncation
+    #[cfg(feature = "amount-exp")]
+    fn assert_error_below(value: Amount, expected: u128, relative_error: u128) {
+        let tolerance = expected / 10u128.pow(30) * relative_error + 1;
+        let error = value.0.abs_diff(expected);
+        assert!(error <= tolerance, "{} != {}", value.0, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "amount-exp")]
+    fn test_exp_ln_pow() {
+        let amount = |x: u128, scale: u8| Amount::from_u128_with_scale(x, scale);
+
+        do_test_amount(Amount::ZERO.checked_exp().unwrap(), Amount::ONE);
+        do_test_amount(Amount::ONE.checked_ln().unwrap(), Amount::ZERO);
+        do_test_amount(
+            Amount::ONE.checked_exp().unwrap(),
+            Amount(2_718281828459045235),
+        );
+        do_test_amount(
+            Amount::TWO.checked_ln().unwrap(),
+            Amount(0_693147180559945309),
+        );
+        do_test_amount(
+            amount(4, 0).checked_pow(amount(0_5, 1)).unwrap(),
+            Amount::TWO,
+        );
+        do_test_amount(
+            amount(0_5, 1).checked_pow(Amount::TWO).unwrap(),
+            amount(0_25, 2),
+        );
+        do_test_amount(Amount::ZERO.checked_pow(Amount::ZERO).unwrap(), Amount::ONE);
+        do_test_amount(Amount::ZERO.checked_pow(Amount::TWO).unwrap(), Amount::ZERO);
+
+        // Expected values computed with 80 digits and truncated to 18 decimals
+        for (x, scale, expected) in [
+            (0_001, 3, 1_001000500166708341),
+            (0_5, 1, 1_648721270700128146),
+            (1, 0, 2_718281828459045235),
+            (10, 0, 22026_465794806716516957),
+            (47, 0, 258131288619006739623285800215273380431),
+        ] {
+            assert_error_below(amount(x, scale).checked_exp().unwrap(), expected, 1);
+        }
+        // Absolute error of logarithm is below 1e-30
+        for (x, scale, expected) in [
+            (1_001, 3, 0_000999500333083533),
+            (1_5, 1, 0_405465108108164381),
+            (10, 0, 2_302585092994045684),
+            (1_000_000, 0, 13_815510557964274104),
+        ] {
+            assert_error_below(amount(x, scale).checked_ln().unwrap(), expected, 0);
+        }
+        // Relative error of power is below `y` × 1e-30
+        for (x, y, expected) in [
+            (90, 100, 0_348678440100000000),
+            (101, 3650, 37_783434332887158877),
+            (200, 15, 2_828427124746190097),
+            (99, 25, 0_975187187108198217),
+        ] {
+            let (x, y) = (amount(x, 2), amount(y, 1));
+            let relative_error = y.0.div_ceil(Amount::SCALE);
+            assert_error_below(x.checked_pow(y).unwrap(), expected, relative_error);
+        }
+
+        // Out of range of Amount
+        assert!(amount(48, 0).checked_exp().is_none());
+        assert!(amount(0_5, 1).checked_ln().is_none());
+        assert!(Amount::ZERO.checked_ln().is_none());
+        assert!(amount(1000, 0).checked_pow(amount(10, 0)).is_none());
+    }
 }
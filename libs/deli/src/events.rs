@@ -0,0 +1,37 @@
+//!
+//! Events emitted by DeVIL contract
+//!
+//! Shared by DeVIL, which emits them, and by off-chain services decoding them.
+//!
+
+use alloy_sol_types::sol;
+
+sol! {
+    /// Vector (or Labels) blob was stored under `id`
+    ///
+    /// Emitted on every mutation of stored data, so that indexers can follow
+    /// vector state without polling. The `hash` is keccak256 of the blob, and
+    /// `length` is the blob length in bytes.
+    event VectorStored(uint128 indexed id, bytes32 hash, uint128 length);
+
+    /// Vector (or Labels) blob under `id` was deleted
+    event VectorDeleted(uint128 indexed id);
+
+    /// VIL program was executed successfully
+    ///
+    /// The `program_hash` is keccak256 of the program code, and `gas_used` is
+    /// the gas consumed by program execution including any vector stores.
+    event ProgramExecuted(bytes32 indexed program_hash, address indexed caller, uint64 gas_used);
+
+    /// Contract was granted or revoked operator access
+    ///
+    /// Operators (e.g. Vault and Market contracts) may store, load and delete
+    /// vectors, and execute programs, while only the owner installs programs.
+    event OperatorSet(address indexed operator, bool enabled);
+
+    /// Stored procedure was installed as new version of program `name`
+    ///
+    /// The code of the procedure is stored under `id`, and it is also what `B`
+    /// and `FOLD` instructions refer to as `prg_id`.
+    event ProgramInstalled(bytes32 indexed name, uint128 indexed id, uint128 version, uint128 num_inputs, uint128 num_outputs, uint128 num_registers);
+}
@@ -6,6 +6,12 @@ pub struct Labels {
     pub data: Vec<u128>,
 }
 
+impl Default for Labels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Labels {
     pub fn new() -> Self {
         Self { data: Vec::new() }
@@ -55,6 +61,98 @@ impl Labels {
         }
         output
     }
+
+    /// Tell whether labels are in strictly increasing order, which is what
+    /// set operations and joins expect.
+    pub fn is_sorted(&self) -> bool {
+        self.data.windows(2).all(|w| w[0] < w[1])
+    }
+
+    /// Position of the label, assuming labels are sorted
+    pub fn position(&self, label: u128) -> Option<usize> {
+        self.data.binary_search(&label).ok()
+    }
+
+    /// Labels present in either of sorted sets
+    ///
+    /// Returns union together with index maps, i.e. for each label of this
+    /// set and of the other set its position in the union, which is what is
+    /// needed to expand vectors into the union space. Duplicate labels map to
+    /// the same position.
+    pub fn union(&self, other: &Labels) -> (Labels, Vec<usize>, Vec<usize>) {
+        let mut result: Vec<u128> = Vec::new();
+        let mut map_a = Vec::with_capacity(self.data.len());
+        let mut map_b = Vec::with_capacity(other.data.len());
+        let (mut i, mut j) = (0, 0);
+        loop {
+            let a = self.data.get(i).copied();
+            let b = other.data.get(j).copied();
+            let label = match (a, b) {
+                (Some(a), Some(b)) => a.min(b),
+                (Some(a), None) => a,
+                (None, Some(b)) => b,
+                (None, None) => break,
+            };
+            if result.last() != Some(&label) {
+                result.push(label);
+            }
+            let pos = result.len() - 1;
+            if a == Some(label) {
+                map_a.push(pos);
+                i += 1;
+            }
+            if b == Some(label) {
+                map_b.push(pos);
+                j += 1;
+            }
+        }
+        (Labels { data: result }, map_a, map_b)
+    }
+
+    /// Labels present in both sorted sets
+    pub fn intersection(&self, other: &Labels) -> Labels {
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.data.len() && j < other.data.len() {
+            let (a, b) = (self.data[i], other.data[j]);
+            if a < b {
+                i += 1;
+            } else if b < a {
+                j += 1;
+            } else {
+                result.push(a);
+                i += 1;
+                j += 1;
+            }
+        }
+        Labels { data: result }
+    }
+
+    /// Labels present in this sorted set, but not in the other
+    pub fn difference(&self, other: &Labels) -> Labels {
+        let mut result = Vec::new();
+        let mut j = 0;
+        for &a in &self.data {
+            while j < other.data.len() && other.data[j] < a {
+                j += 1;
+            }
+            if j == other.data.len() || other.data[j] != a {
+                result.push(a);
+            }
+        }
+        Labels { data: result }
+    }
+
+    /// Sort labels in place
+    ///
+    /// Returns permutation, i.e. for each position in sorted labels the
+    /// position it had before, which is to be applied to accompanying vectors.
+    pub fn sort(&mut self) -> Vec<usize> {
+        let mut permutation: Vec<usize> = (0..self.data.len()).collect();
+        permutation.sort_by_key(|&i| self.data[i]);
+        self.data = permutation.iter().map(|&i| self.data[i]).collect();
+        permutation
+    }
 }
 
 #[macro_export]
@@ -78,3 +176,80 @@ impl core::fmt::Display for Labels {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::{collections::BTreeSet, vec};
+    use crate::testing::Rng;
+
+    /// Simple xorshift generator, so that tests are reproducible without
+    /// extra dependencies
+    fn random_labels(rng: &mut Rng) -> BTreeSet<u128> {
+        let len = rng.next_u64() % 8;
+        (0..len).map(|_| (rng.next_u64() % 16) as u128).collect()
+    }
+
+    fn to_labels(set: &BTreeSet<u128>) -> Labels {
+        Labels {
+            data: set.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..1000 {
+            let (set_a, set_b) = (random_labels(&mut rng), random_labels(&mut rng));
+            let (a, b) = (to_labels(&set_a), to_labels(&set_b));
+            assert!(a.is_sorted());
+
+            let (union, map_a, map_b) = a.union(&b);
+            assert_eq!(union.data, to_labels(&(&set_a | &set_b)).data);
+            for (i, &pos) in map_a.iter().enumerate() {
+                assert_eq!(union.data[pos], a.data[i]);
+            }
+            for (j, &pos) in map_b.iter().enumerate() {
+                assert_eq!(union.data[pos], b.data[j]);
+            }
+
+            assert_eq!(
+                a.intersection(&b).data,
+                to_labels(&(&set_a & &set_b)).data
+            );
+            assert_eq!(a.difference(&b).data, to_labels(&(&set_a - &set_b)).data);
+
+            for label in 0..16 {
+                assert_eq!(
+                    a.position(label),
+                    set_a.iter().position(|&x| x == label)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_union_duplicates() {
+        let a = Labels {
+            data: vec![1, 3, 3, 5],
+        };
+        let b = Labels {
+            data: vec![3, 5, 7, 9],
+        };
+        let (union, map_a, map_b) = a.union(&b);
+        assert_eq!(union.data, vec![1, 3, 5, 7, 9]);
+        assert_eq!(map_a, vec![0, 1, 1, 2]);
+        assert_eq!(map_b, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut a = Labels {
+            data: vec![5, 1, 3],
+        };
+        assert!(!a.is_sorted());
+        assert_eq!(a.sort(), vec![1, 2, 0]);
+        assert_eq!(a.data, vec![1, 3, 5]);
+        assert!(a.is_sorted());
+    }
+}
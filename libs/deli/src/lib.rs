@@ -5,9 +5,13 @@ extern crate alloc;
 
 pub mod amount;
 pub mod asset;
+pub mod events;
 pub mod labels;
 pub mod log;
 pub mod math;
+pub mod matrix;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 pub mod uint;
 pub mod vector;
 pub mod vis;
@@ -21,3 +21,18 @@ pub fn solve_quadratic(a: Amount, b: Amount, negative_c: Amount) -> Option<Amoun
     let val = num.checked_div(den)?;
     Some(val)
 }
+
+/// Dot product of two equally long slices:
+///  A . B = A[0] B[0] + ... + A[n] B[n]
+///
+/// Returns `None` on overflow or when lengths differ.
+pub fn checked_dot(a: &[Amount], b: &[Amount]) -> Option<Amount> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let mut sum = Amount::ZERO;
+    for (x, y) in a.iter().zip(b) {
+        sum = sum.checked_add(x.checked_mul(*y)?)?;
+    }
+    Some(sum)
+}
@@ -0,0 +1,205 @@
+use alloc::vec::Vec;
+
+use crate::{amount::Amount, math::checked_dot, vector::Vector};
+
+/// Matrix stored as row-major vector of components
+///
+/// Shape is given by number of columns, and number of rows is derived from
+/// length of the data, e.g. index baskets form matrix of indexes × asset
+/// weights, where each row is the weights vector of one index.
+pub struct Matrix {
+    pub num_cols: usize,
+    pub data: Vec<Amount>,
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Matrix {
+    pub fn new() -> Self {
+        Self {
+            num_cols: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// Build matrix from rows, which must all be of same length
+    pub fn from_rows(rows: Vec<Vector>) -> Option<Self> {
+        let num_cols = rows.first().map_or(0, |r| r.data.len());
+        let mut data = Vec::with_capacity(num_cols * rows.len());
+        for row in rows {
+            if row.data.len() != num_cols {
+                return None;
+            }
+            data.extend(row.data);
+        }
+        Some(Self { num_cols, data })
+    }
+
+    pub fn into_rows(self) -> Vec<Vector> {
+        if self.num_cols == 0 {
+            return Vec::new();
+        }
+        self.data
+            .chunks(self.num_cols)
+            .map(|r| Vector { data: r.to_vec() })
+            .collect()
+    }
+
+    pub fn num_rows(&self) -> usize {
+        if self.num_cols == 0 {
+            0
+        } else {
+            self.data.len() / self.num_cols
+        }
+    }
+
+    pub fn row(&self, row: usize) -> Option<Vector> {
+        if row >= self.num_rows() {
+            return None;
+        }
+        let start = row * self.num_cols;
+        Some(Vector {
+            data: self.data[start..start + self.num_cols].to_vec(),
+        })
+    }
+
+    pub fn col(&self, col: usize) -> Option<Vector> {
+        if col >= self.num_cols {
+            return None;
+        }
+        Some(Vector {
+            data: self
+                .data
+                .iter()
+                .skip(col)
+                .step_by(self.num_cols)
+                .copied()
+                .collect(),
+        })
+    }
+
+    /// Matrix-vector product, i.e. dot product of each row with `rhs`
+    ///
+    /// Returns `None` on overflow or when length of `rhs` is not matching
+    /// number of columns.
+    pub fn checked_mul_vector(&self, rhs: &Vector) -> Option<Vector> {
+        if rhs.data.len() != self.num_cols {
+            return None;
+        }
+        let mut res = Vector::new();
+        if self.num_cols == 0 {
+            return Some(res);
+        }
+        for row in self.data.chunks(self.num_cols) {
+            res.data.push(checked_dot(row, &rhs.data)?);
+        }
+        Some(res)
+    }
+
+    /// Decode matrix from blob, which is number of columns followed by
+    /// components in row-major order
+    ///
+    /// Returns `None` when blob is not a matrix, e.g. it holds Vector or Labels
+    /// whose length is not matching any number of columns.
+    #[cfg(feature = "vec-u8")]
+    pub fn try_from_vec(data: Vec<u8>) -> Option<Self> {
+        let header_len = size_of::<u128>();
+        if data.len() < header_len || 0 != (data.len() - header_len) % size_of::<Amount>() {
+            return None;
+        }
+        let num_cols = usize::try_from(crate::uint::read_u128(&data)).ok()?;
+        let vector = Vector::from_vec(data[header_len..].to_vec());
+        if num_cols == 0 && !vector.data.is_empty()
+            || num_cols != 0 && 0 != vector.data.len() % num_cols
+        {
+            return None;
+        }
+        Some(Self {
+            num_cols,
+            data: vector.data,
+        })
+    }
+
+    /// Decode matrix from blob, which must be valid (see `try_from_vec()`)
+    #[cfg(feature = "vec-u8")]
+    pub fn from_vec(data: Vec<u8>) -> Self {
+        Self::try_from_vec(data).expect("Incorrect data length")
+    }
+
+    #[cfg(feature = "vec-u8")]
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        crate::uint::write_u128(self.num_cols as u128, &mut output);
+        for val in &self.data {
+            val.to_vec(&mut output);
+        }
+        output
+    }
+}
+
+#[cfg(any(not(feature = "stylus"), feature = "debug"))]
+impl core::fmt::Display for Matrix {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let max_scale_len = f.precision().unwrap_or(18).min(18);
+        let vector = Vector {
+            data: self.data.clone(),
+        };
+        // Vector displays as matrix when given number of columns as width
+        write!(
+            f,
+            "{:n_cols$.max_scale_len$}",
+            vector,
+            n_cols = self.num_cols,
+            max_scale_len = max_scale_len
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    fn amounts(values: &[u128]) -> Vector {
+        Vector {
+            data: values
+                .iter()
+                .map(|&x| Amount::from_u128_with_scale(x, 0))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_matrix() {
+        let matrix = Matrix::from_rows(vec![amounts(&[1, 2, 3]), amounts(&[4, 5, 6])]).unwrap();
+        assert_eq!(matrix.num_rows(), 2);
+        assert_eq!(matrix.num_cols, 3);
+        assert_eq!(matrix.row(1).unwrap().data, amounts(&[4, 5, 6]).data);
+        assert_eq!(matrix.col(1).unwrap().data, amounts(&[2, 5]).data);
+        assert!(matrix.row(2).is_none());
+        assert!(matrix.col(3).is_none());
+
+        let product = matrix.checked_mul_vector(&amounts(&[1, 0, 2])).unwrap();
+        assert_eq!(product.data, amounts(&[7, 16]).data);
+        assert!(matrix.checked_mul_vector(&amounts(&[1, 0])).is_none());
+
+        let decoded = Matrix::from_vec(matrix.to_vec());
+        assert_eq!(decoded.num_cols, 3);
+        assert_eq!(decoded.data, matrix.data);
+
+        // Blobs of other types are not matrices
+        assert!(Matrix::try_from_vec(vec![0; 8]).is_none());
+        assert!(Matrix::try_from_vec(amounts(&[3, 1, 2, 3, 4]).to_vec()).is_none());
+        assert!(Matrix::try_from_vec(amounts(&[2, 1, 2, 3]).to_vec()).is_none());
+
+        let rows = decoded.into_rows();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].data, amounts(&[1, 2, 3]).data);
+
+        assert!(Matrix::from_rows(vec![amounts(&[1, 2]), amounts(&[3])]).is_none());
+    }
+}
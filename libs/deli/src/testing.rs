@@ -0,0 +1,17 @@
+//! Test utilities
+//!
+//! Shared by unit tests of this crate, and by tests of contracts, which enable
+//! `test-utils` feature.
+
+/// Simple xorshift generator, so that property tests are reproducible without
+/// extra dependencies
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
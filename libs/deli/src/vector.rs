@@ -6,6 +6,12 @@ pub struct Vector {
     pub data: Vec<Amount>,
 }
 
+impl Default for Vector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Vector {
     pub fn new() -> Self {
         Self { data: Vec::new() }
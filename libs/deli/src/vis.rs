@@ -1,17 +1,51 @@
 // Vector Instruction Set (VIS) for Vector IL (VIL) Virtual Machine
 
-// 1. Data Loading & Stack Access (10-14)
+// Parametric Storage Ids
+//
+// Stored programs can be executed with arguments. Any <label_id>, <vector_id>
+// or <prg_id> (including Label operands used by indirect LDLI, LDVI, STLI,
+// STVI) with ARG_FLAG set refers to an argument, i.e. `LDV arg(1)` loads
+// Vector whose id was supplied as second argument. Arguments are also bound to
+// registers (R0-Rn) as Scalar values.
+pub const ARG_FLAG: u128 = 1 << 127;
+
+pub const fn arg(index: u128) -> u128 {
+    ARG_FLAG | index
+}
+
+// Operator Namespaces
+//
+// Operators (e.g. Vaults) may only write ids within their own namespace, i.e.
+// ids whose upper 64 bits are lower 64 bits of operator address with top bit
+// cleared (as it is ARG_FLAG). Owner may write any id.
+pub const NAMESPACE_MASK: u128 = u128::MAX << 64;
+
+pub const fn namespace(address: [u8; 20]) -> u128 {
+    let mut result = 0u128;
+    let mut i = 12;
+    while i < 20 {
+        result = (result << 8) | address[i] as u128;
+        i += 1;
+    }
+    (result & (u64::MAX >> 1) as u128) << 64
+}
+
+// 1. Data Loading & Stack Access (10-17)
 pub const OP_LDL: u128 = 10; //   LDL <label_id>                ; no stack args ; result = [TOS: Labels]; Load Labels object from VIO by ID. Pushes on TOS.
 pub const OP_LDV: u128 = 11; //   LDV <vector_id>               ; no stack args ; result = [TOS: Vector]; Load Vector object from VIO by ID. Pushes on TOS.
 
 pub const OP_LDD: u128 = 13; //   LDD <pos>                     ; stack args = [TOS - pos] ; result = [TOS]; Load Duplicate (copy) of stack operand at [T-pos]. Pushes on TOS.
 pub const OP_LDR: u128 = 14; //   LDR <reg>                     ; no stack args ; result = [TOS - pos] ; Load value from Registry (R0-Rn). Pushes on TOS.
 pub const OP_LDM: u128 = 15; //   LDM <reg>                     ; no stack args ; result = [TOS - pos] ; Load value moving it out of Registry (R0-Rn). Value is removed from registry. Pushes on TOS.
+pub const OP_LDLI: u128 = 16; //  LDLI                          ; stack args = [TOS: Label] ; result = [TOS: Labels]; Load Labels object from VIO by ID taken from Label on TOS (indirect). Consumes TOS.
+pub const OP_LDVI: u128 = 17; //  LDVI                          ; stack args = [TOS: Label] ; result = [TOS: Vector]; Load Vector object from VIO by ID taken from Label on TOS (indirect). Consumes TOS.
 
-// 2. Data Storage & Register Access (20-23)
+// 2. Data Storage & Register Access (20-25)
 pub const OP_STL: u128 = 20; //   STL <label_id>                ; stack args = [TOS: Labels] ; Store Labels object into VIO. Consumes TOS.
 pub const OP_STV: u128 = 21; //   STV <vector_id>               ; stack args = [TOS: Vector] ; Store Vector object into VIO. Consumes TOS.
 pub const OP_STR: u128 = 23; //   STR <reg>                     ; stack args = [TOS - pos] ; stack unchanged, result in registry[reg]; Store into Registry (R0-Rn). Consumes TOS.
+pub const OP_STLI: u128 = 24; //  STLI                          ; stack args = [TOS - 1: Labels, TOS: Label] ; Store Labels object into VIO by ID taken from Label on TOS (indirect). Consumes both operands.
+pub const OP_STVI: u128 = 25; //  STVI                          ; stack args = [TOS - 1: Vector, TOS: Label] ; Store Vector object into VIO by ID taken from Label on TOS (indirect). Consumes both operands.
 
 // 3. Data Structure Manipulation (30-35)
 pub const OP_PKV: u128 = 30; //   PKV <count>                   ; stack args = [TOS - count, ..., TOS: Scalar] ; result [TOS]; Pack `count` values from stack into a new Vector. Consumes `count` operands from TOS, and replaces them with Vector.
@@ -22,29 +56,39 @@ pub const OP_VPOP: u128 = 34; //  VPOP                          ; stack args = [
 pub const OP_T: u128 = 35; //     T <count>                     ; stack args = [TOS - count, ..., TOS: Vector[0..len]] ; result = [TOS - len, ..., TOS: Vector[0..count]] ; Transpose `count` vectors on stack [V1, V2] -> [T1, T2]. In-place updates `count` operands from TOS by performing transform.
 
 // 4. Labels Manipulation (40-46)
-pub const OP_LUNION: u128 = 40; // LUNION <pos>                 ; stack args = [TOS - pos, TOS] ; result = [TOS] ; Union of two Labels operands (TOS and T-pos). Pushes on TOS.
+pub const OP_LUNION: u128 = 40; // LUNION <pos>                 ; stack args = [TOS - pos, TOS] ; result = [TOS] ; Union of two sorted Labels operands (TOS and T-pos), merged in sorted order without duplicates. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_LPUSH: u128 = 41; //  LPUSH <immediate (label)>    ; stack args = [TOS: Labels[0..len]] ; result = [TOS:Labels[0..len + 1]] ; Push a label value onto the Labels object (TOS). In-place updates Labels on TOS, appending new component at the end.
 pub const OP_LPOP: u128 = 42; //   LPOP                         ; stack args = [TOS: Labels[0..len]] ; result = [TOS: Lables[0..len - 1]] ; Pop a label value from the Labels object (TOS). In-place updates Labels on TOS, removing last component.
 pub const OP_JUPD: u128 = 43; //   JUPD <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B, TOS: Vector: 'A] ; result = [TOS: 'A filtered mapped 'LB to 'LA]; Update using Labels. Expands vector at [TOS - pos_B] using labels at [TOS - lab_B] to match labels of TOS at [TOS - lab_A]. In-place updates TOS. Consumes TOS.
 pub const OP_JADD: u128 = 44; //   JADD <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B, TOS: Vector: 'A] ; result = [TOS: 'A expaned w/ 0 mapped 'LB to 'LA]; Add using Labels. Expands vector at [TOS - pos_B] using labels at [TOS - lab_B] to match labels of TOS at [TOS - lab_A]. In-place updates TOS. Consumes TOS.
 pub const OP_JFLT: u128 = 45; //   JFLT <lab_A> <lab_B>         ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS: Vector: 'A] ; result = [TOS: 'A filtered mapped 'LB to 'LA]; Filter using Labels. Expands vector at [TOS-1] using labels at [T-lab_B] to match labels of TOS at [T-lab_A]. In-place updates TOS. Does not consume other operands.
+pub const OP_JUNION: u128 = 46; // JUNION <pos_B> <lab_A> <lab_B> ; stack args = [TOS - lab_A: 'LA, TOS - lab_B: Labels 'LB, TOS - pos_B: Vector 'B, TOS: Vector: 'A] ; result = [TOS - pos_B - 1: 'B expanded, TOS - 1: 'A expanded, TOS: Labels 'LA | 'LB]; Expanding (outer) join. Expands both vectors with zeros to match union of their labels, unlike JUPD/JADD, which require 'LB to be subset of 'LA. In-place updates both vectors, and pushes union Labels on TOS.
 
-// 5. Arithmetic & Core Math (50-55)
+// 5. Arithmetic & Core Math (50-58)
 pub const OP_ADD: u128 = 50; //    ADD <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Add TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_SUB: u128 = 51; //    SUB <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Subtract TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_SSB: u128 = 52; //    SSB <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Saturating subtract TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_MUL: u128 = 53; //    MUL <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Multiply TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_DIV: u128 = 54; //    DIV <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Divide TOS by operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_SQRT: u128 = 55; //   SQRT                         ; stack args = [TOS: Vector|Scalar]; result = [TOS] ; Square root of TOS (scalar or component-wise vector). Works with vectors and scalars. In-place updates operand on TOS.
+pub const OP_EXP: u128 = 56; //    EXP                          ; stack args = [TOS: Vector|Scalar]; result = [TOS] ; Exponent e^TOS (scalar or component-wise vector). Fails with MathOverflow if result is out of range. In-place updates operand on TOS.
+pub const OP_LN: u128 = 57; //     LN                           ; stack args = [TOS: Vector|Scalar]; result = [TOS] ; Natural logarithm of TOS (scalar or component-wise vector). Fails with MathOverflow for values below 1, as logarithm would be negative. In-place updates operand on TOS.
+pub const OP_POW: u128 = 58; //    POW <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS] ; Raise TOS to the power of operand at [T-pos] (fractional exponent). Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 
-// 6. Logic & Comparison (60-61)
+// 6. Logic & Comparison (60-65)
 pub const OP_MIN: u128 = 60; //    MIN <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Min between TOS and operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
 pub const OP_MAX: u128 = 61; //    MAX <pos>                    ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Max between TOS and operand at [T-pos]. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_GT: u128 = 62; //     GT <pos>                     ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Mask of TOS greater than operand at [T-pos], i.e. 1 where true and 0 otherwise. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_LT: u128 = 63; //     LT <pos>                     ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Mask of TOS less than operand at [T-pos], i.e. 1 where true and 0 otherwise. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_EQ: u128 = 64; //     EQ <pos>                     ; stack args = [TOS - pos, TOS: Vector|Scalar] ; result = [TOS: Vector|Scalar] ; Mask of TOS equal to operand at [T-pos], i.e. 1 where true and 0 otherwise. Works with vectors and scalars. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_SELECT: u128 = 65; // SELECT <pos_A> <pos_B>       ; stack args = [TOS - pos_A: 'A, TOS - pos_B: 'B, TOS: Vector|Scalar: 'Mask] ; result = [TOS: Vector|Scalar] ; Select components of 'A where 'Mask is non-zero, and of 'B where 'Mask is zero. Operands 'A and 'B are Vectors aligned with 'Mask or Scalars. In-place updates operand on TOS. Does not consume the other operands.
 
-// 7. Vector Aggregation (70-72)
+// 7. Vector Aggregation (70-74)
 pub const OP_VSUM: u128 = 70; //   VSUM                         ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Sum of all vector components. Pushes on TOS. Does not consume the operand.
 pub const OP_VMIN: u128 = 71; //   VMIN                         ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Minimum value found within vector components. Pushes on TOS. Does not consume the operand.
 pub const OP_VMAX: u128 = 72; //   VMAX                         ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Maximum value found within vector components. Pushes on TOS. Does not consume the operand.
+pub const OP_CUMSUM: u128 = 73; // CUMSUM                       ; stack args = [TOS: Vector] ; result = [TOS: Vector] ; Cumulative sum of vector components, i.e. i-th component becomes sum of components 0..=i. In-place updates operand on TOS.
+pub const OP_VPROD: u128 = 74; //  VPROD                        ; stack args = [TOS: Vector] ; result = [TOS: Scalar] ; Product of all vector components. Replaces operand on TOS.
 
 // 8. Immediate Values & Vector Creation (80-83)
 pub const OP_IMMS: u128 = 80; //   IMMS <immediate (scalar)>    ; no stack args ; result = [TOS: Scalar] ; Push immediate Scalar value on stack
@@ -55,5 +99,76 @@ pub const OP_ONES: u128 = 83; //   ONES <pos>                   ; stack args = [
 // 9. Stack Control & Program Flow (90-94)
 pub const OP_POPN: u128 = 90; //   POPN <count>                 ; stack args = ['B..., TOS - count, ..., TOS]; result = ['B...] ; Pop 'n' values from the stack
 pub const OP_SWAP: u128 = 91; //   SWAP <pos>                   ; stack args = [TOS - pos: 'A, TOS: 'B] ; result = [TOS - pos: 'B, TOS: 'A]; Swap TOS with operand at [T-n]
-pub const OP_B: u128 = 92; //      B <prg_id> <N> <M> <R>       ; stack args = [TOS - N] ; result = [TOS - M] ; Call sub-routine stored as Lables at `prg_id`, supplying `N` inputs and taking `M` outputs from stack. `N` inputs are consumed from stack. `M` outputs are moved from sub-routine's TOS to caller's TOS.
+pub const OP_B: u128 = 92; //      B <prg_id> <N> <M> <R>       ; stack args = [TOS - N] ; result = [TOS - M] ; Call sub-routine stored as Lables at `prg_id`, supplying `N` inputs and taking `M` outputs from stack. `N` inputs are consumed from stack. `M` outputs are moved from sub-routine's TOS to caller's TOS. If sub-routine is registered, then `N`, `M` must match its declared arity, and `R` must not be less than declared.
 pub const OP_FOLD: u128 = 93; //   FOLD <prg_id> <N> <M> <R>    ; stack args = [(TOS - N - 1, ..., TOS - 1): 'A..., TOS: 'X] ; result = [TOS - M, ..., TOS] ; first iteration = [(TOS - N - 1, ..., TOS - 1): 'A..., TOS: 'X[1]] ; i-th iteration = ['R..., TOS: 'X[i]], where 'R... stack resulting from previous iteration; Fold (iterate) over vector/label operands. Same as `B` except sub-routine is called repeatedly over components of Vector at TOS.
+
+// 10. Matrix Operations (100-107)
+pub const OP_PKM: u128 = 100; //   PKM <count>                  ; stack args = [TOS - count, ..., TOS: Vector] ; result [TOS: Matrix]; Pack `count` equally long Vectors from stack into rows of a new Matrix. Consumes `count` operands from TOS, and replaces them with Matrix.
+pub const OP_UNPKM: u128 = 101; // UNPKM                        ; stack args = [TOS: Matrix]; result [TOS - rows, ..., TOS: Vector] ; Unpack rows of Matrix onto the stack as Vectors. Consumes TOS, and replaces with its rows.
+pub const OP_MATVEC: u128 = 102; // MATVEC <pos>                ; stack args = [TOS - pos: Matrix, TOS: Vector] ; result = [TOS: Vector] ; Multiply Matrix at [T-pos] by Vector on TOS, i.e. dot product of each row with TOS. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_DOT: u128 = 103; //   DOT <pos>                    ; stack args = [TOS - pos: Vector, TOS: Vector] ; result = [TOS: Scalar] ; Dot product of TOS and Vector at [T-pos] (fused MUL and VSUM in single pass). In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_MROW: u128 = 104; //  MROW <row>                   ; stack args = [TOS: Matrix] ; result = [TOS: Vector] ; Copy row of Matrix on TOS into a new Vector. Pushes on TOS. Does not consume the operand.
+pub const OP_MCOL: u128 = 105; //  MCOL <col>                   ; stack args = [TOS: Matrix] ; result = [TOS: Vector] ; Copy column of Matrix on TOS into a new Vector. Pushes on TOS. Does not consume the operand.
+pub const OP_LDMX: u128 = 106; //  LDMX <matrix_id>             ; no stack args ; result = [TOS: Matrix]; Load Matrix object from VIO by ID. Pushes on TOS.
+pub const OP_STMX: u128 = 107; //  STMX <matrix_id>             ; stack args = [TOS: Matrix] ; Store Matrix object into VIO. Consumes TOS.
+
+// 11. Slicing & Reordering (110-113)
+pub const OP_SLICE: u128 = 110; //  SLICE <from> <len>          ; stack args = [TOS: Vector|Labels] ; result = [TOS: Vector|Labels] ; Take `len` components starting at `from`. Fails with OutOfRange if range exceeds length. In-place updates operand on TOS.
+pub const OP_GATHER: u128 = 111; // GATHER <pos>                ; stack args = [TOS - pos: Vector|Labels: 'A, TOS: Labels: 'I] ; result = [TOS: 'A[I[0]], ..., 'A[I[n]]] ; Gather components of operand at [T-pos] at positions given by index Labels on TOS. Fails with OutOfRange if index exceeds length. Replaces operand on TOS. Does not consume the other operand.
+pub const OP_SCATTER: u128 = 112; // SCATTER <pos_A> <pos_I>    ; stack args = [TOS - pos_A: Vector|Labels: 'A, TOS - pos_I: Labels: 'I, TOS: 'X] ; result = [TOS: 'X with 'X[I[k]] = 'A[k]] ; Scatter components of operand at [T-pos_A] into TOS at positions given by index Labels at [T-pos_I]. Fails with NotAligned if 'A and 'I differ in length, and OutOfRange if index exceeds length of TOS. In-place updates operand on TOS. Does not consume other operands.
+pub const OP_CONCAT: u128 = 113; // CONCAT <pos>                ; stack args = [TOS - pos: Vector|Labels: 'A, TOS: 'B] ; result = [TOS: 'B..., 'A...] ; Append components of operand at [T-pos] to the end of TOS. Works with vectors and labels. In-place updates operand on TOS. Does not consume the other operand.
+
+// 12. Labels Set Algebra (120-124)
+//
+// Set operations expect Labels sorted in strictly increasing order, same as joins (JUPD, JADD,
+// JFLT). Use LCHECK to verify order of Labels loaded from VIO, and LSORT to restore it.
+pub const OP_LINTER: u128 = 120; // LINTER <pos>                ; stack args = [TOS - pos: Labels: 'LB, TOS: Labels: 'LA] ; result = [TOS: 'LA & 'LB] ; Intersection of two sorted Labels operands. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_LDIFF: u128 = 121; //  LDIFF <pos>                 ; stack args = [TOS - pos: Labels: 'LB, TOS: Labels: 'LA] ; result = [TOS: 'LA - 'LB] ; Difference of two sorted Labels operands, i.e. labels of TOS not present at [T-pos]. In-place updates operand on TOS. Does not consume the other operand.
+pub const OP_LFIND: u128 = 122; //  LFIND <pos>                 ; stack args = [TOS - pos: Labels, TOS: Label] ; result = [TOS - 1: Scalar: position, TOS: Scalar: found] ; Find position of Label in sorted Labels at [T-pos]. Replaces TOS with position, and pushes flag, which is 1 if found and 0 if not found (position is then 0). Does not consume the other operand.
+pub const OP_LSORT: u128 = 123; //  LSORT <pos>                 ; stack args = [TOS - pos: Vector, TOS: Labels] ; result = [TOS - pos: Vector permuted, TOS: Labels sorted] ; Sort Labels on TOS, and permute accompanying Vector at [T-pos] the same way (with `pos` = 0 only Labels are sorted). Fails with NotAligned if lengths differ. In-place updates both operands.
+pub const OP_LCHECK: u128 = 124; // LCHECK                      ; stack args = [TOS: Labels] ; result = [TOS: Labels] ; Verify that Labels on TOS are in strictly increasing order, failing with NotSorted otherwise. Does not consume the operand.
+
+// 13. Asset Labels (130-133)
+//
+// Asset labels encode asset id and side (long or short), see `deli::asset`, so that long and short
+// exposures can be stored in one labelled vector, and split on the fly.
+pub const OP_LSIDE: u128 = 130; //  LSIDE                       ; stack args = [TOS: Label|Labels] ; result = [TOS: Label|Labels] ; Side of asset label(s), i.e. SIDE_LONG, SIDE_SHORT or SIDE_FLAT. In-place updates operand on TOS.
+pub const OP_LASSET: u128 = 131; // LASSET                      ; stack args = [TOS: Label|Labels] ; result = [TOS: Label|Labels] ; Asset id of asset label(s) without side. In-place updates operand on TOS.
+pub const OP_LMAKE: u128 = 132; //  LMAKE <side>                ; stack args = [TOS: Label|Labels] ; result = [TOS: Label|Labels] ; Make asset label(s) from asset id(s) on TOS and given side. In-place updates operand on TOS.
+pub const OP_LFSIDE: u128 = 133; // LFSIDE <pos> <side>         ; stack args = [TOS - pos: Vector, TOS: Labels] ; result = [TOS - pos: Vector filtered, TOS: Labels filtered] ; Filter asset Labels on TOS keeping only labels of given side, and filter accompanying Vector at [T-pos] the same way (with `pos` = 0 only Labels are filtered). Fails with NotAligned if lengths differ. In-place updates both operands.
+
+// Code Inspection
+
+/// Number of immediate operands following op-code in VIL code
+pub const fn num_operands(op_code: u128) -> usize {
+    match op_code {
+        OP_LDL | OP_LDV | OP_LDD | OP_LDR | OP_LDM | OP_STL | OP_STV | OP_STR | OP_PKV | OP_PKL
+        | OP_VPUSH | OP_T | OP_LUNION | OP_LPUSH | OP_ADD | OP_SUB | OP_SSB | OP_MUL | OP_DIV
+        | OP_POW | OP_MIN | OP_MAX | OP_GT | OP_LT | OP_EQ | OP_IMMS | OP_IMML | OP_ZEROS
+        | OP_ONES | OP_POPN | OP_SWAP | OP_PKM | OP_MATVEC | OP_DOT | OP_MROW | OP_MCOL
+        | OP_LDMX | OP_STMX | OP_GATHER | OP_CONCAT | OP_LINTER | OP_LDIFF | OP_LFIND
+        | OP_LSORT | OP_LMAKE => 1,
+        OP_JFLT | OP_SELECT | OP_SLICE | OP_SCATTER | OP_LFSIDE => 2,
+        OP_JUPD | OP_JADD | OP_JUNION => 3,
+        OP_B | OP_FOLD => 4,
+        _ => 0,
+    }
+}
+
+/// Number of registers (R0-Rn) VIL code uses, i.e. highest register accessed by LDR, LDM or STR
+/// plus one. Registers used by sub-routines are not included, as they run with their own registry.
+pub fn num_registers(code: &[u128]) -> u128 {
+    let mut result = 0;
+    let mut pc = 0;
+    while pc < code.len() {
+        let op_code = code[pc];
+        pc += 1;
+        if matches!(op_code, OP_LDR | OP_LDM | OP_STR) {
+            if let Some(reg) = code.get(pc) {
+                result = result.max(reg + 1);
+            }
+        }
+        pc += num_operands(op_code);
+    }
+    result
+}
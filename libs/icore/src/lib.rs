@@ -1,6 +1,13 @@
 pub mod vil {
+    pub mod accrue_management_fee;
     pub mod solve_quadratic;
+    pub mod execute_buy_batch;
     pub mod execute_buy_order;
+    pub mod execute_sell_order;
+    pub mod match_market;
+    pub mod names;
+    pub mod queue;
+    pub mod submit_order;
     pub mod update_quote;
     pub mod update_supply;
 }
\ No newline at end of file
@@ -0,0 +1,59 @@
+use devil_macros::devil;
+
+/// Accrue Index Management Fee
+///
+/// Management fee streams per time unit on the value of index supply, i.e.
+/// Fee = Management Fee * Elapsed * Supply * Price, and it is accrued into
+/// Management Fees of the fee accumulator (Entry Fees, Exit Fees, Management
+/// Fees).
+///
+/// Elapsed number of time units since last accrual is given as raw Amount in
+/// first argument of the procedure. Arguments are bound to registers, and
+/// `_Elapsed` is the first register used by the program, i.e. R0, so that the
+/// procedure is installed once as:
+///
+///   accrue_management_fee(arg(1), arg(2), arg(3), arg(4))
+///
+pub fn accrue_management_fee(
+    index_fees_id: u128,
+    index_quote_id: u128,
+    index_supply_id: u128,
+    fee_accumulator_id: u128,
+) -> Vec<u128> {
+    devil! {
+        // Compute Fee Rate = Management Fee * Elapsed
+        LDV         index_fees_id               // Stack: [Fees = (EntryFee, ExitFee, ManagementFee)]
+        UNPK                                    // Stack: [EntryFee, ExitFee, ManagementFee]
+        LDM         _Elapsed                    // Stack: [EntryFee, ExitFee, ManagementFee, Elapsed]
+        MUL         1                           // Stack: [EntryFee, ExitFee, ManagementFee, FR = (Elapsed * ManagementFee)]
+        STR         _FeeRate                    // Stack: [EntryFee, ExitFee, ManagementFee]
+        POPN        3                           // Stack: []
+
+        // Load Index Price
+        LDV         index_quote_id              // Stack: [Quote = (Capacity, Price, Slope)]
+        UNPK                                    // Stack: [Capacity, Price, Slope]
+        POPN        1                           // Stack: [Capacity, Price]
+        STR         _Price                      // Stack: [Capacity]
+        POPN        1                           // Stack: []
+
+        // Compute Fee = Fee Rate * Supply * Price
+        LDV         index_supply_id             // Stack: [Supply]
+        UNPK                                    // Stack: [S]
+        LDM         _Price                      // Stack: [S, Price]
+        MUL         1                           // Stack: [S, V = (Price * S)]
+        LDM         _FeeRate                    // Stack: [S, V, FR]
+        MUL         1                           // Stack: [S, V, Fee = (FR * V)]
+        STR         _Fee                        // Stack: [S, V]
+        POPN        2                           // Stack: []
+
+        // Accrue Management Fee
+        LDV         fee_accumulator_id          // Stack: [Accrued = (EntryFees, ExitFees, ManagementFees)]
+        UNPK                                    // Stack: [EntryFees, ExitFees, ManagementFees]
+        LDM         _Fee                        // Stack: [EntryFees, ExitFees, ManagementFees, Fee]
+        ADD         1                           // Stack: [EntryFees, ExitFees, ManagementFees, MF = (ManagementFees + Fee)]
+        SWAP        1                           // Stack: [EntryFees, ExitFees, MF, ManagementFees]
+        POPN        1                           // Stack: [EntryFees, ExitFees, MF]
+        PKV         3                           // Stack: [(EntryFees, ExitFees, MF)]
+        STV         fee_accumulator_id          // Stack: []
+    }
+}
@@ -0,0 +1,270 @@
+use devil_macros::devil;
+
+/// Execute Batch of Buy Index Orders
+///
+/// All orders in the queue (Labels of order ids) are executed against one quote
+/// snapshot as if they were single order of total collateral, and then each
+/// order is filled pro-rata to its collateral, i.e. it spends Spent Ratio =
+/// (Collateral Spent / Total Collateral) of its collateral and mints Minted
+/// Ratio = (Index Quantity / Total Collateral) per unit of its collateral.
+/// When total demand exceeds capacity all orders are partially filled in same
+/// proportion. Market demand and delta are updated once for the whole batch.
+/// Index quantity filled for each order is stored in Executed Order Quantities
+/// (Vector aligned with the queue), so that it can be minted to order owners.
+///
+/// Orders with zero collateral (e.g. fully filled) are skipped, i.e. they
+/// spend and mint nothing, and when total collateral is zero nothing is
+/// executed.
+///
+#[allow(clippy::too_many_arguments)]
+pub fn execute_buy_batch(
+    queue_id: u128,
+    executed_index_quantities_id: u128,
+    executed_asset_quantities_id: u128,
+    executed_order_quantities_id: u128,
+    asset_names_id: u128,
+    asset_weights_id: u128,
+    index_quote_id: u128,
+    index_fees_id: u128,
+    index_supply_id: u128,
+    market_asset_names_id: u128,
+    supply_long_id: u128,
+    supply_short_id: u128,
+    demand_long_id: u128,
+    demand_short_id: u128,
+    delta_long_id: u128,
+    delta_short_id: u128,
+    fee_accumulator_id: u128,
+    solve_quadratic_id: u128,
+    match_market_id: u128,
+    batch_sum_collateral_id: u128,
+    batch_fill_order_id: u128,
+) -> Vec<u128> {
+    devil! {
+        // Load Weights
+        LDV         asset_weights_id            // Stack: [AssetWeights]
+        STR         _Weights                    // Stack: []
+
+        // Load Entry Fee
+        LDV         index_fees_id               // Stack: [Fees = (EntryFee, ExitFee, ManagementFee)]
+        UNPK                                    // Stack: [EntryFee, ExitFee, ManagementFee]
+        POPN        2                           // Stack: [EntryFee]
+        STR         _EntryFee                   // Stack: []
+
+        // Load Index Quote
+        LDV         index_quote_id              // Stack: [Quote = (Capacity, Price, Slope)]
+        UNPK                                    // Stack: [Capacity, Price, Slope]
+        SWAP        2                           // Stack: [Slope, Price, Capacity]
+        STR         _Capacity                   // Stack: [Slope, Price]
+        STR         _Price                      // Stack: [Slope]
+        STR         _Slope                      // Stack: []
+
+        // Compute Total Collateral of all orders in the queue
+        IMMS        0                           // Stack: [0]
+        LDL         queue_id                    // Stack: [0, Queue]
+        FOLD        batch_sum_collateral_id 1 1 0   // Stack: [TotalCollateral]
+        STR         _TotalCollateral            // Stack: []
+
+        // Reserve Entry Fee: NC = Total Collateral / (1 + EntryFee)
+        LDR         _TotalCollateral            // Stack: [TC]
+        IMMS        1                           // Stack: [TC, 1]
+        LDR         _EntryFee                   // Stack: [TC, 1, EntryFee]
+        ADD         1                           // Stack: [TC, 1, FF = (EntryFee + 1)]
+        SWAP        1                           // Stack: [TC, FF, 1]
+        POPN        1                           // Stack: [TC, FF]
+        SWAP        1                           // Stack: [FF, TC]
+        DIV         1                           // Stack: [FF, NC = (TC / FF)]
+        STR         _NetCollateral              // Stack: [FF]
+        POPN        1                           // Stack: []
+
+        // Solve Quadratic: S * Q^2 + P * Q - NC = 0
+        LDR         _Slope                      // Stack: [Slope]
+        LDR         _Price                      // Stack: [Slope, Price]
+        LDM         _NetCollateral              // Stack: [Slope, Price, NC]
+        B           solve_quadratic_id  3  1  4 // Stack: [IndexQuantity]
+        STR         _IndexQuantity              // Stack: []
+
+        // Cap Index Quantity with Capacity
+        LDM         _Capacity                   // Stack: [Capacity]
+        LDR         _IndexQuantity              // Stack: [Capacity, IndexQuantity]
+        MIN         1                           // Stack: [Capacity, CIQ = MIN(Capacity, IndexQuantity)]
+        STR         _CappedIndexQuantity        // Stack: [Capacity]
+        POPN        1                           // Stack: []
+
+        // Generate Individual Asset Orders (compute asset quantities)
+        LDR         _CappedIndexQuantity        // Stack: [CIQ]
+        LDM         _Weights                    // Stack: [CIQ, AssetWeights]
+        MUL         1                           // Stack: [CIQ, AssetQuantities]
+
+        STR         _AssetQuantities            // Stack: [CIQ]
+        POPN        1                           // Stack: []
+
+        // Match Market: Update Demand and Delta
+        LDL         asset_names_id              // Stack [AssetNames]
+        LDL         market_asset_names_id       // Stack [AssetNames, MarketAssetNames]
+        LDV         supply_long_id              // Stack [AssetNames, MarketAssetNames, SL]
+        LDV         supply_short_id             // Stack [AssetNames, MarketAssetNames, SL, SS]
+        LDV         demand_long_id              // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old]
+        LDV         demand_short_id             // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old, DS_old]
+        LDR         _AssetQuantities            // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old, DS_old, AQ]
+        B           match_market_id  7  4  7    // Stack [DL, DS, RL, RS]
+
+        // Store Delta
+        STV         delta_short_id              // Stack [DL, DS, RL]
+        STV         delta_long_id               // Stack [DL, DS]
+
+        // Store Demand
+        STV         demand_short_id             // Stack [DL]
+        STV         demand_long_id              // Stack []
+
+        // Compute Total Collateral Spent
+        LDR         _CappedIndexQuantity            // Stack: [CIQ]
+        LDM         _Slope                          // Stack: [CIQ, Slope]
+        MUL         1                               // Stack: [CIQ, SQ = (S * Q)]
+        LDM         _Price                          // Stack: [CIQ, SQ, Price]
+        ADD         1                               // Stack: [CIQ, SQ, EP = (SQ + Price)]
+        SWAP        1                               // Stack: [CIQ, EP, SQ]
+        POPN        1                               // Stack: [CIQ, EP]
+        MUL         1                               // Stack: [CIQ, CS = (CIQ * EP)]
+
+        // Compute Total Entry Fee
+        LDR         _EntryFee                       // Stack: [CIQ, CS, EntryFee]
+        MUL         1                               // Stack: [CIQ, CS, Fee = (EntryFee * CS)]
+        STR         _Fee                            // Stack: [CIQ, CS]
+
+        // Compute Spent and Minted Ratios, where zero Total Collateral gives
+        // zero ratios, i.e. TC' = TC + Z, Z = (TC == 0)
+        LDM         _TotalCollateral                // Stack: [CIQ, CS, TC]
+        IMMS        0                               // Stack: [CIQ, CS, TC, 0]
+        EQ          1                               // Stack: [CIQ, CS, TC, Z = (TC == 0)]
+        ADD         1                               // Stack: [CIQ, CS, TC, TC' = (TC + Z)]
+        SWAP        1                               // Stack: [CIQ, CS, TC', TC]
+        POPN        1                               // Stack: [CIQ, CS, TC]
+        SWAP        1                               // Stack: [CIQ, TC, CS]
+        DIV         1                               // Stack: [CIQ, TC, SR = (CS / TC)]
+        SWAP        2                               // Stack: [SR, TC, CIQ]
+        DIV         1                               // Stack: [SR, TC, MR = (CIQ / TC)]
+        SWAP        1                               // Stack: [SR, MR, TC]
+        POPN        1                               // Stack: [SR, MR]
+
+        // Fill Orders pro-rata
+        PKV         0                               // Stack: [SR, MR, Filled = ()]
+        SWAP        2                               // Stack: [Filled, MR, SR]
+        SWAP        1                               // Stack: [Filled, SR, MR]
+        LDM         _EntryFee                       // Stack: [Filled, SR, MR, EntryFee]
+        LDL         queue_id                        // Stack: [Filled, SR, MR, EntryFee, Queue]
+        FOLD        batch_fill_order_id  4  4  4    // Stack: [Filled, SR, MR, EntryFee]
+        POPN        3                               // Stack: [Filled]
+        STV         executed_order_quantities_id    // Stack: []
+
+        // Update Index Supply
+        LDR         _CappedIndexQuantity            // Stack: [CIQ]
+        LDV         index_supply_id                 // Stack: [CIQ, Supply]
+        UNPK                                        // Stack: [CIQ, S_old]
+        ADD         1                               // Stack: [CIQ, S_new = (S_old + CIQ)]
+        PKV         1                               // Stack: [CIQ, (S_new)]
+        STV         index_supply_id                 // Stack: [CIQ]
+        POPN        1                               // Stack: []
+
+        // Accrue Entry Fee
+        LDV         fee_accumulator_id              // Stack: [Accrued = (EntryFees, ExitFees, ManagementFees)]
+        UNPK                                        // Stack: [EntryFees, ExitFees, ManagementFees]
+        SWAP        2                               // Stack: [ManagementFees, ExitFees, EntryFees]
+        LDM         _Fee                            // Stack: [ManagementFees, ExitFees, EntryFees, Fee]
+        ADD         1                               // Stack: [ManagementFees, ExitFees, EntryFees, EF = (EntryFees + Fee)]
+        SWAP        1                               // Stack: [ManagementFees, ExitFees, EF, EntryFees]
+        POPN        1                               // Stack: [ManagementFees, ExitFees, EF]
+        SWAP        2                               // Stack: [EF, ExitFees, ManagementFees]
+        PKV         3                               // Stack: [(EF, ExitFees, ManagementFees)]
+        STV         fee_accumulator_id              // Stack: []
+
+        // Store Executed Index Quantity and Remaining Quantity
+        LDM         _CappedIndexQuantity            // Stack: [CIQ]
+        LDM         _IndexQuantity                  // Stack: [CIQ, IndexQuantity]
+        SUB         1                               // Stack: [CIQ, RIQ = (IndexQuantity - CIQ)]
+        PKV         2                               // Stack: [(CIQ, RIQ)]
+        STV         executed_index_quantities_id    // Stack: []
+
+        // Store Executed Asset Quantities
+        LDM         _AssetQuantities
+        STV         executed_asset_quantities_id
+    }
+}
+
+/// Batch Sub-Routine: Sum Collateral of Orders
+///
+/// Folded over the queue: [TotalCollateral, OrderId] -> [TotalCollateral]
+///
+pub fn batch_sum_collateral() -> Vec<u128> {
+    devil! {
+        LDVI                // Stack: [TC, Order = (Collateral, Spent, Minted)]
+        UNPK                // Stack: [TC, Collateral, Spent, Minted]
+        POPN        2       // Stack: [TC, Collateral]
+        ADD         1       // Stack: [TC, TC_new = (Collateral + TC)]
+        SWAP        1       // Stack: [TC_new, TC]
+        POPN        1       // Stack: [TC_new]
+    }
+}
+
+/// Batch Sub-Routine: Fill Order pro-rata
+///
+/// Folded over the queue: [Filled, SR, MR, EntryFee, OrderId] -> [Filled, SR,
+/// MR, EntryFee], where filled index quantity of the order is appended to
+/// Filled.
+///
+pub fn batch_fill_order() -> Vec<u128> {
+    devil! {
+        // Load Order
+        LDD         0                   // Stack: [SR, MR, EntryFee, Id, Id]
+        LDVI                            // Stack: [SR, MR, EntryFee, Id, Order = (Collateral, Spent, Minted)]
+        UNPK                            // Stack: [SR, MR, EntryFee, Id, Collateral, Spent, Minted]
+        STR         _Minted             // Stack: [SR, MR, EntryFee, Id, Collateral, Spent]
+        STR         _Spent              // Stack: [SR, MR, EntryFee, Id, Collateral]
+        STR         _Collateral         // Stack: [SR, MR, EntryFee, Id]
+
+        // Compute Filled Index Quantity
+        LDR         _Collateral         // Stack: [SR, MR, EntryFee, Id, Collateral]
+        MUL         3                   // Stack: [SR, MR, EntryFee, Id, FQ = (Collateral * MR)]
+        STR         _Filled             // Stack: [SR, MR, EntryFee, Id]
+
+        // Compute Collateral Spent and Entry Fee
+        LDR         _Collateral         // Stack: [SR, MR, EntryFee, Id, Collateral]
+        MUL         4                   // Stack: [SR, MR, EntryFee, Id, CS = (Collateral * SR)]
+        LDD         0                   // Stack: [SR, MR, EntryFee, Id, CS, CS]
+        MUL         3                   // Stack: [SR, MR, EntryFee, Id, CS, Fee = (CS * EntryFee)]
+
+        // Compute Order Remaining Collateral
+        LDM         _Collateral         // Stack: [SR, MR, EntryFee, Id, CS, Fee, C]
+        SSB         2                   // Stack: [SR, MR, EntryFee, Id, CS, Fee, C1 = (C s- CS)]
+        SSB         1                   // Stack: [SR, MR, EntryFee, Id, CS, Fee, CR = (C1 s- Fee)]
+        SWAP        1                   // Stack: [SR, MR, EntryFee, Id, CS, CR, Fee]
+        POPN        1                   // Stack: [SR, MR, EntryFee, Id, CS, CR]
+        SWAP        1                   // Stack: [SR, MR, EntryFee, Id, CR, CS]
+
+        // Compute Order Spent Collateral
+        LDM         _Spent              // Stack: [SR, MR, EntryFee, Id, CR, CS, CS_old]
+        ADD         1                   // Stack: [SR, MR, EntryFee, Id, CR, CS, CS_new = (CS_old + CS)]
+        SWAP        1                   // Stack: [SR, MR, EntryFee, Id, CR, CS_new, CS]
+        POPN        1                   // Stack: [SR, MR, EntryFee, Id, CR, CS_new]
+
+        // Compute Order Minted Quantity
+        LDR         _Filled             // Stack: [SR, MR, EntryFee, Id, CR, CS_new, FQ]
+        LDM         _Minted             // Stack: [SR, MR, EntryFee, Id, CR, CS_new, FQ, M_old]
+        ADD         1                   // Stack: [SR, MR, EntryFee, Id, CR, CS_new, FQ, M_new = (M_old + FQ)]
+        SWAP        1                   // Stack: [SR, MR, EntryFee, Id, CR, CS_new, M_new, FQ]
+        POPN        1                   // Stack: [SR, MR, EntryFee, Id, CR, CS_new, M_new]
+
+        // Store Updated Order
+        PKV         3                   // Stack: [SR, MR, EntryFee, Id, (CR, CS_new, M_new)]
+        SWAP        1                   // Stack: [SR, MR, EntryFee, (CR, CS_new, M_new), Id]
+        STVI                            // Stack: [SR, MR, EntryFee]
+
+        // Record Filled Index Quantity
+        LDM         _Filled             // Stack: [Filled, SR, MR, EntryFee, FQ]
+        PKV         1                   // Stack: [Filled, SR, MR, EntryFee, (FQ)]
+        SWAP        4                   // Stack: [(FQ), SR, MR, EntryFee, Filled]
+        CONCAT      4                   // Stack: [(FQ), SR, MR, EntryFee, Filled' = (Filled, FQ)]
+        SWAP        4                   // Stack: [Filled', SR, MR, EntryFee, (FQ)]
+        POPN        1                   // Stack: [Filled', SR, MR, EntryFee]
+    }
+}
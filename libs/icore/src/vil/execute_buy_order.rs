@@ -1,7 +1,12 @@
 use devil_macros::devil;
 
 /// Execute Buy Index Order
-/// 
+///
+/// Entry fee is charged on collateral spent, i.e. at most Collateral / (1 +
+/// Entry Fee) is spent on index, and the fee is accrued into Entry Fees of the
+/// fee accumulator (Entry Fees, Exit Fees, Management Fees).
+///
+#[allow(clippy::too_many_arguments)]
 pub fn execute_buy_order(
     order_id: u128,
     executed_index_quantities_id: u128,
@@ -9,6 +14,8 @@ pub fn execute_buy_order(
     asset_names_id: u128,
     asset_weights_id: u128,
     index_quote_id: u128,
+    index_fees_id: u128,
+    index_supply_id: u128,
     market_asset_names_id: u128,
     supply_long_id: u128,
     supply_short_id: u128,
@@ -16,13 +23,21 @@ pub fn execute_buy_order(
     demand_short_id: u128,
     delta_long_id: u128,
     delta_short_id: u128,
+    fee_accumulator_id: u128,
     solve_quadratic_id: u128,
+    match_market_id: u128,
 ) -> Vec<u128> {
     devil! {
         // Load Weights
         LDV         asset_weights_id            // Stack: [AssetWeights]
         STR         _Weights                    // Stack: []
 
+        // Load Entry Fee
+        LDV         index_fees_id               // Stack: [Fees = (EntryFee, ExitFee, ManagementFee)]
+        UNPK                                    // Stack: [EntryFee, ExitFee, ManagementFee]
+        POPN        2                           // Stack: [EntryFee]
+        STR         _EntryFee                   // Stack: []
+
         // Load Index Order
         LDV         order_id                    // Stack: [Order = (Collateral, Spent, Minted)] 
         UNPK                                    // Stack: [Collateral, Spent, Minted]
@@ -37,11 +52,23 @@ pub fn execute_buy_order(
         STR         _Capacity                   // Stack: [Slope, Price]
         STR         _Price                      // Stack: [Slope]
         STR         _Slope                      // Stack: []
-        
-        // Solve Quadratic: S * Q^2 + P * Q - C = 0
+
+        // Reserve Entry Fee: NC = Collateral / (1 + EntryFee)
+        LDR         _Collateral                 // Stack: [Collateral]
+        IMMS        1                           // Stack: [Collateral, 1]
+        LDR         _EntryFee                   // Stack: [Collateral, 1, EntryFee]
+        ADD         1                           // Stack: [Collateral, 1, FF = (EntryFee + 1)]
+        SWAP        1                           // Stack: [Collateral, FF, 1]
+        POPN        1                           // Stack: [Collateral, FF]
+        SWAP        1                           // Stack: [FF, Collateral]
+        DIV         1                           // Stack: [FF, NC = (Collateral / FF)]
+        STR         _NetCollateral              // Stack: [FF]
+        POPN        1                           // Stack: []
+
+        // Solve Quadratic: S * Q^2 + P * Q - NC = 0
         LDR         _Slope                      // Stack: [Slope]
         LDR         _Price                      // Stack: [Slope, Price]
-        LDR         _Collateral                 // Stack: [Slope, Price, Collateral]
+        LDM         _NetCollateral              // Stack: [Slope, Price, NC]
         B           solve_quadratic_id  3  1  4 // Stack: [IndexQuantity]
         STR         _IndexQuantity              // Stack: []
 
@@ -63,68 +90,20 @@ pub fn execute_buy_order(
         // Match Market: Update Demand and Delta
         LDL         asset_names_id              // Stack [AssetNames]
         LDL         market_asset_names_id       // Stack [AssetNames, MarketAssetNames]
-        
-        // Compute Demand Short = MAX(Demand Short - Asset Quantities, 0)
-        LDV         demand_short_id             // Stack [AssetNames, MarketAssetNames, DS_old]
-        LDR         _AssetQuantities            // Stack [AssetNames, MarketAssetNames, DS_old, AQ]
-        LDD         1                           // Stack [AssetNames, MarketAssetNames, DS_old, AQ, DS_old]
-        JFLT        3   4                       // Stack [AssetNames, MarketAssetNames, DS_old, AQ, fDS_old]
-        LDD         0                           // Stack [AssetNames, MarketAssetNames, DS_old, AQ, fDS_old, fDS_old]
-        SSB         2                           // Stack [AssetNames, MarketAssetNames, DS_old, AQ, fDS_old, fDS_new = (fDS_old s- AQ)]
-        SWAP        3                           // Stack [AssetNames, MarketAssetNames, fDS_new, AQ, fDS_old, DS_old]
-        JUPD        3   4   5                   // Stack [AssetNames, MarketAssetNames, fDS_new, AQ, fDS_old, DS_new]
-        SWAP        3                           // Stack [AssetNames, MarketAssetNames, DS_new, AQ, fDS_old, fDS_new]
-        POPN        1                           // Stack [AssetNames, MarketAssetNames, DS_new, AQ, fDS_old]
-
-        // Compute Demand Long += MAX(Asset Quantities - Demand Short, 0)
-        SWAP        1                           // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, AQ]
-        SSB         1                           // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, dAQ = (AQ s- fDS_old)]
-        LDV         demand_long_id              // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, dAQ, DL_old]
-        JADD        1   4   5                   // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, dAQ, DL_new = (DL_old j+ dAQ)]
-        SWAP        2                           // Stack [AssetNames, MarketAssetNames, DS_new, DL_new, dAQ, fDS_old]
-        POPN        2                           // Stack [AssetNames, MarketAssetNames, DS_new, DL_new]
-        STR         _DemandLong                 // Stack [AssetNames, MarketAssetNames, DS_new]
-        STR         _DemandShort                // Stack [AssetNames, MarketAssetNames]
-        
-        // Update Delta
-        //
-        // (Delta Long - Delta Short) = (Supply Long + Demand Short) - (Supply Short + Demand Long)
-        //
-        
-        // Supply Long + Demand Short
-        LDV         supply_long_id
-        LDR         _DemandShort
-        ADD         1                           // Stack [AssetNames, MarketAssetNames, SupplyLong, DeltaLong]
-        SWAP        1
-        POPN        1                           // Stack [AssetNames, MarketAssetNames, DeltaLong]
-
-        // Supply Short + Demand Long
-        LDV         supply_short_id
-        LDR         _DemandLong
-        ADD         1                           // Stack [AssetNames, MarketAssetNames, DeltaLong, SupplyShort, DeltaShort]
-        SWAP        1
-        POPN        1                           // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort]
-
-        // Delta Long - Delta Short
-        LDD         0                           // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort, DeltaShort]
-        SSB         2                           // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort, RS = (DeltaShort s- DeltaLong)]
-        STR         _DeltaShort                 // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort]
-        SWAP        1                           // Stack [AssetNames, MarketAssetNames, DeltaShort, DeltaLong]
-        SSB         1                           // Stack [AssetNames, MarketAssetNames, DeltaShort, RL = (DeltaLong s- DeltaShort)]
-        STR         _DeltaLong                  // Stack [AssetNames, MarketAssetNames, DeltaShort]
-        POPN        3                           // Stack []
-
-        // Store Demand
-        LDM         _DemandLong
-        LDM         _DemandShort
-        STV         demand_short_id
-        STV         demand_long_id
+        LDV         supply_long_id              // Stack [AssetNames, MarketAssetNames, SL]
+        LDV         supply_short_id             // Stack [AssetNames, MarketAssetNames, SL, SS]
+        LDV         demand_long_id              // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old]
+        LDV         demand_short_id             // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old, DS_old]
+        LDR         _AssetQuantities            // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old, DS_old, AQ]
+        B           match_market_id  7  4  7    // Stack [DL, DS, RL, RS]
 
         // Store Delta
-        LDM         _DeltaLong
-        LDM         _DeltaShort
-        STV         delta_short_id
-        STV         delta_long_id
+        STV         delta_short_id              // Stack [DL, DS, RL]
+        STV         delta_long_id               // Stack [DL, DS]
+
+        // Store Demand
+        STV         demand_short_id             // Stack [DL]
+        STV         demand_long_id              // Stack []
 
         // Compute Collateral Spent
         LDR         _CappedIndexQuantity            // Stack: [CIQ]
@@ -135,10 +114,20 @@ pub fn execute_buy_order(
         SWAP        1                               // Stack: [CIQ, EP, SQ]
         POPN        1                               // Stack: [CIQ, EP] 
         MUL         1                               // Stack: [CIQ, CS = (CIQ * EP)]
-        
-        // Compute Order Remaining Collateral 
+
+        // Compute Entry Fee
+        LDM         _EntryFee                       // Stack: [CIQ, CS, EntryFee]
+        MUL         1                               // Stack: [CIQ, CS, Fee = (EntryFee * CS)]
+        STR         _Fee                            // Stack: [CIQ, CS]
+
+        // Compute Order Remaining Collateral
         LDM         _Collateral                     // Stack: [CIQ, CS, C]
-        SSB         1                               // Stack: [CIQ, CS, CR = (C - CS)]
+        SSB         1                               // Stack: [CIQ, CS, C1 = (C s- CS)]
+        LDR         _Fee                            // Stack: [CIQ, CS, C1, Fee]
+        SWAP        1                               // Stack: [CIQ, CS, Fee, C1]
+        SSB         1                               // Stack: [CIQ, CS, Fee, CR = (C1 s- Fee)]
+        SWAP        1                               // Stack: [CIQ, CS, CR, Fee]
+        POPN        1                               // Stack: [CIQ, CS, CR]
         SWAP        1                               // Stack: [CIQ, CR, CS]
 
         // Compute Order Spent Collateral
@@ -149,10 +138,36 @@ pub fn execute_buy_order(
         SWAP        1                               // Stack: [CIQ, CS_new, CR]
         SWAP        2                               // Stack: [CR, CS_new, CIQ]
 
+        // Compute Order Minted Quantity
+        LDM         _Minted                         // Stack: [CR, CS_new, CIQ, M_old]
+        ADD         1                               // Stack: [CR, CS_new, CIQ, M_new = (M_old + CIQ)]
+        SWAP        1                               // Stack: [CR, CS_new, M_new, CIQ]
+        POPN        1                               // Stack: [CR, CS_new, M_new]
+
         // Store Updated Order 
-        PKV         3                               // Stack: [(CR, CS_new, CIQ)]
+        PKV         3                               // Stack: [(CR, CS_new, M_new)]
         STV         order_id                        // Stack: []
 
+        // Update Index Supply
+        LDR         _CappedIndexQuantity            // Stack: [CIQ]
+        LDV         index_supply_id                 // Stack: [CIQ, Supply]
+        UNPK                                        // Stack: [CIQ, S_old]
+        ADD         1                               // Stack: [CIQ, S_new = (S_old + CIQ)]
+        PKV         1                               // Stack: [CIQ, (S_new)]
+        STV         index_supply_id                 // Stack: [CIQ]
+        POPN        1                               // Stack: []
+
+        // Accrue Entry Fee
+        LDV         fee_accumulator_id              // Stack: [Accrued = (EntryFees, ExitFees, ManagementFees)]
+        UNPK                                        // Stack: [EntryFees, ExitFees, ManagementFees]
+        SWAP        2                               // Stack: [ManagementFees, ExitFees, EntryFees]
+        LDM         _Fee                            // Stack: [ManagementFees, ExitFees, EntryFees, Fee]
+        ADD         1                               // Stack: [ManagementFees, ExitFees, EntryFees, EF = (EntryFees + Fee)]
+        SWAP        1                               // Stack: [ManagementFees, ExitFees, EF, EntryFees]
+        POPN        1                               // Stack: [ManagementFees, ExitFees, EF]
+        SWAP        2                               // Stack: [EF, ExitFees, ManagementFees]
+        PKV         3                               // Stack: [(EF, ExitFees, ManagementFees)]
+        STV         fee_accumulator_id              // Stack: []
 
         // Store Executed Index Quantity and Remaining Quantity
         LDM         _CappedIndexQuantity            // Stack: [CIQ]
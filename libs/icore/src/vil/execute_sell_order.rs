@@ -0,0 +1,191 @@
+use devil_macros::devil;
+
+/// Execute Sell (Redeem) Index Order
+///
+/// Sell order is stored as (Quantity, Redeemed, Withdrawn), where Quantity is
+/// remaining ITP quantity to sell, Redeemed is ITP quantity sold so far, and
+/// Withdrawn is collateral returned so far.
+///
+/// Collateral returned for index quantity Q is (Price - Slope * Q) * Q, i.e.
+/// slippage works against the seller. As it falls for Q above Price / (2 *
+/// Slope), index quantity is capped there too same as with Capacity. Sell
+/// order is not kept open, so that unfilled remaining quantity is zeroed in
+/// the order, and the user keeps it as it is not burned. Executed index
+/// quantities are stored as (Capped Index Quantity, Remaining Quantity,
+/// Collateral Returned). Exit fee is charged on collateral returned, and the
+/// fee is accrued into Exit Fees of the fee accumulator (Entry Fees, Exit
+/// Fees, Management Fees).
+///
+#[allow(clippy::too_many_arguments)]
+pub fn execute_sell_order(
+    order_id: u128,
+    executed_index_quantities_id: u128,
+    executed_asset_quantities_id: u128,
+    asset_names_id: u128,
+    asset_weights_id: u128,
+    index_quote_id: u128,
+    index_fees_id: u128,
+    index_supply_id: u128,
+    market_asset_names_id: u128,
+    supply_long_id: u128,
+    supply_short_id: u128,
+    demand_long_id: u128,
+    demand_short_id: u128,
+    delta_long_id: u128,
+    delta_short_id: u128,
+    fee_accumulator_id: u128,
+    match_market_id: u128,
+) -> Vec<u128> {
+    devil! {
+        // Load Weights
+        LDV         asset_weights_id            // Stack: [AssetWeights]
+        STR         _Weights                    // Stack: []
+
+        // Load Exit Fee
+        LDV         index_fees_id               // Stack: [Fees = (EntryFee, ExitFee, ManagementFee)]
+        UNPK                                    // Stack: [EntryFee, ExitFee, ManagementFee]
+        POPN        1                           // Stack: [EntryFee, ExitFee]
+        STR         _ExitFee                    // Stack: [EntryFee]
+        POPN        1                           // Stack: []
+
+        // Load Index Order
+        LDV         order_id                    // Stack: [Order = (Quantity, Redeemed, Withdrawn)]
+        UNPK                                    // Stack: [Quantity, Redeemed, Withdrawn]
+        STR         _Withdrawn                  // Stack: [Quantity, Redeemed]
+        STR         _Redeemed                   // Stack: [Quantity]
+        STR         _Quantity                   // Stack: []
+
+        // Load Index Quote
+        LDV         index_quote_id              // Stack: [Quote = (Capacity, Price, Slope)]
+        UNPK                                    // Stack: [Capacity, Price, Slope]
+        SWAP        2                           // Stack: [Slope, Price, Capacity]
+        STR         _Capacity                   // Stack: [Slope, Price]
+        STR         _Price                      // Stack: [Slope]
+        STR         _Slope                      // Stack: []
+
+        // Cap Index Quantity with Capacity
+        LDM         _Capacity                   // Stack: [Capacity]
+        LDR         _Quantity                   // Stack: [Capacity, Quantity]
+        MIN         1                           // Stack: [Capacity, CIQ = MIN(Capacity, Quantity)]
+        STR         _CappedIndexQuantity        // Stack: [Capacity]
+        POPN        1                           // Stack: []
+
+        // Cap Index Quantity with Price / (2 * Slope), where zero Slope gives
+        // no cap, i.e. MaxQ = (Price + Z * CIQ) / (2 * Slope + Z), Z = (Slope == 0)
+        LDR         _CappedIndexQuantity        // Stack: [CIQ]
+        LDR         _Slope                      // Stack: [CIQ, S]
+        IMMS        0                           // Stack: [CIQ, S, 0]
+        EQ          1                           // Stack: [CIQ, S, Z = (S == 0)]
+        LDD         0                           // Stack: [CIQ, S, Z, Z]
+        MUL         3                           // Stack: [CIQ, S, Z, ZQ = (Z * CIQ)]
+        LDR         _Price                      // Stack: [CIQ, S, Z, ZQ, P]
+        ADD         1                           // Stack: [CIQ, S, Z, ZQ, N = (P + ZQ)]
+        SWAP        3                           // Stack: [CIQ, N, Z, ZQ, S]
+        IMMS        2                           // Stack: [CIQ, N, Z, ZQ, S, 2]
+        MUL         1                           // Stack: [CIQ, N, Z, ZQ, S, 2S]
+        ADD         3                           // Stack: [CIQ, N, Z, ZQ, S, D = (2S + Z)]
+        LDD         4                           // Stack: [CIQ, N, Z, ZQ, S, D, N]
+        DIV         1                           // Stack: [CIQ, N, Z, ZQ, S, D, MaxQ = (N / D)]
+        MIN         6                           // Stack: [CIQ, N, Z, ZQ, S, D, CIQ' = MIN(CIQ, MaxQ)]
+        STR         _CappedIndexQuantity        // Stack: [CIQ, N, Z, ZQ, S, D]
+        POPN        6                           // Stack: []
+
+        // Generate Individual Asset Orders (compute asset quantities)
+        LDR         _CappedIndexQuantity        // Stack: [CIQ]
+        LDM         _Weights                    // Stack: [CIQ, AssetWeights]
+        MUL         1                           // Stack: [CIQ, AssetQuantities]
+
+        STR         _AssetQuantities            // Stack: [CIQ]
+        POPN        1                           // Stack: []
+
+        // Match Market: Update Demand and Delta
+        //
+        // Selling mirrors buying, so that match_market is invoked with long
+        // and short sides swapped, and it returns them swapped too, i.e.
+        // Demand Long = MAX(Demand Long - Asset Quantities, 0) and
+        // Demand Short += MAX(Asset Quantities - Demand Long, 0)
+        LDL         asset_names_id              // Stack [AssetNames]
+        LDL         market_asset_names_id       // Stack [AssetNames, MarketAssetNames]
+        LDV         supply_short_id             // Stack [AssetNames, MarketAssetNames, SS]
+        LDV         supply_long_id              // Stack [AssetNames, MarketAssetNames, SS, SL]
+        LDV         demand_short_id             // Stack [AssetNames, MarketAssetNames, SS, SL, DS_old]
+        LDV         demand_long_id              // Stack [AssetNames, MarketAssetNames, SS, SL, DS_old, DL_old]
+        LDR         _AssetQuantities            // Stack [AssetNames, MarketAssetNames, SS, SL, DS_old, DL_old, AQ]
+        B           match_market_id  7  4  7    // Stack [DS, DL, RS, RL]
+
+        // Store Delta
+        STV         delta_long_id               // Stack [DS, DL, RS]
+        STV         delta_short_id              // Stack [DS, DL]
+
+        // Store Demand
+        STV         demand_long_id              // Stack [DS]
+        STV         demand_short_id             // Stack []
+
+        // Compute Collateral Returned
+        LDR         _CappedIndexQuantity            // Stack: [CIQ]
+        LDM         _Slope                          // Stack: [CIQ, Slope]
+        MUL         1                               // Stack: [CIQ, SQ = (S * Q)]
+        LDM         _Price                          // Stack: [CIQ, SQ, Price]
+        SSB         1                               // Stack: [CIQ, SQ, EP = (Price s- SQ)]
+        SWAP        1                               // Stack: [CIQ, EP, SQ]
+        POPN        1                               // Stack: [CIQ, EP]
+        MUL         1                               // Stack: [CIQ, GR = (CIQ * EP)]
+
+        // Compute Exit Fee
+        LDM         _ExitFee                        // Stack: [CIQ, GR, ExitFee]
+        MUL         1                               // Stack: [CIQ, GR, Fee = (ExitFee * GR)]
+        STR         _Fee                            // Stack: [CIQ, GR]
+        LDR         _Fee                            // Stack: [CIQ, GR, Fee]
+        SWAP        1                               // Stack: [CIQ, Fee, GR]
+        SSB         1                               // Stack: [CIQ, Fee, CR = (GR s- Fee)]
+        SWAP        1                               // Stack: [CIQ, CR, Fee]
+        POPN        1                               // Stack: [CIQ, CR]
+
+        // Compute Order Remaining Quantity
+        LDM         _Quantity                       // Stack: [CIQ, CR, Q]
+        SUB         2                               // Stack: [CIQ, CR, QR = (Q - CIQ)]
+
+        // Store Executed Index Quantity, Remaining Quantity and Collateral Returned
+        LDD         2                               // Stack: [CIQ, CR, QR, CIQ]
+        LDD         1                               // Stack: [CIQ, CR, QR, CIQ, QR]
+        LDD         3                               // Stack: [CIQ, CR, QR, CIQ, QR, CR]
+        PKV         3                               // Stack: [CIQ, CR, QR, (CIQ, QR, CR)]
+        STV         executed_index_quantities_id    // Stack: [CIQ, CR, QR]
+
+        // Compute Order Redeemed Quantity and Withdrawn Collateral
+        IMMS        0                               // Stack: [CIQ, CR, QR, 0]
+        LDM         _Redeemed                       // Stack: [CIQ, CR, QR, 0, R_old]
+        ADD         4                               // Stack: [CIQ, CR, QR, 0, R_new = (R_old + CIQ)]
+        LDM         _Withdrawn                      // Stack: [CIQ, CR, QR, 0, R_new, W_old]
+        ADD         4                               // Stack: [CIQ, CR, QR, 0, R_new, W_new = (W_old + CR)]
+
+        // Store Updated Order with Remaining Quantity zeroed
+        PKV         3                               // Stack: [CIQ, CR, QR, (0, R_new, W_new)]
+        STV         order_id                        // Stack: [CIQ, CR, QR]
+        POPN        1                               // Stack: [CIQ, CR]
+
+        // Update Index Supply
+        LDV         index_supply_id                 // Stack: [CIQ, CR, Supply]
+        UNPK                                        // Stack: [CIQ, CR, S_old]
+        SUB         2                               // Stack: [CIQ, CR, S_new = (S_old - CIQ)]
+        PKV         1                               // Stack: [CIQ, CR, (S_new)]
+        STV         index_supply_id                 // Stack: [CIQ, CR]
+        POPN        2                               // Stack: []
+
+        // Accrue Exit Fee
+        LDV         fee_accumulator_id              // Stack: [Accrued = (EntryFees, ExitFees, ManagementFees)]
+        UNPK                                        // Stack: [EntryFees, ExitFees, ManagementFees]
+        SWAP        1                               // Stack: [EntryFees, ManagementFees, ExitFees]
+        LDM         _Fee                            // Stack: [EntryFees, ManagementFees, ExitFees, Fee]
+        ADD         1                               // Stack: [EntryFees, ManagementFees, ExitFees, XF = (ExitFees + Fee)]
+        SWAP        1                               // Stack: [EntryFees, ManagementFees, XF, ExitFees]
+        POPN        1                               // Stack: [EntryFees, ManagementFees, XF]
+        SWAP        1                               // Stack: [EntryFees, XF, ManagementFees]
+        PKV         3                               // Stack: [(EntryFees, XF, ManagementFees)]
+        STV         fee_accumulator_id              // Stack: []
+
+        // Store Executed Asset Quantities
+        LDM         _AssetQuantities
+        STV         executed_asset_quantities_id
+    }
+}
@@ -0,0 +1,84 @@
+use devil_macros::devil;
+
+/// Match Market: Update Demand and Delta
+///
+/// Sub-routine shared by buy order and batch execution, which matches asset
+/// quantities bought against market demand and computes new delta, while sell
+/// order execution invokes it with long and short sides swapped:
+///
+/// [AssetNames, MarketAssetNames, SupplyLong, SupplyShort, DemandLong,
+/// DemandShort, AssetQuantities] -> [DemandLong, DemandShort, DeltaLong,
+/// DeltaShort]
+///
+/// Demand Short = MAX(Demand Short - Asset Quantities, 0)
+/// Demand Long += MAX(Asset Quantities - Demand Short, 0)
+/// (Delta Long - Delta Short) = (Supply Long + Demand Short) - (Supply Short + Demand Long)
+///
+/// Invoke with: B match_market_id 7 4 7
+///
+pub fn match_market() -> Vec<u128> {
+    devil! {
+        // Store Inputs
+        STR         _AssetQuantities            // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old, DS_old]
+        STR         _DemandShort                // Stack [AssetNames, MarketAssetNames, SL, SS, DL_old]
+        STR         _DemandLong                 // Stack [AssetNames, MarketAssetNames, SL, SS]
+        STR         _SupplyShort                // Stack [AssetNames, MarketAssetNames, SL]
+        STR         _SupplyLong                 // Stack [AssetNames, MarketAssetNames]
+
+        // Compute Demand Short = MAX(Demand Short - Asset Quantities, 0)
+        LDM         _DemandShort                // Stack [AssetNames, MarketAssetNames, DS_old]
+        LDM         _AssetQuantities            // Stack [AssetNames, MarketAssetNames, DS_old, AQ]
+        LDD         1                           // Stack [AssetNames, MarketAssetNames, DS_old, AQ, DS_old]
+        JFLT        3   4                       // Stack [AssetNames, MarketAssetNames, DS_old, AQ, fDS_old]
+        LDD         0                           // Stack [AssetNames, MarketAssetNames, DS_old, AQ, fDS_old, fDS_old]
+        SSB         2                           // Stack [AssetNames, MarketAssetNames, DS_old, AQ, fDS_old, fDS_new = (fDS_old s- AQ)]
+        SWAP        3                           // Stack [AssetNames, MarketAssetNames, fDS_new, AQ, fDS_old, DS_old]
+        JUPD        3   4   5                   // Stack [AssetNames, MarketAssetNames, fDS_new, AQ, fDS_old, DS_new]
+        SWAP        3                           // Stack [AssetNames, MarketAssetNames, DS_new, AQ, fDS_old, fDS_new]
+        POPN        1                           // Stack [AssetNames, MarketAssetNames, DS_new, AQ, fDS_old]
+
+        // Compute Demand Long += MAX(Asset Quantities - Demand Short, 0)
+        SWAP        1                           // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, AQ]
+        SSB         1                           // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, dAQ = (AQ s- fDS_old)]
+        LDM         _DemandLong                 // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, dAQ, DL_old]
+        JADD        1   4   5                   // Stack [AssetNames, MarketAssetNames, DS_new, fDS_old, dAQ, DL_new = (DL_old j+ dAQ)]
+        SWAP        2                           // Stack [AssetNames, MarketAssetNames, DS_new, DL_new, dAQ, fDS_old]
+        POPN        2                           // Stack [AssetNames, MarketAssetNames, DS_new, DL_new]
+        STR         _DemandLong                 // Stack [AssetNames, MarketAssetNames, DS_new]
+        STR         _DemandShort                // Stack [AssetNames, MarketAssetNames]
+
+        // Update Delta
+        //
+        // (Delta Long - Delta Short) = (Supply Long + Demand Short) - (Supply Short + Demand Long)
+        //
+
+        // Supply Long + Demand Short
+        LDM         _SupplyLong
+        LDR         _DemandShort
+        ADD         1                           // Stack [AssetNames, MarketAssetNames, SupplyLong, DeltaLong]
+        SWAP        1
+        POPN        1                           // Stack [AssetNames, MarketAssetNames, DeltaLong]
+
+        // Supply Short + Demand Long
+        LDM         _SupplyShort
+        LDR         _DemandLong
+        ADD         1                           // Stack [AssetNames, MarketAssetNames, DeltaLong, SupplyShort, DeltaShort]
+        SWAP        1
+        POPN        1                           // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort]
+
+        // Delta Long - Delta Short
+        LDD         0                           // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort, DeltaShort]
+        SSB         2                           // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort, RS = (DeltaShort s- DeltaLong)]
+        STR         _DeltaShort                 // Stack [AssetNames, MarketAssetNames, DeltaLong, DeltaShort]
+        SWAP        1                           // Stack [AssetNames, MarketAssetNames, DeltaShort, DeltaLong]
+        SSB         1                           // Stack [AssetNames, MarketAssetNames, DeltaShort, RL = (DeltaLong s- DeltaShort)]
+        STR         _DeltaLong                  // Stack [AssetNames, MarketAssetNames, DeltaShort]
+        POPN        3                           // Stack []
+
+        // Return Demand and Delta
+        LDM         _DemandLong                 // Stack [DL]
+        LDM         _DemandShort                // Stack [DL, DS]
+        LDM         _DeltaLong                  // Stack [DL, DS, RL]
+        LDM         _DeltaShort                 // Stack [DL, DS, RL, RS]
+    }
+}
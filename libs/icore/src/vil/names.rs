@@ -0,0 +1,26 @@
+//! Names of stored procedures in DeVIL program registry
+//!
+//! Procedures are installed under these names, and clients look up current
+//! version of the procedure by name before executing it.
+
+pub const SOLVE_QUADRATIC: [u8; 32] = procedure_name(b"solve_quadratic");
+pub const EXECUTE_BUY_ORDER: [u8; 32] = procedure_name(b"execute_buy_order");
+pub const EXECUTE_BUY_BATCH: [u8; 32] = procedure_name(b"execute_buy_batch");
+pub const MATCH_MARKET: [u8; 32] = procedure_name(b"match_market");
+pub const BATCH_SUM_COLLATERAL: [u8; 32] = procedure_name(b"batch_sum_collateral");
+pub const BATCH_FILL_ORDER: [u8; 32] = procedure_name(b"batch_fill_order");
+pub const EXECUTE_SELL_ORDER: [u8; 32] = procedure_name(b"execute_sell_order");
+pub const UPDATE_QUOTE: [u8; 32] = procedure_name(b"update_quote");
+pub const UPDATE_SUPPLY: [u8; 32] = procedure_name(b"update_supply");
+pub const ACCRUE_MANAGEMENT_FEE: [u8; 32] = procedure_name(b"accrue_management_fee");
+
+/// Procedure name as bytes32, i.e. ASCII padded with zeros
+pub const fn procedure_name(name: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    let mut i = 0;
+    while i < name.len() {
+        result[i] = name[i];
+        i += 1;
+    }
+    result
+}
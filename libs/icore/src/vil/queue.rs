@@ -0,0 +1,32 @@
+use devil_macros::devil;
+
+/// Add Order to Queue
+///
+/// Queue is sorted Labels of order ids, and adding order already in the queue
+/// leaves the queue unchanged.
+///
+pub fn enqueue_order(queue_id: u128, order_id: u128) -> Vec<u128> {
+    devil! {
+        LDL         queue_id                    // Stack: [Queue]
+        IMML        order_id                    // Stack: [Queue, OrderId]
+        PKL         1                           // Stack: [Queue, (OrderId)]
+        LUNION      1                           // Stack: [Queue, NQ = (OrderId) | Queue]
+        STL         queue_id                    // Stack: [Queue]
+        POPN        1                           // Stack: []
+    }
+}
+
+/// Remove Order from Queue
+///
+/// Removing order not in the queue leaves the queue unchanged.
+///
+pub fn dequeue_order(queue_id: u128, order_id: u128) -> Vec<u128> {
+    devil! {
+        IMML        order_id                    // Stack: [OrderId]
+        PKL         1                           // Stack: [(OrderId)]
+        LDL         queue_id                    // Stack: [(OrderId), Queue]
+        LDIFF       1                           // Stack: [(OrderId), NQ = Queue - (OrderId)]
+        STL         queue_id                    // Stack: [(OrderId)]
+        POPN        1                           // Stack: []
+    }
+}
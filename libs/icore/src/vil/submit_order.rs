@@ -0,0 +1,36 @@
+use devil_macros::devil;
+
+/// Create New Index Order
+///
+/// Order is stored as (Collateral, Spent, Minted), where Collateral is
+/// remaining collateral, Spent is collateral spent so far, and Minted is index
+/// quantity minted so far.
+///
+pub fn create_order(order_id: u128, collateral_amount: u128) -> Vec<u128> {
+    devil! {
+        IMMS        collateral_amount           // Stack: [Collateral]
+        PKV         1                           // Stack: [(Collateral)]
+        VPUSH       0                           // Stack: [(Collateral, Spent = 0)]
+        VPUSH       0                           // Stack: [(Collateral, Spent = 0, Minted = 0)]
+        STV         order_id                    // Stack: []
+    }
+}
+
+/// Add Collateral to Existing Index Order
+///
+/// Spent collateral and minted quantity are left unchanged.
+///
+pub fn update_order(order_id: u128, collateral_amount: u128) -> Vec<u128> {
+    devil! {
+        LDV         order_id                    // Stack: [Order = (Collateral, Spent, Minted)]
+        UNPK                                    // Stack: [Collateral, Spent, Minted]
+        SWAP        2                           // Stack: [Minted, Spent, Collateral]
+        IMMS        collateral_amount           // Stack: [Minted, Spent, Collateral, Amount]
+        ADD         1                           // Stack: [Minted, Spent, Collateral, C_new = (Amount + Collateral)]
+        SWAP        1                           // Stack: [Minted, Spent, C_new, Collateral]
+        POPN        1                           // Stack: [Minted, Spent, C_new]
+        SWAP        2                           // Stack: [C_new, Spent, Minted]
+        PKV         3                           // Stack: [(C_new, Spent, Minted)]
+        STV         order_id                    // Stack: []
+    }
+}
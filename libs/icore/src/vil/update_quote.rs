@@ -1,20 +1,67 @@
 use devil_macros::devil;
 
 /// Update Index Quote (Capacity, Price, Slope)
-/// 
+///
+/// Market data (prices, slopes, liquidity and delta) is filtered to match index
+/// assets, and then:
+/// - Price = SUM(Weights * Prices)
+/// - Slope = SUM(Weights^2 * Slopes), i.e. buying Q index units buys Weights * Q
+///   of assets, so that cost is Q * Price + Q^2 * Slope
+/// - Capacity = MIN((Liquidity + Delta Long s- Delta Short) / Weights)
+///
+#[allow(clippy::too_many_arguments)]
 pub fn update_quote(
     index_asset_names_id: u128,
-    _weights_id: u128,
-    _quote_id: u128,
-    _market_asset_names_id: u128,
-    _asset_prices_id: u128,
-    _asset_slopes_id: u128,
-    _asset_liquidity: u128,
-    _delta_long_id: u128,
-    _delta_short_id: u128,
+    weights_id: u128,
+    quote_id: u128,
+    market_asset_names_id: u128,
+    asset_prices_id: u128,
+    asset_slopes_id: u128,
+    asset_liquidity_id: u128,
+    delta_long_id: u128,
+    delta_short_id: u128,
 ) -> Vec<u128> {
     devil! {
-        // TODO: Write implementation
-        LDV  index_asset_names_id
+        // Match Market: Weights stay on stack between Labels and market vectors
+        LDL         index_asset_names_id        // Stack: [AssetNames]
+        LDL         market_asset_names_id       // Stack: [AssetNames, MarketAssetNames]
+        LDV         weights_id                  // Stack: [AssetNames, MarketAssetNames, Weights]
+
+        // Compute Price = Weights . Prices
+        LDV         asset_prices_id             // Stack: [AssetNames, MarketAssetNames, Weights, Prices]
+        JFLT        2   3                       // Stack: [AssetNames, MarketAssetNames, Weights, fPrices]
+        DOT         1                           // Stack: [AssetNames, MarketAssetNames, Weights, Price]
+        STR         _Price                      // Stack: [AssetNames, MarketAssetNames, Weights]
+
+        // Compute Slope = (Weights * Weights) . Slopes
+        LDV         asset_slopes_id             // Stack: [AssetNames, MarketAssetNames, Weights, Slopes]
+        JFLT        2   3                       // Stack: [AssetNames, MarketAssetNames, Weights, fSlopes]
+        MUL         1                           // Stack: [AssetNames, MarketAssetNames, Weights, WS = (fSlopes * Weights)]
+        DOT         1                           // Stack: [AssetNames, MarketAssetNames, Weights, Slope = (WS . Weights)]
+        STR         _Slope                      // Stack: [AssetNames, MarketAssetNames, Weights]
+
+        // Compute Available Liquidity = Liquidity + Delta Long s- Delta Short
+        LDV         asset_liquidity_id          // Stack: [AssetNames, MarketAssetNames, Weights, Liquidity]
+        LDV         delta_long_id               // Stack: [AssetNames, MarketAssetNames, Weights, Liquidity, DeltaLong]
+        ADD         1                           // Stack: [AssetNames, MarketAssetNames, Weights, Liquidity, LDL = (DeltaLong + Liquidity)]
+        LDV         delta_short_id              // Stack: [AssetNames, MarketAssetNames, Weights, Liquidity, LDL, DeltaShort]
+        SWAP        1                           // Stack: [AssetNames, MarketAssetNames, Weights, Liquidity, DeltaShort, LDL]
+        SSB         1                           // Stack: [AssetNames, MarketAssetNames, Weights, Liquidity, DeltaShort, AL = (LDL s- DeltaShort)]
+        SWAP        2                           // Stack: [AssetNames, MarketAssetNames, Weights, AL, DeltaShort, Liquidity]
+        POPN        2                           // Stack: [AssetNames, MarketAssetNames, Weights, AL]
+
+        // Compute Capacity = MIN(Available Liquidity / Weights)
+        JFLT        2   3                       // Stack: [AssetNames, MarketAssetNames, Weights, fAL]
+        DIV         1                           // Stack: [AssetNames, MarketAssetNames, Weights, AC = (fAL / Weights)]
+        VMIN                                    // Stack: [AssetNames, MarketAssetNames, Weights, Capacity]
+        STR         _Capacity                   // Stack: [AssetNames, MarketAssetNames, Weights]
+        POPN        3                           // Stack: []
+
+        // Store Quote
+        LDM         _Capacity                   // Stack: [Capacity]
+        LDM         _Price                      // Stack: [Capacity, Price]
+        LDM         _Slope                      // Stack: [Capacity, Price, Slope]
+        PKV         3                           // Stack: [Quote = (Capacity, Price, Slope)]
+        STV         quote_id                    // Stack: []
     }
 }
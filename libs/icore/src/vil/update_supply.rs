@@ -1,18 +1,155 @@
 use devil_macros::devil;
 
 /// Update Market (Supply, Delta)
-/// 
+///
+/// Vendor submits changes of supply for some assets, which may include assets
+/// not yet known to the market, in which case market asset names are extended
+/// with them (vendor asset names must be sorted, otherwise program fails with
+/// NotSorted), and all per-asset market vectors are expanded with zeros, i.e.
+/// new assets have no liquidity, price or slope until they are quoted. Then:
+/// - Supply Long += Asset Quantities Long, Supply Short += Asset Quantities Short
+/// - Supply Long and Supply Short are netted against each other
+/// - (Delta Long - Delta Short) = (Supply Long + Demand Short) - (Supply Short + Demand Long)
+///
+#[allow(clippy::too_many_arguments)]
 pub fn update_supply(
+    asset_names_id: u128,
+    asset_quantities_long_id: u128,
+    asset_quantities_short_id: u128,
     market_asset_names_id: u128,
-    _supply_long_id: u128,
-    _supply_short_id: u128,
-    _demand_long_id: u128,
-    _demand_short_id: u128,
-    _delta_long_id: u128,
-    _delta_short_id: u128,
+    supply_long_id: u128,
+    supply_short_id: u128,
+    demand_long_id: u128,
+    demand_short_id: u128,
+    delta_long_id: u128,
+    delta_short_id: u128,
+    asset_liquidity_id: u128,
+    asset_prices_id: u128,
+    asset_slopes_id: u128,
 ) -> Vec<u128> {
     devil! {
-        // TODO: Write implementation
-        LDV  market_asset_names_id
+        // Grow Market: Union of market asset names and supplied asset names
+        LDL         market_asset_names_id       // Stack: [MarketAssetNames]
+        LDL         asset_names_id              // Stack: [MarketAssetNames, AssetNames]
+        LCHECK                                  // Stack: [MarketAssetNames, AssetNames]
+        LDD         1                           // Stack: [MarketAssetNames, AssetNames, MarketAssetNames]
+        LUNION      1                           // Stack: [MarketAssetNames, AssetNames, NewNames = (MarketAssetNames | AssetNames)]
+
+        // Compute Supply Long += Asset Quantities Long
+        LDV         supply_long_id              // Stack: [MarketAssetNames, AssetNames, NewNames, SL_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, SL_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, SL_old, eSL = (0 j+ SL_old)]
+        SWAP        1                           // Stack: [MarketAssetNames, AssetNames, NewNames, eSL, SL_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames, eSL]
+        LDV         asset_quantities_long_id    // Stack: [MarketAssetNames, AssetNames, NewNames, eSL, AQL]
+        SWAP        1                           // Stack: [MarketAssetNames, AssetNames, NewNames, AQL, eSL]
+        JADD        1   2   3                   // Stack: [MarketAssetNames, AssetNames, NewNames, AQL, SL_new = (eSL j+ AQL)]
+        STR         _SupplyLong                 // Stack: [MarketAssetNames, AssetNames, NewNames, AQL]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        // Compute Supply Short += Asset Quantities Short
+        LDV         supply_short_id             // Stack: [MarketAssetNames, AssetNames, NewNames, SS_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, SS_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, SS_old, eSS = (0 j+ SS_old)]
+        SWAP        1                           // Stack: [MarketAssetNames, AssetNames, NewNames, eSS, SS_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames, eSS]
+        LDV         asset_quantities_short_id   // Stack: [MarketAssetNames, AssetNames, NewNames, eSS, AQS]
+        SWAP        1                           // Stack: [MarketAssetNames, AssetNames, NewNames, AQS, eSS]
+        JADD        1   2   3                   // Stack: [MarketAssetNames, AssetNames, NewNames, AQS, SS_new = (eSS j+ AQS)]
+        STR         _SupplyShort                // Stack: [MarketAssetNames, AssetNames, NewNames, AQS]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        // Expand Demand to match new market asset names
+        LDV         demand_long_id              // Stack: [MarketAssetNames, AssetNames, NewNames, DL_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, DL_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, DL_old, DL_new = (0 j+ DL_old)]
+        STR         _DemandLong                 // Stack: [MarketAssetNames, AssetNames, NewNames, DL_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        LDV         demand_short_id             // Stack: [MarketAssetNames, AssetNames, NewNames, DS_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, DS_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, DS_old, DS_new = (0 j+ DS_old)]
+        STR         _DemandShort                // Stack: [MarketAssetNames, AssetNames, NewNames, DS_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        // Expand Liquidity, Prices and Slopes to match new market asset names
+        LDV         asset_liquidity_id          // Stack: [MarketAssetNames, AssetNames, NewNames, L_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, L_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, L_old, L_new = (0 j+ L_old)]
+        STV         asset_liquidity_id          // Stack: [MarketAssetNames, AssetNames, NewNames, L_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        LDV         asset_prices_id             // Stack: [MarketAssetNames, AssetNames, NewNames, P_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, P_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, P_old, P_new = (0 j+ P_old)]
+        STV         asset_prices_id             // Stack: [MarketAssetNames, AssetNames, NewNames, P_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        LDV         asset_slopes_id             // Stack: [MarketAssetNames, AssetNames, NewNames, S_old]
+        ZEROS       1                           // Stack: [MarketAssetNames, AssetNames, NewNames, S_old, 0]
+        JADD        1   2   4                   // Stack: [MarketAssetNames, AssetNames, NewNames, S_old, S_new = (0 j+ S_old)]
+        STV         asset_slopes_id             // Stack: [MarketAssetNames, AssetNames, NewNames, S_old]
+        POPN        1                           // Stack: [MarketAssetNames, AssetNames, NewNames]
+
+        // Store Market Asset Names
+        STL         market_asset_names_id       // Stack: [MarketAssetNames, AssetNames]
+        POPN        2                           // Stack: []
+
+        // Net Supply Long against Supply Short
+        LDM         _SupplyLong                 // Stack: [SL]
+        LDM         _SupplyShort                // Stack: [SL, SS]
+        LDD         0                           // Stack: [SL, SS, SS]
+        SSB         2                           // Stack: [SL, SS, NS = (SS s- SL)]
+        STR         _SupplyShort                // Stack: [SL, SS]
+        SWAP        1                           // Stack: [SS, SL]
+        SSB         1                           // Stack: [SS, NL = (SL s- SS)]
+        STR         _SupplyLong                 // Stack: [SS]
+        POPN        1                           // Stack: []
+
+        // Update Delta
+        //
+        // (Delta Long - Delta Short) = (Supply Long + Demand Short) - (Supply Short + Demand Long)
+        //
+
+        // Supply Long + Demand Short
+        LDR         _SupplyLong                 // Stack: [SupplyLong]
+        LDR         _DemandShort                // Stack: [SupplyLong, DemandShort]
+        ADD         1                           // Stack: [SupplyLong, DeltaLong]
+        SWAP        1
+        POPN        1                           // Stack: [DeltaLong]
+
+        // Supply Short + Demand Long
+        LDR         _SupplyShort                // Stack: [DeltaLong, SupplyShort]
+        LDR         _DemandLong                 // Stack: [DeltaLong, SupplyShort, DemandLong]
+        ADD         1                           // Stack: [DeltaLong, SupplyShort, DeltaShort]
+        SWAP        1
+        POPN        1                           // Stack: [DeltaLong, DeltaShort]
+
+        // Delta Long - Delta Short
+        LDD         0                           // Stack: [DeltaLong, DeltaShort, DeltaShort]
+        SSB         2                           // Stack: [DeltaLong, DeltaShort, RS = (DeltaShort s- DeltaLong)]
+        STR         _DeltaShort                 // Stack: [DeltaLong, DeltaShort]
+        SWAP        1                           // Stack: [DeltaShort, DeltaLong]
+        SSB         1                           // Stack: [DeltaShort, RL = (DeltaLong s- DeltaShort)]
+        STR         _DeltaLong                  // Stack: [DeltaShort]
+        POPN        1                           // Stack: []
+
+        // Store Supply
+        LDM         _SupplyLong
+        LDM         _SupplyShort
+        STV         supply_short_id
+        STV         supply_long_id
+
+        // Store Demand
+        LDM         _DemandLong
+        LDM         _DemandShort
+        STV         demand_short_id
+        STV         demand_long_id
+
+        // Store Delta
+        LDM         _DeltaLong
+        LDM         _DeltaShort
+        STV         delta_short_id
+        STV         delta_long_id
     }
-}
\ No newline at end of file
+}
@@ -54,7 +54,7 @@ fn process_literal_expr(expr: Expr) -> proc_macro2::TokenStream {
         e => panic!("amount! only accepts a literal. Instead got: {:?}", e),
     };
 
-    output.into()
+    output
 }
 
 #[proc_macro]
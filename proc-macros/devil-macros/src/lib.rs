@@ -14,9 +14,9 @@ enum ArgType {
     RegisterId, // <reg>
     Amount,     // <immediate (scalar)> for IMMS/VPUSH
     StackPos,   // <pos>, <pos_A>, <pos_B>
-    StorageId,  // <label_id>, <vector_id>, <scalar_id>, <prg_id>
+    StorageId,  // <label_id>, <vector_id>, <matrix_id>, <scalar_id>, <prg_id>
     Label,      // <immediate (label)>
-    Size,       // <count>, <N>, <M>, <R>
+    Size,       // <count>, <N>, <M>, <R>, <row>, <col>, <from>, <len>
 }
 
 // --- 2. Static Argument Type Map (Grouped by vis.rs Layout) ---
@@ -27,17 +27,21 @@ lazy_static::lazy_static! {
         use ArgType::*;
         let mut m = HashMap::new();
         
-        // 1. Data Loading & Stack Access (10-14)
+        // 1. Data Loading & Stack Access (10-17)
         m.insert("LDL", vec![StorageId]);  
         m.insert("LDV", vec![StorageId]);  
         m.insert("LDD", vec![StackPos]);   
         m.insert("LDR", vec![RegisterId]); 
         m.insert("LDM", vec![RegisterId]); 
+        m.insert("LDLI", vec![]);
+        m.insert("LDVI", vec![]);
 
-        // 2. Data Storage & Register Access (20-23)
+        // 2. Data Storage & Register Access (20-25)
         m.insert("STL", vec![StorageId]);  
         m.insert("STV", vec![StorageId]);  
         m.insert("STR", vec![RegisterId]);
+        m.insert("STLI", vec![]);
+        m.insert("STVI", vec![]);
 
         // 3. Data Structure Manipulation (30-35)
         m.insert("PKV", vec![Size]);       // <count>
@@ -54,23 +58,33 @@ lazy_static::lazy_static! {
         m.insert("JUPD", vec![StackPos, StackPos, StackPos]);
         m.insert("JADD", vec![StackPos, StackPos, StackPos]); 
         m.insert("JFLT", vec![StackPos, StackPos]);
+        m.insert("JUNION", vec![StackPos, StackPos, StackPos]);
         
-        // 5. Arithmetic & Core Math (50-55)
+        // 5. Arithmetic & Core Math (50-58)
         m.insert("ADD", vec![StackPos]);   
         m.insert("SUB", vec![StackPos]);
         m.insert("SSB", vec![StackPos]);
         m.insert("MUL", vec![StackPos]);   
         m.insert("DIV", vec![StackPos]);   
         m.insert("SQRT", vec![]); 
+        m.insert("EXP", vec![]);
+        m.insert("LN", vec![]);
+        m.insert("POW", vec![StackPos]);
 
-        // 6. Logic & Comparison (60-61)
+        // 6. Logic & Comparison (60-65)
         m.insert("MIN", vec![StackPos]);   
         m.insert("MAX", vec![StackPos]);
+        m.insert("GT", vec![StackPos]);
+        m.insert("LT", vec![StackPos]);
+        m.insert("EQ", vec![StackPos]);
+        m.insert("SELECT", vec![StackPos, StackPos]); // <pos_A> <pos_B>
 
-        // 7. Vector Aggregation (70-72)
+        // 7. Vector Aggregation (70-74)
         m.insert("VSUM", vec![]); 
         m.insert("VMIN", vec![]); 
         m.insert("VMAX", vec![]); 
+        m.insert("CUMSUM", vec![]);
+        m.insert("VPROD", vec![]);
 
         // 8. Immediate Values & Vector Creation (80-83)
         m.insert("IMMS", vec![Amount]);    // <immediate (scalar)>
@@ -83,6 +97,35 @@ lazy_static::lazy_static! {
         m.insert("SWAP", vec![StackPos]);  // <pos>
         m.insert("B", vec![StorageId, Size, Size, Size]); // <prg_id> <N> <M> <R>
         m.insert("FOLD", vec![StorageId, Size, Size, Size]); // <prg_id> <N> <M> <R>
+
+        // 10. Matrix Operations (100-107)
+        m.insert("PKM", vec![Size]);       // <count>
+        m.insert("UNPKM", vec![]);
+        m.insert("MATVEC", vec![StackPos]);
+        m.insert("DOT", vec![StackPos]);
+        m.insert("MROW", vec![Size]);      // <row>
+        m.insert("MCOL", vec![Size]);      // <col>
+        m.insert("LDMX", vec![StorageId]);
+        m.insert("STMX", vec![StorageId]);
+
+        // 11. Slicing & Reordering (110-113)
+        m.insert("SLICE", vec![Size, Size]); // <from> <len>
+        m.insert("GATHER", vec![StackPos]);
+        m.insert("SCATTER", vec![StackPos, StackPos]); // <pos_A> <pos_I>
+        m.insert("CONCAT", vec![StackPos]);
+
+        // 12. Labels Set Algebra (120-124)
+        m.insert("LINTER", vec![StackPos]);
+        m.insert("LDIFF", vec![StackPos]);
+        m.insert("LFIND", vec![StackPos]);
+        m.insert("LSORT", vec![StackPos]);
+        m.insert("LCHECK", vec![]);
+
+        // 13. Asset Labels (130-133)
+        m.insert("LSIDE", vec![]);
+        m.insert("LASSET", vec![]);
+        m.insert("LMAKE", vec![Label]);    // <side>
+        m.insert("LFSIDE", vec![StackPos, Label]); // <pos> <side>
         
         m
     };